@@ -14,6 +14,8 @@ pub struct ReleaseRef {
     /// it needs.
     pub id: i64,
     pub draft: bool,
+    /// Marked as a prerelease, so it must never be made the latest release.
+    pub prerelease: bool,
     /// GitHub has frozen this release's assets. Only ever true for a
     /// published release; a draft is by definition still open.
     pub immutable: bool,
@@ -153,6 +155,7 @@ impl ReleaseAssetClient {
         Ok(Some(ReleaseRef {
             id,
             draft: chosen.is_draft,
+            prerelease: chosen.is_prerelease,
             immutable: chosen.immutable,
         }))
     }
@@ -170,6 +173,7 @@ impl ReleaseAssetClient {
                   databaseId
                   tagName
                   isDraft
+                  isPrerelease
                   immutable
                 }
                 releases(first: 100, orderBy: {field: CREATED_AT, direction: DESC}) {
@@ -177,6 +181,7 @@ impl ReleaseAssetClient {
                     databaseId
                     tagName
                     isDraft
+                    isPrerelease
                     immutable
                   }
                 }
@@ -312,6 +317,8 @@ struct ReleaseNode {
     tag_name: String,
     #[serde(rename = "isDraft")]
     is_draft: bool,
+    #[serde(rename = "isPrerelease", default)]
+    is_prerelease: bool,
     immutable: bool,
 }
 
@@ -783,4 +790,22 @@ mod tests {
         assert_eq!(candidates.len(), 1);
         assert!(candidates[0].is_draft);
     }
+
+    #[test]
+    fn deserialises_the_prerelease_flag() {
+        let response = r#"{
+            "repository": {
+                "release": {"databaseId": 7, "tagName": "v1.2.0-rc.1", "isDraft": false, "isPrerelease": true, "immutable": false},
+                "releases": {"nodes": []}
+            }
+        }"#;
+        let data: GetReleases = serde_json::from_str(response).unwrap();
+        assert!(data.repository.release.unwrap().is_prerelease);
+
+        let data: GetReleases = serde_json::from_str(RESPONSE).unwrap();
+        assert!(
+            !data.repository.releases.nodes[0].is_prerelease,
+            "a response without the field is not a prerelease"
+        );
+    }
 }
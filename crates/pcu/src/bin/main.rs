@@ -37,6 +37,7 @@ async fn main() -> Result<()> {
         Commands::Trigger(trigger_args) => trigger_args.run().await,
         Commands::CreateIssue(create_issue_args) => create_issue_args.run().await,
         Commands::CommentPr(comment_pr_args) => comment_pr_args.run().await,
+        Commands::Prlog(prlog_args) => prlog_args.run().await,
     };
 
     match res {
@@ -76,6 +77,19 @@ async fn main() -> Result<()> {
                 CIExit::WebhookTriggered(url) => log::info!("Webhook triggered: {url}"),
                 CIExit::IssueCreated(url) => log::info!("Issue created: {url}"),
                 CIExit::PrCommentCreated(url) => log::info!("PR comment created: {url}"),
+                CIExit::PrlogLintPassed => log::info!("✓ PRLOG lint passed"),
+                CIExit::NextVersion(version) => log::info!("Next version: {version}"),
+                CIExit::ReleaseStatus => log::info!("Release status reported"),
+                CIExit::ReleaseNotes => log::info!("Release notes rendered"),
+                CIExit::SbomGenerated(name) => log::info!("SBOM {name} generated"),
+                CIExit::ReleaseAborted(tag) => log::info!("Aborted release {tag}"),
+                CIExit::BinstallCheckPassed(tag) => {
+                    log::info!("✓ cargo-binstall metadata matches the assets of {tag}")
+                }
+                #[cfg(feature = "attest")]
+                CIExit::AttestationVerified(artifact) => {
+                    log::info!("✓ Attestation of {artifact} verified")
+                }
             };
             Ok(())
         }
@@ -7,6 +7,7 @@ mod create_issue;
 mod label;
 #[cfg(feature = "linkedin")]
 mod linkedin;
+mod prlog;
 mod pull_request;
 mod push;
 mod release;
@@ -27,6 +28,7 @@ use create_issue::CreateIssue;
 use label::Label;
 #[cfg(feature = "linkedin")]
 use linkedin::Linkedin;
+use prlog::Prlog;
 use pull_request::Pr;
 use push::Push;
 use release::Release;
@@ -69,6 +71,15 @@ pub enum CIExit {
     WebhookTriggered(String),
     IssueCreated(String),
     PrCommentCreated(String),
+    PrlogLintPassed,
+    NextVersion(String),
+    ReleaseStatus,
+    ReleaseNotes,
+    SbomGenerated(String),
+    ReleaseAborted(String),
+    BinstallCheckPassed(String),
+    #[cfg(feature = "attest")]
+    AttestationVerified(String),
 }
 
 #[derive(Parser, Debug)]
@@ -120,6 +131,8 @@ the lowest number submitted by the `renovate` or `app/renovate` user")]
     CreateIssue(CreateIssue),
     /// Post a markdown comment on the current pull request
     CommentPr(CommentPr),
+    /// Inspect and maintain the prlog
+    Prlog(Prlog),
 }
 
 impl Display for Commands {
@@ -139,6 +152,7 @@ impl Display for Commands {
             Commands::Trigger(_) => write!(f, "trigger"),
             Commands::CreateIssue(_) => write!(f, "create-issue"),
             Commands::CommentPr(_) => write!(f, "comment-pr"),
+            Commands::Prlog(_) => write!(f, "prlog"),
         }
     }
 }
@@ -207,8 +221,15 @@ impl Commands {
             Commands::Trigger(_) => settings.set_override("command", "trigger")?,
             Commands::CreateIssue(_) => settings.set_override("command", "create-issue")?,
             Commands::CommentPr(_) => settings.set_override("command", "comment-pr")?,
+            Commands::Prlog(_) => settings.set_override("command", "prlog")?,
         };
 
+        if let Commands::Pr(pr) = self {
+            if pr.credit_authors {
+                settings = settings.set_override("credit_authors", true)?;
+            }
+        }
+
         #[cfg(feature = "bsky")]
         if let Commands::Bsky(bsky) = self {
             if bsky.owner.is_some() {
@@ -414,6 +435,9 @@ mod tests {
                 allow_no_pull_request: true,
                 skip_ci,
                 no_skip_ci: false,
+                credit_authors: false,
+                dry_run: false,
+                comment_diff: false,
             });
             let settings = cmd.get_settings().unwrap();
             assert_eq!(
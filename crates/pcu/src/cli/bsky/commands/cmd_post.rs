@@ -51,7 +51,7 @@ impl CmdPost {
         );
 
         client
-            .commit_changed_files(sign_config, &commit_message, "", None)
+            .commit_changed_files(sign_config, &commit_message, None)
             .await?;
 
         if env::var("CI").is_ok() && !self.release {
@@ -31,14 +31,12 @@ impl Commit {
 
     pub async fn run_commit(&self, sign_config: SignConfig) -> Result<CIExit, Error> {
         let client = Commands::Commit(self.clone()).get_client().await?;
+        let tag = client
+            .tag_templates
+            .version_tag(&self.prefix, self.tag_opt())?;
 
         client
-            .commit_changed_files(
-                sign_config,
-                self.commit_message(),
-                &self.prefix,
-                self.tag_opt(),
-            )
+            .commit_changed_files(sign_config, self.commit_message(), tag.as_deref())
             .await?;
 
         Ok(CIExit::Committed)
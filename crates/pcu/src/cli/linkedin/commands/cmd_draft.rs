@@ -67,13 +67,13 @@ impl CmdDraft {
         let sign_config = SignConfig::default();
         let commit_message = "chore: add drafted linkedin posts to git repo";
         client
-            .commit_changed_files(sign_config, commit_message, "", None)
+            .commit_changed_files(sign_config, commit_message, None)
             .await?;
 
         let ahead = client.branch_status()?.ahead;
         if self.push && ahead > 0 {
             let bot_user_name = env::var("BOT_USER_NAME").unwrap_or_else(|_| "bot".to_string());
-            client.push_commit("v", None, false, &bot_user_name)?;
+            client.push_commit(None, false, &bot_user_name)?;
         }
 
         Ok(resolve_draft_exit(self.push, ahead))
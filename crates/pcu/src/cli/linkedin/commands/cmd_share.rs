@@ -122,4 +122,34 @@ mod tests {
         let url = compute_release_url(&cfg, "v", Some("1.2.3")).unwrap();
         assert!(url.is_some());
     }
+
+    #[test]
+    fn test_build_release_text_reads_prlog_file_from_table() {
+        let d = tempdir().unwrap();
+        let prlog_path = d.path().join("CHANGES.md");
+        let contents = r#"# Changelog
+
+## [1.2.3] - 2025-01-01
+
+### Added
+- Feature from CHANGES.md
+"#;
+        fs::write(&prlog_path, contents).unwrap();
+
+        // `[prlog] file = ...` alongside `[prlog.sections]`, over the
+        // default string key.
+        let toml = format!(
+            "[prlog]\nfile = {:?}\n\n[prlog.sections.types.perf]\nsection = \"Changed\"\n",
+            prlog_path.to_string_lossy()
+        );
+        let cfg = Config::builder()
+            .set_default("prlog", "PRLOG.md")
+            .unwrap()
+            .add_source(config::File::from_str(&toml, config::FileFormat::Toml))
+            .build()
+            .unwrap();
+
+        let text = build_release_text(&cfg, "v", "1.2.3").unwrap();
+        assert!(text.contains("Feature from CHANGES.md"));
+    }
 }
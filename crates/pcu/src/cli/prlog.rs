@@ -0,0 +1,81 @@
+mod commands;
+
+use std::env;
+
+use clap::Parser;
+use commands::Cmd;
+use config::Config;
+use keep_a_changelog::ChangelogParseOptions;
+
+use super::Commands;
+use crate::{
+    client::{extract_owner_repo_from_git, prlog_file, prlog_parse_options},
+    CIExit, Error,
+};
+
+/// Configuration for the prlog command
+#[derive(Debug, Parser, Clone)]
+pub struct Prlog {
+    /// Command to execute
+    #[command(subcommand)]
+    pub cmd: Cmd,
+}
+
+impl Prlog {
+    pub async fn run(&self) -> Result<CIExit, Error> {
+        let settings = Commands::Prlog(self.clone()).get_settings()?;
+
+        match self.cmd.clone() {
+            Cmd::Lint(lint) => {
+                let (prlog, opts) = prlog_and_options(&settings)?;
+                lint.run(&prlog, opts)
+            }
+            Cmd::NextVersion(next_version) => {
+                let (prlog, opts) = prlog_and_options(&settings)?;
+                next_version.run(&prlog, opts)
+            }
+            Cmd::Backfill(backfill) => {
+                let client = Commands::Prlog(self.clone()).get_client().await?;
+                backfill.run(&client).await
+            }
+        }
+    }
+}
+
+/// The prlog file and the options to parse it with.
+///
+/// Unlike `pcu pr`, lint, `pcu release notes` and similar commands do not need
+/// GitHub: the owner and repo come from the CI environment when it is set and
+/// the `origin` remote otherwise.
+pub(crate) fn prlog_and_options(
+    settings: &Config,
+) -> Result<(String, ChangelogParseOptions), Error> {
+    let prlog = prlog_file(settings)?;
+
+    let from_env = |key: &str| {
+        settings
+            .get::<String>(key)
+            .ok()
+            .and_then(|var| env::var(var).ok())
+            .filter(|value| !value.is_empty())
+    };
+    let owner_repo = match (from_env("username"), from_env("reponame")) {
+        (Some(owner), Some(repo)) => Some((owner, repo)),
+        _ => git2::Repository::open(".")
+            .ok()
+            .and_then(|repo| extract_owner_repo_from_git(&repo)),
+    };
+
+    let opts = match owner_repo {
+        Some((owner, repo)) => prlog_parse_options(settings, &owner, &repo),
+        None => {
+            log::warn!("Repository not found, compare links will not be checked against it");
+            ChangelogParseOptions {
+                url: None,
+                ..prlog_parse_options(settings, "", "")
+            }
+        }
+    };
+
+    Ok((prlog, opts))
+}
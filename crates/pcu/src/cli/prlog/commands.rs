@@ -0,0 +1,30 @@
+mod cmd_backfill;
+mod cmd_lint;
+mod cmd_next_version;
+
+use std::fmt::Display;
+
+use clap::Subcommand;
+use cmd_backfill::CmdBackfill;
+use cmd_lint::CmdLint;
+use cmd_next_version::CmdNextVersion;
+
+#[derive(Debug, Subcommand, Clone)]
+pub enum Cmd {
+    /// Check the structure of the prlog
+    Lint(CmdLint),
+    /// Rebuild prlog entries from the pull requests merged in git history
+    Backfill(CmdBackfill),
+    /// Suggest the next version from the Unreleased section
+    NextVersion(CmdNextVersion),
+}
+
+impl Display for Cmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cmd::Lint(_) => write!(f, "lint"),
+            Cmd::Backfill(_) => write!(f, "backfill"),
+            Cmd::NextVersion(_) => write!(f, "next-version"),
+        }
+    }
+}
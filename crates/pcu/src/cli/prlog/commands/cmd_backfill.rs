@@ -0,0 +1,439 @@
+use std::path;
+
+use chrono::{DateTime, NaiveDate};
+use clap::Parser;
+use git2::{Oid, Repository, Sort};
+use keep_a_changelog::{
+    changelog::ChangelogBuilder, Changelog, ChangelogParseOptions, Release, Version,
+};
+use url::Url;
+
+use crate::{
+    client::graphql::get_pull_request_by_past_commit,
+    pr_title::references_pr,
+    section_map::section_heading,
+    utilities::{ChangelogLinks, ReleaseEntries},
+    CIExit, Client, Error, PrTitle,
+};
+
+/// Rebuild prlog entries from the pull requests merged in the git history,
+/// filing each under the release whose tag first contains it. Pull requests
+/// already in the prlog are left alone, so it is safe to run again to fill
+/// gaps.
+#[derive(Debug, Parser, Clone)]
+pub struct CmdBackfill {
+    /// Start of the history to walk (exclusive). Without it the walk goes
+    /// back past the first tag, so the first release is filled too.
+    #[arg(long)]
+    pub from: Option<String>,
+    /// End of the history to walk
+    #[arg(long, default_value = "HEAD")]
+    pub to: String,
+}
+
+impl CmdBackfill {
+    pub async fn run(&self, client: &Client) -> Result<CIExit, Error> {
+        let opts = client.prlog_parse_options.clone();
+        let tag_prefix = opts.tag_prefix.clone().unwrap_or_default();
+        let groups = merges_by_release(
+            &client.git_repo,
+            self.from.as_deref(),
+            &self.to,
+            &tag_prefix,
+        )?;
+
+        let prlog = client.prlog_as_str();
+        let mut change_log = load_prlog(prlog, opts)?;
+        let mut added = 0;
+
+        for group in &groups {
+            for commit in &group.merges {
+                let sha = commit.to_string();
+                let (number, title, url, body, author) = match get_pull_request_by_past_commit(
+                    &client.github_graphql,
+                    &client.owner,
+                    &client.repo,
+                    &sha,
+                )
+                .await
+                {
+                    Ok(pull_request) => pull_request,
+                    Err(e) => {
+                        log::debug!("No pull request for merge commit {sha}: {e}");
+                        continue;
+                    }
+                };
+
+                let mut pr_title = PrTitle::parse(&title)?;
+                pr_title.set_pr_id(number);
+                pr_title.set_pr_url(Url::parse(&url)?);
+                pr_title.set_pr_body(Some(body));
+                pr_title.set_sections(client.prlog_sections.clone());
+                if client.credit_authors {
+                    pr_title.set_author(author);
+                }
+                pr_title.calculate_section_and_entry();
+
+                if backfill_entry(&mut change_log, group.release.as_ref(), &pr_title)? {
+                    println!(
+                        "{}: {} {}",
+                        group.name(),
+                        section_heading(&pr_title.section()),
+                        pr_title.entry()
+                    );
+                    added += 1;
+                }
+            }
+        }
+
+        if added == 0 {
+            log::info!("Every merged pull request is already in {prlog}");
+            return Ok(CIExit::UnChanged);
+        }
+
+        change_log
+            .save_to_file(prlog)
+            .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+        log::info!("Backfilled {added} pull request(s) into {prlog}");
+
+        Ok(CIExit::Updated)
+    }
+}
+
+/// The merge commits of one release, oldest first. `release` is `None` for
+/// the commits after the last tag.
+#[derive(Debug)]
+struct ReleaseMerges {
+    release: Option<(Version, NaiveDate)>,
+    merges: Vec<Oid>,
+}
+
+impl ReleaseMerges {
+    fn name(&self) -> String {
+        match &self.release {
+            Some((version, _)) => version.to_string(),
+            None => "Unreleased".to_string(),
+        }
+    }
+}
+
+/// Group the merge commits on the first-parent line of `to` by the version
+/// tag that first contains them, oldest release first.
+fn merges_by_release(
+    repo: &Repository,
+    from: Option<&str>,
+    to: &str,
+    tag_prefix: &str,
+) -> Result<Vec<ReleaseMerges>, Error> {
+    let to = repo.revparse_single(to)?.peel_to_commit()?.id();
+    let from = match from {
+        Some(from) => Some(repo.revparse_single(from)?.peel_to_commit()?.id()),
+        None => None,
+    };
+
+    let mut groups = Vec::new();
+    let mut previous = None;
+
+    for (version, tag) in version_tags(repo, tag_prefix, to)? {
+        let seconds = repo.find_commit(tag)?.time().seconds();
+        let date = DateTime::from_timestamp(seconds, 0)
+            .ok_or_else(|| Error::GitError(format!("Invalid commit time for tag {version}")))?
+            .date_naive();
+        let hide = previous.iter().chain(from.iter()).copied();
+        groups.push(ReleaseMerges {
+            release: Some((version, date)),
+            merges: merge_commits(repo, tag, hide)?,
+        });
+        previous = Some(tag);
+    }
+
+    let hide = previous.iter().chain(from.iter()).copied();
+    groups.push(ReleaseMerges {
+        release: None,
+        merges: merge_commits(repo, to, hide)?,
+    });
+
+    groups.retain(|group| !group.merges.is_empty());
+    Ok(groups)
+}
+
+/// Tags named `{tag_prefix}{semver}` that `to` contains, lowest version first.
+fn version_tags(
+    repo: &Repository,
+    tag_prefix: &str,
+    to: Oid,
+) -> Result<Vec<(Version, Oid)>, Error> {
+    let mut tags = Vec::new();
+
+    let names = repo.tag_names(None)?;
+    for name in names.iter() {
+        let Some(name) = name? else {
+            continue;
+        };
+        let Some(version) = name
+            .strip_prefix(tag_prefix)
+            .and_then(|version| Version::parse(version).ok())
+        else {
+            log::trace!("Skipping tag `{name}`: not a version tag");
+            continue;
+        };
+        let oid = repo
+            .revparse_single(&format!("refs/tags/{name}"))?
+            .peel_to_commit()?
+            .id();
+        if oid == to || repo.graph_descendant_of(to, oid)? {
+            tags.push((version, oid));
+        }
+    }
+
+    tags.sort();
+    Ok(tags)
+}
+
+fn merge_commits(
+    repo: &Repository,
+    tip: Oid,
+    hide: impl Iterator<Item = Oid>,
+) -> Result<Vec<Oid>, Error> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(tip)?;
+    for oid in hide {
+        revwalk.hide(oid)?;
+    }
+    revwalk.simplify_first_parent()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+
+    let mut merges = Vec::new();
+    for oid in revwalk {
+        let oid = oid?;
+        if repo.find_commit(oid)?.parent_count() > 1 {
+            merges.push(oid);
+        }
+    }
+
+    Ok(merges)
+}
+
+fn load_prlog(prlog: &str, opts: ChangelogParseOptions) -> Result<Changelog, Error> {
+    if path::Path::new(prlog).exists() {
+        return Changelog::parse_from_file(prlog, Some(opts))
+            .map_err(|e| Error::KeepAChangelog(e.to_string()));
+    }
+
+    log::info!("The prlog does not exist! Create a default prlog.");
+    let mut change_log = ChangelogBuilder::default()
+        .url(opts.url)
+        .tag_prefix(opts.tag_prefix)
+        .build()
+        .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+    let unreleased = Release::builder()
+        .build()
+        .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+    change_log.add_release(unreleased);
+
+    Ok(change_log)
+}
+
+/// Add the entry for `pr_title` to `release`, creating the release if the
+/// prlog does not have it yet. Returns false, changing nothing, if any
+/// release already has an entry for the pull request.
+fn backfill_entry(
+    change_log: &mut Changelog,
+    release: Option<&(Version, NaiveDate)>,
+    pr_title: &PrTitle,
+) -> Result<bool, Error> {
+    let Some(id) = pr_title.pr_id else {
+        return Ok(false);
+    };
+
+    let recorded = change_log.releases().iter().any(|release| {
+        release
+            .entries()
+            .iter()
+            .any(|(_, entry)| references_pr(entry, id))
+    });
+    if recorded {
+        log::trace!("Pull request #{id} is already in the prlog");
+        return Ok(false);
+    }
+
+    let existing = match release {
+        Some((version, _)) => change_log
+            .find_release(version.to_string())
+            .map_err(|e| Error::KeepAChangelog(e.to_string()))?
+            .is_some(),
+        None => change_log.get_unreleased().is_some(),
+    };
+    if !existing {
+        let mut builder = Release::builder();
+        if let Some((version, date)) = release {
+            builder.version(version.clone()).date(*date);
+        }
+        let new_release = builder
+            .build()
+            .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+        change_log.add_release(new_release);
+    }
+
+    let target = match release {
+        Some((version, _)) => change_log
+            .find_release_mut(version.to_string())
+            .map_err(|e| Error::KeepAChangelog(e.to_string()))?,
+        None => change_log.get_unreleased_mut(),
+    }
+    .ok_or(Error::NoUnreleasedSection)?;
+
+    let mut entries = target.entries();
+    entries.push((pr_title.section(), pr_title.entry()));
+    target.set_entries(entries);
+
+    let has_body = pr_title
+        .pr_body
+        .as_ref()
+        .is_some_and(|body| !body.is_empty());
+    if let (Some(url), true) = (&pr_title.pr_url, has_body) {
+        let anchor = format!("#{id}");
+        if !change_log.has_link(&anchor) {
+            change_log.add_link(format!("[{anchor}]:"), url.to_string());
+        }
+    }
+    pr_title.add_issue_links(change_log);
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(repo: &Repository, message: &str, parents: &[Oid]) -> Oid {
+        let sig = git2::Signature::now("Test User", "test@example.test").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let parents = parents
+            .iter()
+            .map(|oid| repo.find_commit(*oid).unwrap())
+            .collect::<Vec<_>>();
+        let parents = parents.iter().collect::<Vec<_>>();
+        repo.commit(None, &sig, &sig, message, &tree, &parents)
+            .unwrap()
+    }
+
+    /// Merge a one-commit feature branch into `main`, returning the merge.
+    fn merge_pr(repo: &Repository, main: Oid, number: i64) -> Oid {
+        let feature = commit(repo, &format!("feat: change {number}"), &[main]);
+        commit(
+            repo,
+            &format!("Merge pull request #{number}"),
+            &[main, feature],
+        )
+    }
+
+    #[test]
+    fn test_merges_are_grouped_by_release_tag() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+
+        let root = commit(&repo, "chore: initial commit", &[]);
+        let pr1 = merge_pr(&repo, root, 1);
+        let pr2 = merge_pr(&repo, pr1, 2);
+        let release = repo.find_commit(pr2).unwrap();
+        repo.tag_lightweight("v0.1.0", release.as_object(), false)
+            .unwrap();
+        repo.tag_lightweight("nightly", release.as_object(), false)
+            .unwrap();
+        let pr3 = merge_pr(&repo, pr2, 3);
+        let release = repo.find_commit(pr3).unwrap();
+        repo.tag_lightweight("v0.2.0", release.as_object(), false)
+            .unwrap();
+        let pr4 = merge_pr(&repo, pr3, 4);
+
+        let tip = pr4.to_string();
+        let groups = merges_by_release(&repo, None, &tip, "v").unwrap();
+        let names = groups
+            .iter()
+            .map(|group| (group.name(), group.merges.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("0.1.0".to_string(), vec![pr1, pr2]),
+                ("0.2.0".to_string(), vec![pr3]),
+                ("Unreleased".to_string(), vec![pr4]),
+            ]
+        );
+
+        let from = pr1.to_string();
+        let groups = merges_by_release(&repo, Some(&from), "v0.2.0", "v").unwrap();
+        let names = groups
+            .iter()
+            .map(|group| (group.name(), group.merges.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("0.1.0".to_string(), vec![pr2]),
+                ("0.2.0".to_string(), vec![pr3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_backfill_entry_is_idempotent() {
+        let markdown = "# Changelog\n\n## [Unreleased]\n\n## [0.1.0] - 2024-01-10\n\n### Added\n\n- first(pr [#1])\n\n[#1]: https://github.com/jerus-org/pcu/pull/1\n";
+        let opts = ChangelogParseOptions {
+            url: Some("https://github.com/jerus-org/pcu".to_string()),
+            head: Some("HEAD".to_string()),
+            tag_prefix: Some("v".to_string()),
+        };
+        let mut change_log = Changelog::parse(markdown.to_string(), Some(opts)).unwrap();
+
+        let pr_title = |id: i64, title: &str| {
+            let mut pr_title = PrTitle::parse(title).unwrap();
+            pr_title.set_pr_id(id);
+            pr_title.set_pr_url(
+                Url::parse(&format!("https://github.com/jerus-org/pcu/pull/{id}")).unwrap(),
+            );
+            pr_title.set_pr_body(Some("Details".to_string()));
+            pr_title.calculate_section_and_entry();
+            pr_title
+        };
+        let v010 = (
+            Version::parse("0.1.0").unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(),
+        );
+        let v020 = (
+            Version::parse("0.2.0").unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 20).unwrap(),
+        );
+
+        assert!(
+            !backfill_entry(&mut change_log, Some(&v010), &pr_title(1, "feat: first")).unwrap()
+        );
+        assert!(backfill_entry(&mut change_log, Some(&v010), &pr_title(2, "fix: second")).unwrap());
+        assert!(backfill_entry(&mut change_log, Some(&v020), &pr_title(3, "feat: third")).unwrap());
+        assert!(
+            !backfill_entry(&mut change_log, Some(&v020), &pr_title(3, "feat: third")).unwrap()
+        );
+
+        let v010 = change_log
+            .find_release("0.1.0".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(v010.entries().len(), 2);
+        let v020 = change_log
+            .find_release("0.2.0".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!(*v020.date(), NaiveDate::from_ymd_opt(2024, 2, 20));
+        assert!(v020.entries()[0].1.starts_with("third(pr [#3])"));
+
+        let markdown = change_log.to_string();
+        assert!(markdown.contains("[#2]: https://github.com/jerus-org/pcu/pull/2"));
+        assert!(markdown.contains("[#3]: https://github.com/jerus-org/pcu/pull/3"));
+        assert!(
+            markdown.find("## [0.2.0]").unwrap() < markdown.find("## [0.1.0]").unwrap(),
+            "releases stay newest first"
+        );
+    }
+}
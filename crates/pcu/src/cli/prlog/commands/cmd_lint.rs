@@ -0,0 +1,293 @@
+use std::{collections::BTreeMap, fmt::Display, fs};
+
+use clap::Parser;
+use keep_a_changelog::{Changelog, ChangelogParseOptions, Version};
+use owo_colors::OwoColorize;
+use regex::Regex;
+
+use crate::{utilities::ReleaseEntries, CIExit, Error};
+
+/// Check the prlog for broken links, duplicated or misplaced entries and
+/// release ordering. Exits nonzero when problems are found.
+#[derive(Debug, Parser, Clone)]
+pub struct CmdLint {}
+
+impl CmdLint {
+    pub fn run(&self, prlog: &str, opts: ChangelogParseOptions) -> Result<CIExit, Error> {
+        let markdown = fs::read_to_string(prlog)?;
+        let problems = lint(&markdown, opts)?;
+
+        if problems.is_empty() {
+            println!("{} {prlog}: no problems found", "✓".green());
+            return Ok(CIExit::PrlogLintPassed);
+        }
+
+        for problem in &problems {
+            println!("{} {prlog}: {problem}", "✗".red());
+        }
+        Err(Error::PrlogLintFailed(problems.len()))
+    }
+}
+
+/// A structural problem found in the prlog
+#[derive(Debug, PartialEq, Eq)]
+enum Problem {
+    MissingUnreleased,
+    DanglingLink(i64),
+    UndefinedLink(i64, String),
+    DuplicatePr(i64, String, String),
+    ReleaseOrder(Version, Version),
+    CompareLinkDirection(String, String),
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::MissingUnreleased => write!(f, "no Unreleased section"),
+            Problem::DanglingLink(id) => {
+                write!(f, "link [#{id}] is not referenced by any entry")
+            }
+            Problem::UndefinedLink(id, release) => {
+                write!(f, "entry in {release} references undefined link [#{id}]")
+            }
+            Problem::DuplicatePr(id, first, second) => {
+                write!(f, "PR #{id} has entries in both {first} and {second}")
+            }
+            Problem::ReleaseOrder(first, second) => {
+                write!(
+                    f,
+                    "release {first} is listed above the later release {second}"
+                )
+            }
+            Problem::CompareLinkDirection(anchor, url) => {
+                write!(
+                    f,
+                    "compare link [{anchor}] runs in the wrong direction: {url}"
+                )
+            }
+        }
+    }
+}
+
+fn lint(markdown: &str, opts: ChangelogParseOptions) -> Result<Vec<Problem>, Error> {
+    let prlog = Changelog::parse(markdown.to_string(), Some(opts))
+        .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+
+    let mut problems = Vec::new();
+
+    if prlog.get_unreleased().is_none() {
+        problems.push(Problem::MissingUnreleased);
+    }
+
+    let links = link_definitions(markdown)?;
+    problems.extend(pr_reference_problems(&prlog, &links)?);
+    problems.extend(release_order_problems(markdown)?);
+    problems.extend(compare_link_problems(&links)?);
+
+    Ok(problems)
+}
+
+/// `[anchor]: url` link definitions as written in the file. The parsed
+/// changelog regenerates compare links, so the file text is read instead.
+fn link_definitions(markdown: &str) -> Result<Vec<(String, String)>, Error> {
+    let re = Regex::new(r"(?m)^\[(?P<anchor>[^\]]+)\]:\s*(?P<url>\S+)")?;
+    Ok(re
+        .captures_iter(markdown)
+        .map(|c| (c["anchor"].to_string(), c["url"].to_string()))
+        .collect())
+}
+
+/// Dangling and undefined `[#N]` links, and PRs with more than one entry.
+fn pr_reference_problems(
+    prlog: &Changelog,
+    links: &[(String, String)],
+) -> Result<Vec<Problem>, Error> {
+    let reference_re = Regex::new(r"\[#(?P<id>\d+)\]")?;
+    let own_pr_re = Regex::new(r"\(pr \[?#(?P<id>\d+)\]?")?;
+
+    let defined = links
+        .iter()
+        .filter_map(|(anchor, _)| anchor.strip_prefix('#')?.parse::<i64>().ok())
+        .collect::<Vec<i64>>();
+
+    let mut problems = Vec::new();
+    let mut referenced = Vec::new();
+    let mut first_seen: BTreeMap<i64, String> = BTreeMap::new();
+
+    for release in prlog.releases() {
+        let name = release
+            .version()
+            .as_ref()
+            .map_or_else(|| "Unreleased".to_string(), |v| v.to_string());
+
+        for (_, entry) in release.entries() {
+            for captures in reference_re.captures_iter(&entry) {
+                let Ok(id) = captures["id"].parse::<i64>() else {
+                    continue;
+                };
+                if !defined.contains(&id) {
+                    problems.push(Problem::UndefinedLink(id, name.clone()));
+                }
+                referenced.push(id);
+            }
+
+            if let Some(id) = own_pr_re
+                .captures(&entry)
+                .and_then(|c| c["id"].parse::<i64>().ok())
+            {
+                match first_seen.get(&id) {
+                    Some(first) => {
+                        problems.push(Problem::DuplicatePr(id, first.clone(), name.clone()))
+                    }
+                    None => {
+                        first_seen.insert(id, name.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    for id in defined {
+        if !referenced.contains(&id) {
+            problems.push(Problem::DanglingLink(id));
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Releases must be listed newest first.
+fn release_order_problems(markdown: &str) -> Result<Vec<Problem>, Error> {
+    let re = Regex::new(r"(?m)^## \[(?P<version>[^\]]+)\]")?;
+    let versions = re
+        .captures_iter(markdown)
+        .filter_map(|c| Version::parse(&c["version"]).ok())
+        .collect::<Vec<Version>>();
+
+    Ok(versions
+        .windows(2)
+        .filter(|pair| pair[0] <= pair[1])
+        .map(|pair| Problem::ReleaseOrder(pair[0].clone(), pair[1].clone()))
+        .collect())
+}
+
+/// Compare links must run from the earlier release to the later one.
+fn compare_link_problems(links: &[(String, String)]) -> Result<Vec<Problem>, Error> {
+    let version_re = Regex::new(r"\d+\.\d+\.\d+(?:-[0-9A-Za-z.-]+)?")?;
+    let version_in = |tag: &str| {
+        version_re
+            .find(tag)
+            .and_then(|m| Version::parse(m.as_str()).ok())
+    };
+
+    let mut problems = Vec::new();
+    for (anchor, url) in links {
+        let Some((_, range)) = url.split_once("/compare/") else {
+            continue;
+        };
+        let Some((from, to)) = range.split_once("...").or_else(|| range.split_once("..")) else {
+            continue;
+        };
+
+        let (Some(from), Some(to)) = (version_in(from), version_in(to)) else {
+            continue;
+        };
+        if from >= to {
+            problems.push(Problem::CompareLinkDirection(anchor.clone(), url.clone()));
+        }
+    }
+
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/)
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+";
+
+    fn lint_str(body: &str) -> Vec<Problem> {
+        lint(
+            &format!("{HEADER}\n{body}"),
+            ChangelogParseOptions::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_clean_prlog_has_no_problems() {
+        let problems = lint_str(
+            "## [Unreleased]
+
+### Added
+
+- new thing(pr [#3])
+
+## [0.2.0] - 2024-02-01
+
+### Fixed
+
+- a fix(pr [#2])
+
+## [0.1.0] - 2024-01-01
+
+### Added
+
+- first(pr [#1])
+
+[#1]: https://github.com/jerus-org/pcu/pull/1
+[#2]: https://github.com/jerus-org/pcu/pull/2
+[#3]: https://github.com/jerus-org/pcu/pull/3
+[Unreleased]: https://github.com/jerus-org/pcu/compare/v0.2.0...HEAD
+[0.2.0]: https://github.com/jerus-org/pcu/compare/v0.1.0...v0.2.0
+[0.1.0]: https://github.com/jerus-org/pcu/releases/tag/v0.1.0
+",
+        );
+        assert_eq!(problems, vec![]);
+    }
+
+    #[test]
+    fn test_problems_are_reported() {
+        let problems = lint_str(
+            "## [0.1.0] - 2024-01-01
+
+### Added
+
+- first(pr [#1])
+- first again(pr [#1])
+- unlinked(pr [#4])
+
+## [0.2.0] - 2024-02-01
+
+### Fixed
+
+- a fix(pr #2)
+
+[#1]: https://github.com/jerus-org/pcu/pull/1
+[#9]: https://github.com/jerus-org/pcu/pull/9
+[0.2.0]: https://github.com/jerus-org/pcu/compare/v0.2.0...v0.1.0
+",
+        );
+
+        assert_eq!(
+            problems,
+            vec![
+                Problem::MissingUnreleased,
+                Problem::DuplicatePr(1, "0.1.0".to_string(), "0.1.0".to_string()),
+                Problem::UndefinedLink(4, "0.1.0".to_string()),
+                Problem::DanglingLink(9),
+                Problem::ReleaseOrder(Version::new(0, 1, 0), Version::new(0, 2, 0)),
+                Problem::CompareLinkDirection(
+                    "0.2.0".to_string(),
+                    "https://github.com/jerus-org/pcu/compare/v0.2.0...v0.1.0".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use std::fmt::Display;
+
+use clap::Parser;
+use keep_a_changelog::{ChangeKind, Changelog, ChangelogParseOptions, Version};
+
+use crate::{cli::release::write_to_bash_env, utilities::ReleaseEntries, CIExit, Error};
+
+/// Version a release from the Unreleased section would get when no earlier
+/// release is recorded in the prlog.
+const FIRST_VERSION: Version = Version::new(0, 1, 0);
+
+/// Suggest the next version from the entries in the Unreleased section of the
+/// prlog. Prints `none` when there is nothing to release.
+#[derive(Debug, Parser, Clone)]
+pub struct CmdNextVersion {
+    /// Also write `NEXT_VERSION` to `$BASH_ENV` for later CI steps
+    #[arg(long)]
+    pub bash_env: bool,
+}
+
+impl CmdNextVersion {
+    pub fn run(&self, prlog: &str, opts: ChangelogParseOptions) -> Result<CIExit, Error> {
+        let change_log = Changelog::parse_from_file(prlog, Some(opts))
+            .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+
+        let next_version = match next_version(&change_log) {
+            Some(version) => version.to_string(),
+            None => "none".to_string(),
+        };
+        println!("{next_version}");
+
+        if self.bash_env {
+            write_to_bash_env("NEXT_VERSION", &next_version)?;
+        }
+
+        Ok(CIExit::NextVersion(next_version))
+    }
+}
+
+/// The part of the version a set of changes calls for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// The bump called for by a single prlog entry: major for a breaking
+    /// change, minor for anything that adds, deprecates or removes, otherwise
+    /// patch.
+    fn for_entry(kind: &ChangeKind, entry: &str) -> Self {
+        if entry.contains("BREAKING: ") {
+            return Bump::Major;
+        }
+
+        match kind {
+            ChangeKind::Added | ChangeKind::Deprecated | ChangeKind::Removed => Bump::Minor,
+            ChangeKind::Changed | ChangeKind::Fixed | ChangeKind::Security => Bump::Patch,
+        }
+    }
+
+    /// Apply the bump to `version`. Before 1.0.0 the minor number is the one
+    /// that signals breaking changes, so everything shifts down a place.
+    ///
+    /// A prerelease already stands for its release: `1.3.0-rc.1` becomes
+    /// `1.3.0` for anything up to a minor bump, and only a breaking change
+    /// moves past it.
+    fn apply(self, version: &Version) -> Version {
+        let bump = match (version.major, self) {
+            (0, Bump::Major) => Bump::Minor,
+            (0, _) => Bump::Patch,
+            (_, bump) => bump,
+        };
+
+        let release = Version::new(version.major, version.minor, version.patch);
+        if !version.pre.is_empty() {
+            let covered = match bump {
+                Bump::Major => release.minor == 0 && release.patch == 0,
+                Bump::Minor => release.patch == 0,
+                Bump::Patch => true,
+            };
+            if covered {
+                return release;
+            }
+        }
+
+        match bump {
+            Bump::Major => Version::new(version.major + 1, 0, 0),
+            Bump::Minor => Version::new(version.major, version.minor + 1, 0),
+            Bump::Patch => Version::new(version.major, version.minor, version.patch + 1),
+        }
+    }
+}
+
+impl Display for Bump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bump::Patch => write!(f, "patch"),
+            Bump::Minor => write!(f, "minor"),
+            Bump::Major => write!(f, "major"),
+        }
+    }
+}
+
+/// The version for releasing the Unreleased section, or `None` if it has no
+/// entries.
+fn next_version(change_log: &Changelog) -> Option<Version> {
+    let unreleased = change_log.get_unreleased()?;
+    let bump = unreleased
+        .entries()
+        .iter()
+        .map(|(kind, entry)| Bump::for_entry(kind, entry))
+        .max()?;
+
+    let Some(current) = change_log
+        .releases()
+        .iter()
+        .filter_map(|release| release.version().as_ref())
+        .max()
+    else {
+        log::info!("No release in the prlog yet, starting at {FIRST_VERSION}");
+        return Some(FIRST_VERSION);
+    };
+
+    let next = bump.apply(current);
+    log::info!("Unreleased changes call for a {bump} bump: {current} -> {next}");
+    Some(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::fix_only("### Fixed\n\n- broken link(pr [#7])\n", "1.2.3", Some("1.2.4"))]
+    #[case::feature(
+        "### Added\n\n- new flag(pr [#7])\n### Fixed\n\n- typo(pr [#8])\n",
+        "1.2.3",
+        Some("1.3.0")
+    )]
+    #[case::breaking(
+        "### Changed\n\n- BREAKING: ci-drop flag(pr [#7])\n",
+        "1.2.3",
+        Some("2.0.0")
+    )]
+    #[case::emoji_breaking(
+        "### Added\n\n- ✨ BREAKING: new api(pr [#7])\n",
+        "1.2.3",
+        Some("2.0.0")
+    )]
+    #[case::pre_1_breaking(
+        "### Changed\n\n- BREAKING: ci-drop flag(pr [#7])\n",
+        "0.6.33",
+        Some("0.7.0")
+    )]
+    #[case::pre_1_feature("### Added\n\n- new flag(pr [#7])\n", "0.6.33", Some("0.6.34"))]
+    #[case::pre_fix_only("### Fixed\n\n- broken link(pr [#7])\n", "1.3.0-rc.1", Some("1.3.0"))]
+    #[case::pre_feature("### Added\n\n- new flag(pr [#7])\n", "1.3.0-rc.1", Some("1.3.0"))]
+    #[case::pre_patch_feature("### Added\n\n- new flag(pr [#7])\n", "1.3.1-rc.1", Some("1.4.0"))]
+    #[case::pre_breaking(
+        "### Changed\n\n- BREAKING: ci-drop flag(pr [#7])\n",
+        "1.3.0-rc.1",
+        Some("2.0.0")
+    )]
+    #[case::pre_major_breaking(
+        "### Changed\n\n- BREAKING: ci-drop flag(pr [#7])\n",
+        "2.0.0-rc.1",
+        Some("2.0.0")
+    )]
+    #[case::empty("", "1.2.3", None)]
+    fn test_next_version(
+        #[case] unreleased: &str,
+        #[case] current: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let markdown = format!(
+            "# Changelog\n\n## [Unreleased]\n\n{unreleased}\n## [{current}] - 2024-01-10\n\n### Added\n\n- first(pr [#1])\n"
+        );
+        let change_log = Changelog::parse(markdown, None).unwrap();
+
+        let next = next_version(&change_log).map(|version| version.to_string());
+        assert_eq!(next.as_deref(), expected);
+    }
+
+    #[test]
+    fn test_first_release() {
+        let markdown = "# Changelog\n\n## [Unreleased]\n\n### Fixed\n\n- typo(pr [#1])\n";
+        let change_log = Changelog::parse(markdown.to_string(), None).unwrap();
+
+        assert_eq!(next_version(&change_log), Some(FIRST_VERSION));
+    }
+}
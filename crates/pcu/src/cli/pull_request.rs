@@ -1,10 +1,11 @@
-use std::env;
+use std::{env, ffi::OsString, fs, path::Path};
 
 use clap::Parser;
 use keep_a_changelog::ChangeKind;
 use owo_colors::{OwoColorize, Style};
+use similar::TextDiff;
 
-use super::CIExit;
+use super::{comment_pr::CommentPr, CIExit};
 use crate::{
     cli::{Commands, GitOps},
     Client, Error, SignConfig, UpdateFromPr,
@@ -44,6 +45,17 @@ pub struct Pr {
     /// "skip the skip" is stated explicitly. If both are given, the last wins.
     #[clap(long = "no-skip-ci", action = clap::ArgAction::SetTrue, overrides_with = "skip_ci")]
     pub no_skip_ci: bool,
+    /// Credit the pull request author with `by @login` at the end of the
+    /// entry. Also enabled by `credit_authors = true` in pcu.toml.
+    #[clap(long, default_value_t = false)]
+    pub credit_authors: bool,
+    /// Print the change the entry would make to the prlog as a unified diff,
+    /// without writing the prlog or touching git
+    #[clap(long, default_value_t = false)]
+    pub dry_run: bool,
+    /// With --dry-run, also post the diff as a comment on the pull request
+    #[clap(long, default_value_t = false, requires = "dry_run")]
+    pub comment_diff: bool,
 }
 
 impl Pr {
@@ -78,6 +90,10 @@ impl Pr {
         client.create_entry()?;
         log::debug!("Proposed entry: {:?}", client.entry());
 
+        if self.dry_run {
+            return self.preview_prlog(&mut client).await;
+        }
+
         if !self.update_and_log_prlog(&mut client)? {
             return Ok(CIExit::UnChanged);
         }
@@ -170,6 +186,52 @@ impl Pr {
         Ok(true)
     }
 
+    /// Apply the entry to a scratch copy of the prlog and print the change as
+    /// a unified diff. The prlog itself and the git repository are untouched.
+    async fn preview_prlog(&self, client: &mut Client) -> Result<CIExit, Error> {
+        let prlog = client.prlog_as_str().to_string();
+        let original = fs::read_to_string(&prlog).unwrap_or_default();
+
+        let scratch = tempfile::tempdir()?;
+        let file_name = Path::new(&prlog)
+            .file_name()
+            .map(OsString::from)
+            .unwrap_or_else(|| OsString::from("PRLOG.md"));
+        let copy = scratch.path().join(file_name);
+        if Path::new(&prlog).exists() {
+            fs::copy(&prlog, &copy)?;
+        }
+
+        client.set_prlog(&copy.to_string_lossy());
+        let update = client.update_prlog();
+        client.set_prlog(&prlog);
+        update?;
+
+        let updated = fs::read_to_string(&copy).unwrap_or_default();
+        let diff = prlog_diff(&prlog, &original, &updated);
+        if diff.is_empty() {
+            println!("No change to {prlog}");
+            return Ok(CIExit::UnChanged);
+        }
+        print!("{diff}");
+
+        if self.comment_diff {
+            let comment = CommentPr {
+                body: format!(
+                    "`pcu pr` will make this change to `{prlog}`:\n\n```diff\n{diff}```\n"
+                ),
+                pr_number: Some(client.pr_number() as u64),
+                owner: Some(client.owner().to_string()),
+                repo: Some(client.repo().to_string()),
+                github_token: Some(client.github_token.clone()).filter(|t| !t.is_empty()),
+                dry_run: false,
+            };
+            return comment.run().await;
+        }
+
+        Ok(CIExit::UnChanged)
+    }
+
     fn section_to_string(&self, section: ChangeKind) -> &'static str {
         match section {
             ChangeKind::Added => "Added",
@@ -193,7 +255,7 @@ impl Pr {
         let commit_message =
             super::with_skip_ci(&client.commit_message, self.skip_ci, on_default_branch);
         client
-            .commit_changed_files(sign_config, &commit_message, &self.prefix, None)
+            .commit_changed_files(sign_config, &commit_message, None)
             .await?;
 
         if self.push {
@@ -214,6 +276,9 @@ impl Pr {
             allow_no_pull_request: true,
             skip_ci,
             no_skip_ci: false,
+            credit_authors: false,
+            dry_run: false,
+            comment_diff: false,
         }
     }
 
@@ -241,7 +306,7 @@ impl Pr {
             })
             .unwrap_or_else(|| "<unknown>".to_string());
 
-        let res = client.push_commit(&self.prefix, None, false, &bot_user_name);
+        let res = client.push_commit(None, false, &bot_user_name);
 
         // Propagate hard errors immediately (anything other than non-fast-forward,
         // which may be a race condition that fetch-and-check can diagnose).
@@ -295,6 +360,18 @@ impl Pr {
     }
 }
 
+/// Unified diff of the prlog from `original` to `updated`, empty if they match.
+fn prlog_diff(prlog: &str, original: &str, updated: &str) -> String {
+    if original == updated {
+        return String::new();
+    }
+
+    TextDiff::from_lines(original, updated)
+        .unified_diff()
+        .header(&format!("a/{prlog}"), &format!("b/{prlog}"))
+        .to_string()
+}
+
 #[cfg(test)]
 mod commit_message_tests {
     use super::*;
@@ -382,3 +459,70 @@ mod commit_message_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod dry_run_tests {
+    use url::Url;
+
+    use super::*;
+    use crate::PrTitle;
+
+    const PRLOG: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/)
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+## [Unreleased]
+
+### Added
+
+- first feature(pr [#4])
+
+[#4]: https://github.com/jerus-org/pcu/pull/4
+";
+
+    #[test]
+    fn test_prlog_diff() {
+        assert_eq!(prlog_diff("PRLOG.md", PRLOG, PRLOG), "");
+
+        let updated = PRLOG.replace(
+            "- first feature(pr [#4])",
+            "- first feature(pr [#4])\n- second",
+        );
+        let diff = prlog_diff("PRLOG.md", PRLOG, &updated);
+        assert!(diff.starts_with("--- a/PRLOG.md\n+++ b/PRLOG.md\n@@ "));
+        assert!(diff.contains("\n+- second\n"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_leaves_prlog_and_git_untouched() {
+        let tmp = tempfile::tempdir().unwrap();
+        git2::Repository::init(tmp.path()).unwrap();
+        let prlog = tmp.path().join("PRLOG.md");
+        fs::write(&prlog, PRLOG).unwrap();
+
+        let mut client = Client::new_local_at(tmp.path()).unwrap();
+        client.set_prlog(&prlog.to_string_lossy());
+        let mut pr_title = PrTitle::parse("feat: add preview").unwrap();
+        pr_title.set_pr_id(5);
+        pr_title.set_pr_url(Url::parse("https://github.com/jerus-org/pcu/pull/5").unwrap());
+        pr_title.set_pr_body(Some("Details".to_string()));
+        client.prlog_update = Some(pr_title);
+
+        let mut pr = Pr::for_test(false);
+        pr.dry_run = true;
+        let exit = pr.preview_prlog(&mut client).await.unwrap();
+
+        assert!(matches!(exit, CIExit::UnChanged));
+        assert_eq!(fs::read_to_string(&prlog).unwrap(), PRLOG);
+        assert_eq!(client.prlog_as_str(), prlog.to_string_lossy());
+        let statuses = git2::Repository::open(tmp.path())
+            .unwrap()
+            .statuses(None)
+            .unwrap()
+            .len();
+        assert_eq!(statuses, 1, "only the untracked PRLOG.md");
+    }
+}
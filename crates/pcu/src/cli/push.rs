@@ -39,6 +39,9 @@ impl Push {
 
     pub async fn run_push(&self) -> Result<CIExit, Error> {
         let client = Commands::Push(self.clone()).get_client().await?;
+        let tag = client
+            .tag_templates
+            .version_tag(&self.prefix, self.tag_opt())?;
 
         let branch_status = client.branch_status()?;
         log::debug!("Branch status report: {branch_status}");
@@ -48,16 +51,12 @@ impl Push {
         };
 
         log::info!("Push the commit");
-        log::trace!(
-            "tag_opt: {:?} and no_push: {:?}",
-            self.tag_opt(),
-            self.no_push
-        );
+        log::trace!("tag: {tag:?} and no_push: {:?}", self.no_push);
 
         let bot_user_name = std::env::var("BOT_USER_NAME").unwrap_or_else(|_| "bot".to_string());
         log::debug!("Using bot user name: {bot_user_name}");
 
-        client.push_commit(&self.prefix, self.tag_opt(), self.no_push, &bot_user_name)?;
+        client.push_commit(tag.as_deref(), self.no_push, &bot_user_name)?;
         let hdr_style = Style::new().bold().underline();
         log::debug!("{}", "Check Push".style(hdr_style));
         log::debug!("Branch status: {}", client.branch_status()?);
@@ -11,6 +11,8 @@ async fn share_release_to_linkedin(prefix: &str, version: &str) -> Result<(), Er
         skip_ci: false,
         no_skip_ci: false,
         draft: false,
+        collect_prereleases: false,
+        journal: None,
         mode: Mode::Version(crate::cli::release::mode::Version {
             version: version.to_string(),
         }),
@@ -41,18 +43,38 @@ async fn share_release_to_linkedin(prefix: &str, version: &str) -> Result<(), Er
     Ok(())
 }
 
-use std::{fs, io::Write, path::Path, process::Command};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 use super::{CIExit, Commands};
-use crate::{Client, Error, GitOps, MakeRelease, SignConfig, Workspace};
+use crate::{Client, Error, GitOps, MakeRelease, SignConfig, TagTemplates, Workspace};
+mod abort;
 #[cfg(feature = "attest")]
 mod attest;
+mod check_binstall;
+#[cfg(feature = "attest")]
+mod ci_provider;
+mod journal;
 mod mode;
+mod notes;
+#[cfg(feature = "attest")]
+mod provenance;
+mod required_assets;
+mod sbom;
+mod sign;
+mod upload_asset;
+#[cfg(feature = "attest")]
+mod verify_attestation;
 
 use clap::Parser;
+use journal::{Journal, Step, StepStatus, JOURNAL_ENV};
 use mode::Mode;
-use octocrate::{APIConfig, PersonalAccessToken};
 use owo_colors::{OwoColorize, Style};
+use required_assets::RequiredAssets;
 
 /// Poll `probe` up to `max_attempts` times, sleeping `retry_delay` between
 /// attempts, returning `true` as soon as it yields `true`.
@@ -218,6 +240,28 @@ fn publish_decision(found: Option<(i64, bool)>) -> PublishAction {
     }
 }
 
+/// Refuse to publish a release that lacks required assets, unless `force`d.
+///
+/// Publication freezes the assets of an immutable release, so a release
+/// published without them can never be completed.
+fn check_required_assets(tag: &str, missing: &[String], force: bool) -> Result<(), Error> {
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let list = missing
+        .iter()
+        .map(|pattern| format!("\n  - {pattern}"))
+        .collect::<String>();
+
+    if force {
+        log::warn!("Publishing {tag} without required assets (--force):{list}");
+        return Ok(());
+    }
+
+    Err(Error::MissingReleaseAssets(tag.to_string(), list))
+}
+
 /// Resolve a version from an optional CLI argument, falling back to $SEMVER or $NEXT_VERSION.
 /// Returns "none" when no version is available.
 fn resolve_version(version_opt: &Option<String>) -> String {
@@ -292,7 +336,7 @@ fn inject_pubkey_value(
 
 /// Append `export KEY=VALUE\n` to the file named by $BASH_ENV.
 /// Logs a warning if $BASH_ENV is unset (e.g. running locally).
-fn write_to_bash_env(key: &str, value: &str) -> Result<(), Error> {
+pub(crate) fn write_to_bash_env(key: &str, value: &str) -> Result<(), Error> {
     let bash_env = std::env::var("BASH_ENV").unwrap_or_default();
     if bash_env.is_empty() {
         log::warn!("$BASH_ENV not set — {key}={value} will not persist to subsequent CI steps");
@@ -307,6 +351,75 @@ fn write_to_bash_env(key: &str, value: &str) -> Result<(), Error> {
     Ok(())
 }
 
+/// The tag of `version` of the crate `package`: from the tag template, or
+/// `<package>-v<version>` without one.
+fn crate_tag(templates: &TagTemplates, package: &str, version: &str) -> Result<String, Error> {
+    templates.tag(Some(package), &format!("{package}-v"), version)
+}
+
+/// Record the state of a release step in the journal at `path`, with the
+/// command line of the running pcu so `resume` can repeat it.
+fn record_step(
+    path: &Path,
+    tag: &str,
+    step: Step,
+    label: Option<String>,
+    status: StepStatus,
+    error: Option<String>,
+) -> Result<(), Error> {
+    let mut journal = Journal::load(path)?;
+    let args = std::env::args().skip(1).collect();
+    journal.record(tag, step, label, args, status, error);
+    journal.save(path)?;
+    log::debug!("Journal: {step} for {tag} {status}");
+    Ok(())
+}
+
+/// Record how a step ended and pass its result on. The step has already run,
+/// so a journal that cannot be written only warns: it must not fail a step
+/// that succeeded, nor hide the error of one that failed.
+fn record_outcome(
+    path: &Path,
+    tag: &str,
+    step: Step,
+    label: Option<String>,
+    result: Result<CIExit, Error>,
+) -> Result<CIExit, Error> {
+    let (status, error) = match &result {
+        Ok(_) => (StepStatus::Done, None),
+        Err(e) => (StepStatus::Failed, Some(e.to_string())),
+    };
+    if let Err(e) = record_step(path, tag, step, label, status, error) {
+        log::warn!("Could not record the {step} step of {tag} as {status}: {e}");
+    }
+
+    result
+}
+
+/// The command lines `resume` runs for `tag`: every recorded step that is not
+/// done, then `publish` if it was never recorded — the one step that needs
+/// nothing but the tag.
+fn resume_plan(journal: &Journal, tag: &str, path: &Path) -> Vec<Vec<String>> {
+    let mut plan = journal
+        .unfinished(tag)
+        .into_iter()
+        .map(|record| record.args.clone())
+        .collect::<Vec<_>>();
+
+    if journal.steps(tag).iter().all(|r| r.step != Step::Publish) {
+        plan.push(vec![
+            "release".to_string(),
+            "--journal".to_string(),
+            path.display().to_string(),
+            "publish".to_string(),
+            "--tag".to_string(),
+            tag.to_string(),
+        ]);
+    }
+
+    plan
+}
+
 /// Commit subject for the prlog update that accompanies a release.
 ///
 /// Distinct from the routine post-merge `chore: update prlog for pr`: this
@@ -358,6 +471,16 @@ pub struct Release {
     /// success.
     #[arg(long, default_value_t = false)]
     pub draft: bool,
+    /// With `--update-prlog`, fold the prlog sections of this version's
+    /// prereleases (e.g. `1.2.0-rc.1`) into the full release as it ships
+    #[arg(long, default_value_t = false)]
+    pub collect_prereleases: bool,
+    /// Record each step of the release in this JSON journal, for `release
+    /// status` and `release resume` (reads $PCU_RELEASE_JOURNAL if not
+    /// provided). Keep it outside the working tree, or ignored by git, so the
+    /// prlog commit does not pick it up.
+    #[arg(long)]
+    pub journal: Option<PathBuf>,
     #[command(subcommand)]
     pub mode: Mode,
 }
@@ -375,6 +498,44 @@ impl Release {
     }
 
     pub async fn run_release(self, sign_config: SignConfig) -> Result<CIExit, Error> {
+        match self.mode {
+            Mode::Notes(ref cmd) => return self.notes(cmd),
+            Mode::Status(ref cmd) => return self.status(&cmd.tag),
+            Mode::Resume(ref cmd) => return self.resume(&cmd.tag),
+            #[cfg(feature = "attest")]
+            Mode::VerifyAttestation(ref cmd) => return self.verify_attestation(cmd).await,
+            Mode::Sbom(ref cmd) if cmd.no_upload => return self.sbom(None).await,
+            _ => {}
+        }
+
+        let journal = match self.journal_path() {
+            Some(path) => {
+                let settings = Commands::Release(self.clone()).get_settings()?;
+                let templates = TagTemplates::from_settings(&settings)?;
+                self.journal_entry(&templates)?.map(|entry| (path, entry))
+            }
+            None => None,
+        };
+        // The journal is a record for `status` and `resume`, not a gate: a
+        // step runs even when it cannot be written, as in `record_outcome`.
+        if let Some((path, (tag, step, label))) = &journal {
+            let started = StepStatus::Started;
+            if let Err(e) = record_step(path, tag, *step, label.clone(), started, None) {
+                log::warn!("Could not record the {step} step of {tag} as {started}: {e}");
+            }
+        }
+
+        let result = self.run_step(sign_config).await;
+
+        match &journal {
+            Some((path, (tag, step, label))) => {
+                record_outcome(path, tag, *step, label.clone(), result)
+            }
+            None => result,
+        }
+    }
+
+    async fn run_step(self, sign_config: SignConfig) -> Result<CIExit, Error> {
         let client = Commands::Release(self.clone()).get_client().await?;
 
         match self.mode {
@@ -384,14 +545,133 @@ impl Release {
             Mode::Current(_) => self.release_current(client).await,
             Mode::CheckVersionPublished(_) => self.check_version_published().await,
             Mode::CheckTag(_) => self.check_tag(client).await,
-            Mode::InjectPubkey(_) => self.inject_pubkey().await,
+            Mode::InjectPubkey(_) => self.inject_pubkey(client).await,
+            Mode::CheckBinstall(_) => self.check_binstall(client).await,
             Mode::UploadAsset(_) => self.upload_asset(client).await,
+            Mode::Sign(_) => self.sign(client).await,
+            Mode::Sbom(_) => self.sbom(Some(client)).await,
             Mode::Publish(_) => self.publish(client).await,
+            Mode::Abort(_) => self.abort(client).await,
             #[cfg(feature = "attest")]
             Mode::Attest(_) => self.attest(client).await,
+            #[cfg(feature = "attest")]
+            Mode::VerifyAttestation(_) => {
+                unreachable!("verify-attestation runs without a client")
+            }
+            Mode::Notes(_) | Mode::Status(_) | Mode::Resume(_) => {
+                unreachable!("notes and journal commands run without a client")
+            }
         }
     }
 
+    /// The release journal named by `--journal` or `$PCU_RELEASE_JOURNAL`.
+    fn journal_path(&self) -> Option<PathBuf> {
+        self.journal
+            .clone()
+            .or_else(|| std::env::var_os(JOURNAL_ENV).map(PathBuf::from))
+    }
+
+    fn required_journal_path(&self) -> Result<PathBuf, Error> {
+        self.journal_path().ok_or_else(|| {
+            Error::ReleaseJournal(format!(
+                "no journal named: pass --journal or set ${JOURNAL_ENV}"
+            ))
+        })
+    }
+
+    /// The tag, step and label the journal records this command under, or
+    /// `None` for commands that are not a step of a release.
+    fn journal_entry(
+        &self,
+        templates: &TagTemplates,
+    ) -> Result<Option<(String, Step, Option<String>)>, Error> {
+        let entry = match &self.mode {
+            Mode::Version(version) => Some((
+                templates.tag(None, &self.prefix, &version.version)?,
+                Step::Version,
+                None,
+            )),
+            Mode::UploadAsset(cmd) => Some((cmd.tag.clone(), Step::UploadAsset, cmd.label())),
+            Mode::InjectPubkey(cmd) => match resolve_version(&cmd.version).as_str() {
+                "none" => None,
+                version => Some((
+                    crate_tag(templates, &cmd.package, version)?,
+                    Step::InjectPubkey,
+                    None,
+                )),
+            },
+            Mode::Sign(cmd) => match (&cmd.tag, resolve_version(&cmd.version).as_str()) {
+                (Some(tag), _) => Some((tag.clone(), Step::Sign, None)),
+                (None, "none") => None,
+                (None, version) => Some((
+                    crate_tag(templates, &cmd.package, version)?,
+                    Step::Sign,
+                    None,
+                )),
+            },
+            Mode::Sbom(cmd) if !cmd.no_upload => {
+                match (&cmd.tag, resolve_version(&cmd.version).as_str()) {
+                    (Some(tag), _) => Some((tag.clone(), Step::Sbom, None)),
+                    (None, "none") => None,
+                    (None, version) => Some((
+                        crate_tag(templates, &cmd.package, version)?,
+                        Step::Sbom,
+                        None,
+                    )),
+                }
+            }
+            #[cfg(feature = "attest")]
+            Mode::Attest(cmd) => match resolve_version(&cmd.version).as_str() {
+                "none" => None,
+                version => Some((cmd.release_tag(templates, version)?, Step::Attest, None)),
+            },
+            Mode::Publish(cmd) => Some((cmd.tag.clone(), Step::Publish, None)),
+            _ => None,
+        };
+
+        Ok(entry)
+    }
+
+    /// Print the progress of the release for `tag` from the journal.
+    fn status(&self, tag: &str) -> Result<CIExit, Error> {
+        let journal = Journal::load(&self.required_journal_path()?)?;
+        println!("{}", journal.status_report(tag));
+        Ok(CIExit::ReleaseStatus)
+    }
+
+    /// Run the unfinished steps of the release for `tag`, each with the
+    /// command line the journal recorded for it, stopping at the first that
+    /// fails.
+    fn resume(&self, tag: &str) -> Result<CIExit, Error> {
+        let path = self.required_journal_path()?;
+        let journal = Journal::load(&path)?;
+
+        for step in Step::ALL {
+            if step != Step::Publish && journal.steps(tag).iter().all(|r| r.step != step) {
+                log::info!("No {step} step recorded for {tag} — nothing to resume for it");
+            }
+        }
+
+        let plan = resume_plan(&journal, tag, &path);
+        let pcu = std::env::current_exe()?;
+        for args in plan {
+            log::info!("Resuming: pcu {}", args.join(" "));
+            let status = Command::new(&pcu)
+                .args(&args)
+                .env(JOURNAL_ENV, &path)
+                .status()?;
+            if !status.success() {
+                return Err(Error::ReleaseJournal(format!(
+                    "`pcu {}` failed; run `pcu release resume --tag {tag}` again once fixed",
+                    args.join(" ")
+                )));
+            }
+        }
+
+        log::info!("Release {tag} has no unfinished steps");
+        Ok(CIExit::Released)
+    }
+
     async fn release_workspace(&self, client: Client) -> Result<CIExit, Error> {
         //     log::info!("Running release for workspace");
         let path = Path::new("./Cargo.toml");
@@ -401,7 +681,10 @@ impl Release {
 
         if let Some(packages) = packages {
             for package in packages {
-                let prefix = format!("{}-{}", package.name, self.prefix);
+                let prefix = client.tag_templates.prefix(
+                    Some(&package.name),
+                    &format!("{}-{}", package.name, self.prefix),
+                )?;
                 let version = package.version;
                 ensure_github_release(&client, &prefix, &version, self.draft).await?;
             }
@@ -430,7 +713,10 @@ impl Release {
                 if package.name != *rel_package {
                     continue;
                 }
-                let prefix = format!("{}-{}", package.name, self.prefix);
+                let prefix = client.tag_templates.prefix(
+                    Some(&package.name),
+                    &format!("{}-{}", package.name, self.prefix),
+                )?;
                 let version = package.version;
                 ensure_github_release(&client, &prefix, &version, self.draft).await?;
                 break;
@@ -470,7 +756,10 @@ impl Release {
                         continue;
                     }
                 }
-                let prefix = format!("{}-{}", package.name, self.prefix);
+                let prefix = client.tag_templates.prefix(
+                    Some(&package.name),
+                    &format!("{}-{}", package.name, self.prefix),
+                )?;
                 let version = package.version;
                 ensure_github_release(&client, &prefix, &version, self.draft).await?;
                 break;
@@ -489,7 +778,9 @@ impl Release {
         };
 
         let version = version.version.to_string();
-        log::info!("Running version release for release {version}");
+        let prefix = client.tag_templates.prefix(None, &self.prefix)?;
+        let tag = format!("{prefix}{version}");
+        log::info!("Running version release for release {tag}");
         log::trace!(
             "PR ID: {} - Owner: {} - Repo: {}",
             client.pr_number(),
@@ -500,7 +791,7 @@ impl Release {
         log::trace!("Update prlog flag: {}", self.update_prlog);
 
         if self.update_prlog {
-            client.release_unreleased(&version)?;
+            client.release_unreleased(&version, self.collect_prereleases)?;
             log::debug!("Changelog file name: {}", client.prlog_as_str());
 
             log::trace!(
@@ -516,29 +807,27 @@ impl Release {
             );
 
             client
-                .commit_changed_files(sign_config, &commit_message, &self.prefix, Some(&version))
+                .commit_changed_files(sign_config, &commit_message, Some(&tag))
                 .await?;
 
             log::info!("Push the commit");
-            log::trace!("tag_opt: {:?} and no_push: {:?}", Some(&version), false);
+            log::trace!("tag: {tag:?} and no_push: false");
 
             let bot_user_name =
                 std::env::var("BOT_USER_NAME").unwrap_or_else(|_| "bot".to_string());
             log::debug!("Using bot user name: {bot_user_name}");
 
-            client.push_commit(&self.prefix, Some(&version), false, &bot_user_name)?;
+            client.push_commit(Some(&tag), false, &bot_user_name)?;
             let hdr_style = Style::new().bold().underline();
             log::debug!("{}", "Check Push".style(hdr_style));
             log::debug!("Branch status: {}", client.branch_status()?);
         }
 
-        client
-            .make_release(&self.prefix, &version, self.draft)
-            .await?;
+        client.make_release(&prefix, &version, self.draft).await?;
 
         #[cfg(feature = "linkedin")]
         if self.linkedin_share {
-            share_release_to_linkedin(&self.prefix, &version).await?;
+            share_release_to_linkedin(&prefix, &version).await?;
         }
 
         Ok(CIExit::Released)
@@ -600,7 +889,7 @@ impl Release {
             return Ok(CIExit::Released);
         }
 
-        let tag = format!("{}-v{}", cmd.package, version);
+        let tag = crate_tag(&client.tag_templates, &cmd.package, &version)?;
         log::info!("Checking if tag {tag} exists on remote");
 
         if client.tag_exists(&tag).await {
@@ -624,7 +913,7 @@ impl Release {
     /// signing scaffold errors instead of silently no-opping (pcu#1012), unless
     /// `--no-github-release`/`$PCU_NO_GITHUB_RELEASE` marks the binary as not
     /// published as a signed release.
-    async fn inject_pubkey(self) -> Result<CIExit, Error> {
+    async fn inject_pubkey(self, client: Client) -> Result<CIExit, Error> {
         let Mode::InjectPubkey(ref cmd) = self.mode else {
             return Err(Error::NoPackageSpecified);
         };
@@ -636,7 +925,7 @@ impl Release {
             return Ok(CIExit::Released);
         }
 
-        let tag = format!("{}-v{}", cmd.package, version);
+        let tag = crate_tag(&client.tag_templates, &cmd.package, &version)?;
         let crate_dir = format!("crates/{}", cmd.package);
         let cargo_toml_path = format!("{crate_dir}/Cargo.toml");
         let content = fs::read_to_string(&cargo_toml_path)?;
@@ -727,10 +1016,20 @@ impl Release {
 
         let found = client.find_release_for_tag(&cmd.tag).await?;
 
+        let prerelease = found.is_some_and(|r| r.prerelease);
+
         match publish_decision(found.map(|r| (r.id, r.draft))) {
             PublishAction::Publish(id) => {
+                let settings = Commands::Release(self.clone()).get_settings()?;
+                let required = RequiredAssets::from_settings(&settings)?;
+                if !required.patterns(cmd.package.as_deref()).is_empty() {
+                    let assets = client.release_asset_names(id).await?;
+                    let missing = required.missing(cmd.package.as_deref(), &assets);
+                    check_required_assets(&cmd.tag, &missing, cmd.force)?;
+                }
+
                 log::info!("Publishing draft release {} (id={id})", cmd.tag);
-                client.publish_release(id).await?;
+                client.publish_release(id, prerelease).await?;
                 log::info!("Published release {}", cmd.tag);
                 Ok(CIExit::Released)
             }
@@ -747,90 +1046,6 @@ impl Release {
             ))),
         }
     }
-
-    /// Upload a binary asset to an existing GitHub release.
-    ///
-    /// Looks up the release ID via `get_release_by_tag`, then uploads to
-    /// `uploads.github.com` using a dedicated `APIConfig` (octocrate's
-    /// `upload_release_asset` requires this separate base URL).
-    async fn upload_asset(self, client: Client) -> Result<CIExit, Error> {
-        let Mode::UploadAsset(ref cmd) = self.mode else {
-            return Err(Error::NoPackageSpecified);
-        };
-
-        let asset_name = cmd
-            .asset_name
-            .as_deref()
-            .map(String::from)
-            .or_else(|| {
-                cmd.asset_path
-                    .file_name()
-                    .map(|n| n.to_string_lossy().into_owned())
-            })
-            .ok_or_else(|| Error::GitError("Could not determine asset name".to_string()))?;
-
-        if !cmd.asset_path.exists() {
-            return Err(Error::GitError(format!(
-                "Asset file not found: {}",
-                cmd.asset_path.display()
-            )));
-        }
-
-        log::info!("Looking up GitHub release for tag {}", cmd.tag);
-
-        // Draft-aware: on a draft-first pipeline the release is still unpublished
-        // at this point, and a draft is invisible to get_release_by_tag.
-        let release_ref = client
-            .find_release_for_tag(&cmd.tag)
-            .await?
-            .ok_or_else(|| {
-                Error::GitError(format!(
-                    "no GitHub release found for tag '{}' to upload to",
-                    cmd.tag
-                ))
-            })?;
-
-        if release_ref.immutable {
-            return Err(Error::ImmutableRelease(
-                cmd.tag.clone(),
-                "the release is published with immutable assets".to_string(),
-            ));
-        }
-
-        // GitHub binary uploads must go to uploads.github.com, not api.github.com.
-        // A dedicated APIConfig with the upload base URL is required.
-        let upload_token = PersonalAccessToken::new(client.github_token.clone());
-        let upload_config = APIConfig::new("https://uploads.github.com", upload_token);
-        let upload_api = octocrate::GitHubAPI::new(&upload_config);
-
-        let file = tokio::fs::File::open(&cmd.asset_path).await?;
-        let content_length = file.metadata().await?.len();
-
-        // Minisign signatures are text; binaries use octet-stream
-        let content_type = if asset_name.ends_with(".sig") {
-            "text/plain"
-        } else {
-            "application/octet-stream"
-        };
-
-        let query = octocrate::repos::upload_release_asset::Query::builder()
-            .name(asset_name.clone())
-            .build();
-
-        upload_api
-            .repos
-            .upload_release_asset(client.owner(), client.repo(), release_ref.id)
-            .query(&query)
-            .header("Content-Type", content_type)
-            .header("Content-Length", content_length.to_string())
-            .file(file)
-            .send()
-            .await
-            .map_err(|e| crate::client::map_asset_upload_error(&cmd.tag, &e.to_string()))?;
-
-        log::info!("Successfully uploaded {asset_name}");
-        Ok(CIExit::Released)
-    }
 }
 
 fn print_prlog(prlog_path: &str, mut line_limit: usize) -> String {
@@ -933,6 +1148,23 @@ mod release_package_tests {
         // earlier step failed silently, which is exactly what must not pass.
         assert_eq!(publish_decision(None), PublishAction::NotFound);
     }
+
+    #[test]
+    fn check_required_assets_lists_what_is_missing() {
+        let missing = vec!["*.minisig".to_string(), "*.sigstore.json".to_string()];
+        let err = check_required_assets("pcu-v1.2.0", &missing, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Release pcu-v1.2.0 is missing required assets (pass --force to publish anyway):\n  - *.minisig\n  - *.sigstore.json"
+        );
+    }
+
+    #[test]
+    fn check_required_assets_passes_when_forced_or_complete() {
+        let missing = vec!["*.minisig".to_string()];
+        assert!(check_required_assets("pcu-v1.2.0", &missing, true).is_ok());
+        assert!(check_required_assets("pcu-v1.2.0", &[], false).is_ok());
+    }
 }
 
 /// Maps a `kdeets_lib::version_exists` result to `Result<bool, Error>`.
@@ -1308,4 +1540,82 @@ path = "src/lib.rs"
             other => panic!("expected Injected, got {other:?}"),
         }
     }
+
+    #[test]
+    fn resume_plan_reruns_unfinished_steps_then_publishes() {
+        let mut journal = Journal::default();
+        let upload = vec![
+            "release".to_string(),
+            "upload-asset".to_string(),
+            "--tag".to_string(),
+            "v1.2.0".to_string(),
+        ];
+        journal.record(
+            "v1.2.0",
+            Step::Version,
+            None,
+            vec!["release".to_string()],
+            StepStatus::Done,
+            None,
+        );
+        journal.record(
+            "v1.2.0",
+            Step::UploadAsset,
+            Some("pcu.tar.gz".to_string()),
+            upload.clone(),
+            StepStatus::Failed,
+            Some("upload failed".to_string()),
+        );
+
+        let plan = resume_plan(&journal, "v1.2.0", Path::new("journal.json"));
+        assert_eq!(
+            plan,
+            vec![
+                upload,
+                vec![
+                    "release".to_string(),
+                    "--journal".to_string(),
+                    "journal.json".to_string(),
+                    "publish".to_string(),
+                    "--tag".to_string(),
+                    "v1.2.0".to_string(),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn resume_plan_is_empty_once_published() {
+        let mut journal = Journal::default();
+        journal.record(
+            "v1.2.0",
+            Step::Publish,
+            None,
+            vec!["release".to_string()],
+            StepStatus::Done,
+            None,
+        );
+
+        assert!(resume_plan(&journal, "v1.2.0", Path::new("journal.json")).is_empty());
+    }
+
+    #[test]
+    fn record_outcome_passes_the_result_on_when_the_journal_cannot_be_written() {
+        let dir = tempfile::tempdir().unwrap();
+        // A directory where the journal file should be cannot be written.
+        let path = dir.path().join("journal.json");
+        fs::create_dir(&path).unwrap();
+
+        let ok = record_outcome(&path, "v1.2.0", Step::Publish, None, Ok(CIExit::Released));
+        assert!(matches!(ok, Ok(CIExit::Released)));
+
+        let failed = record_outcome(
+            &path,
+            "v1.2.0",
+            Step::Publish,
+            None,
+            Err(Error::AssetUpload("upload failed".to_string())),
+        );
+        assert!(matches!(failed, Err(Error::AssetUpload(message)) if message == "upload failed"));
+    }
 }
@@ -0,0 +1,221 @@
+//! `pcu release abort`: the supported way to unwind a release that failed
+//! part-way, rather than deleting its pieces by hand.
+//!
+//! Every guard runs before anything is deleted. A release that anyone could
+//! already have installed — a published or immutable GitHub release, or a
+//! version on crates.io — is finished, not half-finished, and is refused.
+
+use std::process::Command;
+
+use git2::{Oid, Repository};
+use pcu_release_assets::ReleaseRef;
+
+use super::{
+    crate_tag, map_kdeets_result, release_prlog_commit_message, resolve_version, Mode, Release,
+};
+use crate::{CIExit, Client, Error, GitOps};
+
+/// How far back from HEAD to look for the prlog commit of the release.
+const PRLOG_COMMIT_SEARCH_DEPTH: usize = 50;
+
+impl Release {
+    pub(super) async fn abort(self, client: Client) -> Result<CIExit, Error> {
+        let Mode::Abort(ref cmd) = self.mode else {
+            return Err(Error::NoPackageSpecified);
+        };
+
+        let version = resolve_version(&cmd.version);
+        if version == "none" {
+            return Err(Error::MissingSemver);
+        }
+        let tag = match &cmd.tag {
+            Some(tag) => tag.clone(),
+            None => crate_tag(&client.tag_templates, &cmd.package, &version)?,
+        };
+        log::info!("Aborting release {tag}");
+
+        let release = client.find_release_for_tag(&tag).await?;
+        let on_crates_io = map_kdeets_result(kdeets_lib::version_exists(&cmd.package, &version))?;
+        check_abort(&tag, release, on_crates_io)?;
+
+        let prlog_commit = if cmd.revert_prlog {
+            let commit = find_prlog_release_commit(&client.git_repo, &version)?;
+            Some(commit.ok_or_else(|| {
+                Error::ReleaseAbortRefused(
+                    tag.clone(),
+                    format!(
+                        "no `{}` commit in the last {PRLOG_COMMIT_SEARCH_DEPTH} commits to revert",
+                        release_prlog_commit_message(&version)
+                    ),
+                )
+            })?)
+        } else {
+            None
+        };
+
+        if let Some(release) = release {
+            client.delete_release(release.id).await?;
+            log::info!("Deleted draft release {tag} (id={})", release.id);
+        }
+
+        if client.tag_exists(&tag).await {
+            client.delete_remote_tag(&tag)?;
+            log::info!("Deleted remote tag {tag}");
+        }
+
+        if client
+            .git_repo
+            .find_reference(&format!("refs/tags/{tag}"))
+            .is_ok()
+        {
+            client.git_repo.tag_delete(&tag)?;
+            log::info!("Deleted local tag {tag}");
+        }
+
+        if let Some(commit) = prlog_commit {
+            revert_commit(&client.git_repo, commit)?;
+
+            let bot_user_name =
+                std::env::var("BOT_USER_NAME").unwrap_or_else(|_| "bot".to_string());
+            client.push_commit(None, false, &bot_user_name)?;
+            log::info!("Reverted and pushed prlog commit {commit}");
+        }
+
+        Ok(CIExit::ReleaseAborted(tag))
+    }
+}
+
+/// Refuse to abort a release that has shipped: one whose GitHub release is
+/// published or immutable, or whose version is on crates.io.
+fn check_abort(tag: &str, release: Option<ReleaseRef>, on_crates_io: bool) -> Result<(), Error> {
+    let refuse = |reason: &str| Err(Error::ReleaseAbortRefused(tag.to_string(), reason.into()));
+
+    if on_crates_io {
+        return refuse("the crate version is already published to crates.io");
+    }
+
+    match release {
+        Some(release) if release.immutable => refuse("the GitHub release is immutable"),
+        Some(release) if !release.draft => refuse("the GitHub release is already published"),
+        _ => Ok(()),
+    }
+}
+
+/// The `chore: update prlog for release <version>` commit among the recent
+/// first-parent history of HEAD, if there is one.
+fn find_prlog_release_commit(repo: &Repository, version: &str) -> Result<Option<Oid>, Error> {
+    let message = release_prlog_commit_message(version);
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.simplify_first_parent()?;
+
+    for oid in revwalk.take(PRLOG_COMMIT_SEARCH_DEPTH) {
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+        let summary = commit.summary()?.unwrap_or_default();
+        // The subject may carry the ci-skip marker after the version.
+        if summary == message || summary.starts_with(&format!("{message} ")) {
+            return Ok(Some(oid));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Revert `commit` with the git CLI, so the revert is signed whenever the
+/// repository's git configuration signs commits.
+fn revert_commit(repo: &Repository, commit: Oid) -> Result<(), Error> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| Error::GitError("repository has no working directory".into()))?;
+
+    let output = Command::new("git")
+        .args(["revert", "--no-edit", &commit.to_string()])
+        .current_dir(workdir)
+        .output()
+        .map_err(|e| Error::GitError(format!("Failed to run git revert: {e}")))?;
+
+    if !output.status.success() {
+        return Err(Error::GitError(format!(
+            "git revert {commit} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::Signature;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn release(draft: bool, immutable: bool) -> ReleaseRef {
+        ReleaseRef {
+            id: 42,
+            draft,
+            prerelease: false,
+            immutable,
+        }
+    }
+
+    #[rstest]
+    #[case::draft(Some(release(true, false)), false, true)]
+    #[case::no_release(None, false, true)]
+    #[case::published(Some(release(false, false)), false, false)]
+    #[case::immutable(Some(release(false, true)), false, false)]
+    #[case::on_crates_io(Some(release(true, false)), true, false)]
+    fn test_check_abort(
+        #[case] found: Option<ReleaseRef>,
+        #[case] on_crates_io: bool,
+        #[case] allowed: bool,
+    ) {
+        let result = check_abort("pcu-v1.2.0", found, on_crates_io);
+        match allowed {
+            true => assert!(result.is_ok()),
+            false => assert!(matches!(result, Err(Error::ReleaseAbortRefused(_, _)))),
+        }
+    }
+
+    #[test]
+    fn test_find_prlog_release_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let sig = Signature::now("Test", "test@example.com").unwrap();
+
+        let mut parent = None;
+        let mut commits = Vec::new();
+        for message in [
+            "feat: add flag",
+            "chore: update prlog for release 1.2.30",
+            "chore: update prlog for release 1.2.3 [skip ci]",
+            "fix: typo",
+        ] {
+            let tree_id = repo.index().unwrap().write_tree().unwrap();
+            let tree = repo.find_tree(tree_id).unwrap();
+            let parents = parent
+                .map(|oid| repo.find_commit(oid).unwrap())
+                .into_iter()
+                .collect::<Vec<_>>();
+            let parents = parents.iter().collect::<Vec<_>>();
+            let oid = repo
+                .commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+                .unwrap();
+            commits.push(oid);
+            parent = Some(oid);
+        }
+
+        assert_eq!(
+            find_prlog_release_commit(&repo, "1.2.3").unwrap(),
+            Some(commits[2])
+        );
+        assert_eq!(
+            find_prlog_release_commit(&repo, "1.2.30").unwrap(),
+            Some(commits[1])
+        );
+        assert_eq!(find_prlog_release_commit(&repo, "1.3.0").unwrap(), None);
+    }
+}
@@ -11,22 +11,62 @@
 //! its dispatch arm are gated too, so the CLI simply does not offer the command
 //! when it is compiled out.
 
-use octocrate::{APIConfig, PersonalAccessToken};
+use super::{
+    ci_provider::{CiProvider, Provider},
+    crate_tag,
+    mode::Attest,
+    provenance::BuildContext,
+    resolve_version,
+    upload_asset::{
+        hex_encode, local_assets, resolve_asset_paths, sha256_hex, upload_all, PlannedUpload,
+        Uploader,
+    },
+    Mode, Release,
+};
+use crate::{CIExit, Client, Error, TagTemplates};
+
+impl Attest {
+    /// The tag of the release the attestation is attached to.
+    pub(super) fn release_tag(
+        &self,
+        templates: &TagTemplates,
+        version: &str,
+    ) -> Result<String, Error> {
+        match &self.crate_tag_prefix {
+            Some(prefix) => Ok(format!("{prefix}{version}")),
+            None => crate_tag(templates, &self.package, version),
+        }
+    }
 
-use super::{resolve_version, Mode, Release};
-use crate::{CIExit, Client, Error};
+    /// Release asset name of the provenance. Attested artifacts get a name of
+    /// their own, so that the crate's provenance on the same release is not
+    /// overwritten.
+    fn provenance_filename(&self, version: &str) -> String {
+        let pkg = &self.package;
+        match &self.provenance_name {
+            Some(name) => name.clone(),
+            None if self.artifact.is_empty() => format!("{pkg}-{version}.provenance.json"),
+            None => format!("{pkg}-{version}-artifacts.provenance.json"),
+        }
+    }
+}
 
 impl Release {
-    /// Attest a published crate with SLSA v0.2 provenance signed via Sigstore keyless.
+    /// Attest a published crate, or local artifacts, with SLSA provenance
+    /// signed via Sigstore keyless.
     ///
     /// Steps:
-    /// 1. Download the .crate from crates.io (with retry for indexing delay)
-    /// 2. Compute SHA256 of the downloaded artifact
-    /// 3. Generate SLSA v0.2 provenance JSON recording source, environment, and artifact
-    /// 4. Sign the .crate with cosign-compatible keyless signing (CircleCI OIDC → Fulcio → Rekor)
+    /// 1. Download the .crate from crates.io (with retry for indexing delay),
+    ///    or read the files named by `--artifact`
+    /// 2. Compute SHA256 of each artifact, and of the SBOM named by `--sbom`
+    /// 3. Generate an in-toto Statement v1 with a SLSA Provenance v1 predicate
+    ///    recording source, Cargo.lock, builder and artifacts (or, with
+    ///    `--provenance-format v0.2`, the legacy v0.2 document)
+    /// 4. Sign with cosign-compatible keyless signing (CI OIDC → Fulcio → Rekor):
+    ///    the .crate itself, or, for `--artifact`, the provenance listing them all
     /// 5. Upload the .sigstore.json bundle and provenance.json to the GitHub release
     ///
-    /// Requires CIRCLE_OIDC_TOKEN_V2 with audience "sigstore" in the environment.
+    /// The identity token comes from the detected CI provider; see `ci_provider`.
     pub(super) async fn attest(self, client: Client) -> Result<CIExit, Error> {
         let Mode::Attest(ref cmd) = self.mode else {
             return Err(Error::NoPackageSpecified);
@@ -40,13 +80,19 @@ impl Release {
 
         let pkg = &cmd.package;
         let crate_filename = format!("{pkg}-{version}.crate");
-        let crate_url = format!("https://static.crates.io/crates/{pkg}/{crate_filename}");
-        let bundle_filename = format!("{crate_filename}.sigstore.json");
-        let provenance_filename = format!("{pkg}-{version}.provenance.json");
+        let provenance_filename = cmd.provenance_filename(&version);
+        // What the bundle signs: the .crate, or the provenance that lists
+        // every artifact.
+        let signed_filename = if cmd.artifact.is_empty() {
+            &crate_filename
+        } else {
+            &provenance_filename
+        };
+        let bundle_filename = format!("{signed_filename}.sigstore.json");
 
         // Step 1: Check whether attestation assets already exist on the GitHub release.
         // If both assets are present the previous run completed successfully — skip all work.
-        let release_tag = format!("{}{}", cmd.crate_tag_prefix, version);
+        let release_tag = cmd.release_tag(&client.tag_templates, &version)?;
         // Draft-aware: attestation assets are uploaded BEFORE publication on a
         // draft-first pipeline (assets freeze at publication), so the release
         // being attested is normally still a draft — invisible to
@@ -67,6 +113,13 @@ impl Release {
             .await?;
         let existing_assets: std::collections::HashSet<String> =
             release.assets.iter().map(|a| a.name.clone()).collect();
+        let existing_id = |name: &str| {
+            release
+                .assets
+                .iter()
+                .find(|asset| asset.name == name)
+                .map(|asset| asset.id)
+        };
         if attestation_assets_already_uploaded(
             &existing_assets,
             &bundle_filename,
@@ -76,117 +129,81 @@ impl Release {
             return Ok(CIExit::Released);
         }
 
+        let provider = Provider::detect(cmd.env_file.as_deref())?;
+        log::info!("Attesting on {}", provider.name());
+
+        let started_on = chrono::Utc::now();
         let attest_dir = std::path::Path::new("/tmp/attestation");
         std::fs::create_dir_all(attest_dir)?;
-        let crate_path = attest_dir.join(&crate_filename);
 
-        // Step 2: Download .crate from crates.io with retry
-        log::info!(
-            "Waiting {}s for crates.io indexing before download...",
-            cmd.crates_io_delay
-        );
-        tokio::time::sleep(std::time::Duration::from_secs(cmd.crates_io_delay)).await;
-
-        let http_client = reqwest::Client::new();
-        let crate_bytes =
-            download_with_retry(
-                &crate_filename,
-                cmd.max_attempts.into(),
-                std::time::Duration::from_secs(30),
-                || {
-                    let client = http_client.clone();
-                    let url = crate_url.clone();
-                    async move {
-                        let response =
-                            client.get(&url).send().await.map_err(|e| {
-                                Error::Attestation(format!("HTTP request failed: {e}"))
-                            })?;
-                        if !response.status().is_success() {
-                            return Err(Error::Attestation(format!(
-                                "HTTP {} for {url}",
-                                response.status()
-                            )));
-                        }
-                        response.bytes().await.map(|b| b.to_vec()).map_err(|e| {
-                            Error::Attestation(format!("Failed to read response: {e}"))
-                        })
-                    }
-                },
-            )
-            .await?;
-        std::fs::write(&crate_path, &crate_bytes)?;
-
-        // Step 3: Read bytes and compute SHA256
-        use sha2::Digest as _;
-        let hash_hex = sha2::Sha256::digest(&crate_bytes).iter().fold(
-            String::with_capacity(64),
-            |mut s, b| {
-                use std::fmt::Write as _;
-                write!(s, "{b:02x}").unwrap();
-                s
-            },
-        );
-        log::info!("SHA256({crate_filename}) = {hash_hex}");
+        // Steps 1 and 2: read the artifacts and compute their SHA256
+        let artifacts = if cmd.artifact.is_empty() {
+            let crate_bytes = download_crate(cmd, &crate_filename).await?;
+            std::fs::write(attest_dir.join(&crate_filename), &crate_bytes)?;
+            vec![(crate_filename.clone(), crate_bytes)]
+        } else {
+            local_artifacts(&cmd.artifact)?
+        };
+        let mut subjects: Vec<_> = artifacts
+            .iter()
+            .map(|(name, bytes)| {
+                let hash_hex = sha256_bytes_hex(bytes);
+                log::info!("SHA256({name}) = {hash_hex}");
+                (name.clone(), hash_hex)
+            })
+            .collect();
+        if let Some(sbom) = &cmd.sbom {
+            subjects.push(sbom_subject(sbom)?);
+        }
 
-        // Step 4: Generate SLSA v0.2 provenance JSON
-        let build_started = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        // Step 4: Generate the provenance document
         let rust_version = std::process::Command::new("rustc")
             .arg("--version")
             .output()
             .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
             .unwrap_or_else(|_| "unknown".to_string());
+        let cargo_lock = std::path::Path::new("Cargo.lock");
+        let cargo_lock = if cargo_lock.is_file() {
+            Some(sha256_hex(cargo_lock)?)
+        } else {
+            log::warn!("No Cargo.lock in the working directory to record in the provenance");
+            None
+        };
 
-        let provenance = serde_json::json!({
-            "builder": {
-                "id": std::env::var("CIRCLE_BUILD_URL").unwrap_or_default()
-            },
-            "buildType": "https://github.com/jerus-org/circleci-toolkit",
-            "invocation": {
-                "configSource": {
-                    "uri": std::env::var("CIRCLE_REPOSITORY_URL").unwrap_or_default(),
-                    "digest": { "sha1": std::env::var("CIRCLE_SHA1").unwrap_or_default() },
-                    "entryPoint": ".circleci/release.yml"
-                },
-                "parameters": {
-                    "package": pkg,
-                    "version": &version,
-                    "rust_version": &rust_version
-                },
-                "environment": {
-                    "CIRCLE_BUILD_URL": std::env::var("CIRCLE_BUILD_URL").unwrap_or_default(),
-                    "CIRCLE_WORKFLOW_ID": std::env::var("CIRCLE_WORKFLOW_ID").unwrap_or_default(),
-                    "CIRCLE_PROJECT_USERNAME": std::env::var("CIRCLE_PROJECT_USERNAME").unwrap_or_default(),
-                    "CIRCLE_PROJECT_REPONAME": std::env::var("CIRCLE_PROJECT_REPONAME").unwrap_or_default()
-                }
-            },
-            "metadata": {
-                "buildStartedOn": build_started,
-                "completeness": { "parameters": true, "environment": true, "materials": true },
-                "reproducible": false
-            },
-            "materials": [
-                {
-                    "uri": std::env::var("CIRCLE_REPOSITORY_URL").unwrap_or_default(),
-                    "digest": { "sha1": std::env::var("CIRCLE_SHA1").unwrap_or_default() }
-                }
-            ],
-            "subject": [
-                {
-                    "name": &crate_filename,
-                    "digest": { "sha256": &hash_hex }
-                }
-            ]
-        });
+        let build = provider.build_info();
+        let repository = format!("https://github.com/{}/{}", client.owner(), client.repo());
+        let context = BuildContext {
+            package: pkg.clone(),
+            version: version.clone(),
+            subjects,
+            legacy_repository: build.repository_url.unwrap_or_else(|| repository.clone()),
+            repository,
+            commit: build.commit,
+            entry_point: build.entry_point,
+            builder_id: build.builder_id,
+            invocation_id: build.invocation_id,
+            environment: build.environment,
+            rust_version,
+            cargo_lock,
+            started_on,
+            finished_on: chrono::Utc::now(),
+        };
+        let provenance = context.render(cmd.provenance_format)?;
 
         let provenance_path = attest_dir.join(&provenance_filename);
-        std::fs::write(&provenance_path, serde_json::to_string_pretty(&provenance)?)?;
+        std::fs::write(&provenance_path, &provenance)?;
         log::info!("Generated provenance: {provenance_filename}");
 
-        // Step 5: Sign with Sigstore keyless (CircleCI OIDC → Fulcio v1 → Rekor)
-        let oidc_token_str = get_oidc_token()?;
+        // Step 5: Sign with Sigstore keyless (CI OIDC → Fulcio v1 → Rekor)
+        let oidc_token_str = provider.id_token().await?;
 
-        log::info!("Signing {crate_filename} via Fulcio v1 API...");
-        let bundle_json = sign_artifact_fulcio_v1(&crate_bytes, &oidc_token_str).await?;
+        let signed = if cmd.artifact.is_empty() {
+            artifacts[0].1.as_slice()
+        } else {
+            provenance.as_bytes()
+        };
+        log::info!("Signing {signed_filename} via Fulcio v1 API...");
+        let bundle_json = sign_artifact_fulcio_v1(signed, &oidc_token_str).await?;
 
         let bundle_path = attest_dir.join(&bundle_filename);
         std::fs::write(&bundle_path, &bundle_json)?;
@@ -195,44 +212,106 @@ impl Release {
         // Step 6: Upload bundle and provenance to GitHub release
         log::info!("Uploading attestation assets to release {release_tag}...");
 
-        let upload_token = PersonalAccessToken::new(client.github_token.clone());
-        let upload_config = APIConfig::new("https://uploads.github.com", upload_token);
-        let upload_api = octocrate::GitHubAPI::new(&upload_config);
-
-        for (path, name) in [
-            (&bundle_path, bundle_filename.as_str()),
-            (&provenance_path, provenance_filename.as_str()),
-        ] {
-            let file = tokio::fs::File::open(path).await?;
-            let content_length = file.metadata().await?.len();
-            let query = octocrate::repos::upload_release_asset::Query::builder()
-                .name(name)
-                .build();
-            upload_api
-                .repos
-                .upload_release_asset(client.owner(), client.repo(), release.id)
-                .query(&query)
-                .header("Content-Type", "application/octet-stream")
-                .header("Content-Length", content_length.to_string())
-                .file(file)
-                .send()
-                .await?;
-            log::info!("Uploaded {name}");
-        }
+        // What a failed earlier run left behind is replaced, not duplicated.
+        let uploads = [
+            (bundle_path, bundle_filename.clone()),
+            (provenance_path, provenance_filename.clone()),
+        ]
+        .into_iter()
+        .map(|(path, name)| PlannedUpload {
+            replace: existing_id(&name),
+            path,
+            name,
+        })
+        .collect::<Vec<_>>();
+        let uploader = Uploader::new(&client, &release_tag, release.id, cmd.max_attempts);
+        let concurrency = uploads.len();
+        upload_all(&uploader, uploads, concurrency).await?;
 
         log::info!("Attestation complete.");
+        let (issuer, identity) = provider.identity();
         log::info!(
-            "Verify with: cosign verify-blob \
-        --certificate-oidc-issuer 'https://oidc.circleci.com/org/<ORG_ID>' \
-        --certificate-identity-regexp 'https://circleci.com/gh/{}/.*' \
-        --bundle '{bundle_filename}' '{crate_filename}'",
-            std::env::var("CIRCLE_PROJECT_USERNAME").unwrap_or_default()
+            "Verify with: pcu release verify-attestation '{signed_filename}' \
+        --bundle '{bundle_filename}' --trusted-root trusted_root.json \
+        --certificate-oidc-issuer '{issuer}' \
+        --certificate-identity-regexp '{identity}'"
         );
 
         Ok(CIExit::Released)
     }
 }
 
+/// Download the published .crate from crates.io, after waiting for it to be
+/// indexed.
+async fn download_crate(cmd: &Attest, crate_filename: &str) -> Result<Vec<u8>, Error> {
+    let crate_url = format!(
+        "https://static.crates.io/crates/{}/{crate_filename}",
+        cmd.package
+    );
+
+    log::info!(
+        "Waiting {}s for crates.io indexing before download...",
+        cmd.crates_io_delay
+    );
+    tokio::time::sleep(std::time::Duration::from_secs(cmd.crates_io_delay)).await;
+
+    let http_client = reqwest::Client::new();
+    download_with_retry(
+        crate_filename,
+        cmd.max_attempts.into(),
+        std::time::Duration::from_secs(30),
+        || {
+            let client = http_client.clone();
+            let url = crate_url.clone();
+            async move {
+                let response = client
+                    .get(&url)
+                    .send()
+                    .await
+                    .map_err(|e| Error::Attestation(format!("HTTP request failed: {e}")))?;
+                if !response.status().is_success() {
+                    return Err(Error::Attestation(format!(
+                        "HTTP {} for {url}",
+                        response.status()
+                    )));
+                }
+                response
+                    .bytes()
+                    .await
+                    .map(|b| b.to_vec())
+                    .map_err(|e| Error::Attestation(format!("Failed to read response: {e}")))
+            }
+        },
+    )
+    .await
+}
+
+/// The files named by `--artifact` paths, directories and globs, each with
+/// the name it is attested under.
+fn local_artifacts(args: &[String]) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    local_assets(resolve_asset_paths(args)?, None)?
+        .into_iter()
+        .map(|asset| Ok((asset.name, std::fs::read(&asset.path)?)))
+        .collect()
+}
+
+/// The SBOM as a provenance subject, under its file name — the asset name
+/// `sbom` uploads it as.
+fn sbom_subject(path: &std::path::Path) -> Result<(String, String), Error> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| Error::Attestation(format!("{} is not an SBOM file", path.display())))?;
+    let hash_hex = sha256_hex(path)?;
+    log::info!("SHA256({name}) = {hash_hex}");
+    Ok((name, hash_hex))
+}
+
+fn sha256_bytes_hex(bytes: &[u8]) -> String {
+    use sha2::Digest as _;
+    hex_encode(&sha2::Sha256::digest(bytes))
+}
+
 /// Returns true if the version string indicates no release is needed.
 fn should_skip_attest(version: &str) -> bool {
     version == "none"
@@ -252,20 +331,6 @@ fn attestation_assets_already_uploaded(
         && existing_asset_names.contains(provenance_filename)
 }
 
-/// Read the CircleCI OIDC token (v2) from the environment.
-///
-/// Returns `Error::Attestation` with a clear message if the variable is unset.
-fn get_oidc_token() -> Result<String, Error> {
-    std::env::var("CIRCLE_OIDC_TOKEN_V2").map_err(|_| {
-        Error::Attestation(
-            "CIRCLE_OIDC_TOKEN_V2 is not set. \
-            Set it to a CircleCI OIDC token with audience 'sigstore'. \
-            Use `circleci run oidc get --claims '{\"aud\":\"sigstore\"}'` to obtain one."
-                .to_string(),
-        )
-    })
-}
-
 /// Extract the `sub` claim from a raw JWT string without requiring an `email` claim.
 ///
 /// CircleCI machine OIDC tokens do not include an `email` field; only `sub` is needed
@@ -311,7 +376,7 @@ fn pem_to_der(pem_str: &str) -> Result<Vec<u8>, Error> {
         .map_err(|e| Error::Attestation(format!("PEM to DER conversion failed: {e}")))
 }
 
-/// Sign `artifact` bytes using the Fulcio v1 API with a CircleCI OIDC token.
+/// Sign `artifact` bytes using the Fulcio v1 API with a CI OIDC token.
 ///
 /// The v1 path (`FulcioClient::request_cert`) uses `TokenProvider::Static` and signs
 /// the challenge (= `sub` claim) to prove key possession.  It does NOT require an
@@ -358,13 +423,7 @@ async fn sign_artifact_fulcio_v1(artifact: &[u8], oidc_token_str: &str) -> Resul
 
     // Compute SHA256 of artifact
     let sha256_hash = sha2::Sha256::digest(artifact);
-    let sha256_hex = sha256_hash
-        .iter()
-        .fold(String::with_capacity(64), |mut s, b| {
-            use std::fmt::Write as _;
-            write!(s, "{b:02x}").unwrap();
-            s
-        });
+    let sha256_hex = hex_encode(&sha256_hash);
 
     // Sign artifact bytes
     let signature_bytes = signer
@@ -547,40 +606,6 @@ mod attest_tests {
         );
     }
 
-    #[test]
-    fn get_oidc_token_errors_when_env_var_missing() {
-        let saved = std::env::var("CIRCLE_OIDC_TOKEN_V2").ok();
-        unsafe { std::env::remove_var("CIRCLE_OIDC_TOKEN_V2") };
-
-        let result = get_oidc_token();
-
-        if let Some(v) = saved {
-            unsafe { std::env::set_var("CIRCLE_OIDC_TOKEN_V2", v) };
-        }
-
-        assert!(result.is_err(), "should error when env var is absent");
-        let msg = result.unwrap_err().to_string();
-        assert!(
-            msg.contains("CIRCLE_OIDC_TOKEN_V2"),
-            "error should name the missing env var: {msg}"
-        );
-    }
-
-    #[test]
-    fn get_oidc_token_returns_value_when_env_var_set() {
-        let saved = std::env::var("CIRCLE_OIDC_TOKEN_V2").ok();
-        unsafe { std::env::set_var("CIRCLE_OIDC_TOKEN_V2", "some-token") };
-
-        let result = get_oidc_token();
-
-        unsafe { std::env::remove_var("CIRCLE_OIDC_TOKEN_V2") };
-        if let Some(v) = saved {
-            unsafe { std::env::set_var("CIRCLE_OIDC_TOKEN_V2", v) };
-        }
-
-        assert_eq!(result.unwrap(), "some-token");
-    }
-
     #[test]
     fn extract_sub_from_jwt_returns_sub_claim() {
         let jwt = fake_jwt("https://circleci.com/org/abc/project/xyz/user/u");
@@ -691,4 +716,84 @@ mod attest_tests {
             "my-crate-1.2.3.provenance.json",
         ));
     }
+
+    #[test]
+    fn provenance_filename_sets_artifact_provenance_apart() {
+        use clap::Parser as _;
+        let attest =
+            |args: &[&str]| Attest::try_parse_from(["attest", "pcu"].iter().chain(args)).unwrap();
+
+        assert_eq!(
+            attest(&[]).provenance_filename("1.2.3"),
+            "pcu-1.2.3.provenance.json"
+        );
+        assert_eq!(
+            attest(&["--artifact", "dist"]).provenance_filename("1.2.3"),
+            "pcu-1.2.3-artifacts.provenance.json"
+        );
+        assert_eq!(
+            attest(&["--artifact", "dist", "--provenance-name", "bin.json"])
+                .provenance_filename("1.2.3"),
+            "bin.json"
+        );
+    }
+
+    #[test]
+    fn sha256_bytes_hex_is_lowercase_hex() {
+        assert_eq!(
+            sha256_bytes_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn local_artifacts_reads_every_file_a_glob_matches() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("pcu-x86_64.tar.gz"), b"linux").unwrap();
+        std::fs::write(dir.path().join("pcu-aarch64.tar.gz"), b"arm").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"skip").unwrap();
+
+        let mut artifacts =
+            local_artifacts(&[format!("{}/*.tar.gz", dir.path().display())]).unwrap();
+        artifacts.sort();
+
+        assert_eq!(
+            artifacts,
+            vec![
+                ("pcu-aarch64.tar.gz".to_string(), b"arm".to_vec()),
+                ("pcu-x86_64.tar.gz".to_string(), b"linux".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sbom_needs_the_signed_provenance_of_artifact_mode() {
+        use clap::Parser as _;
+
+        assert!(Attest::try_parse_from(["attest", "pcu", "--sbom", "pcu.cdx.json"]).is_err());
+        assert!(Attest::try_parse_from([
+            "attest",
+            "pcu",
+            "--artifact",
+            "dist",
+            "--sbom",
+            "pcu.cdx.json"
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn sbom_subject_is_named_after_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pcu-1.2.3.cdx.json");
+        std::fs::write(&path, b"abc").unwrap();
+
+        assert_eq!(
+            sbom_subject(&path).unwrap(),
+            (
+                "pcu-1.2.3.cdx.json".to_string(),
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string()
+            )
+        );
+    }
 }
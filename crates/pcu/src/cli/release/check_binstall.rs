@@ -0,0 +1,401 @@
+//! `pcu release check-binstall`: check that the asset names cargo-binstall
+//! will build from `[package.metadata.binstall]` exist on the release.
+//!
+//! A broken `pkg-url` template otherwise shows up only when a user's
+//! `cargo binstall` fails. For each target, the crate-wide settings are
+//! overlaid with the target's `overrides`, `pkg-url` is expanded the way
+//! cargo-binstall expands it, and the asset it names is looked up on the
+//! (draft) release. `bin-dir` names a path inside the archive, so it is only
+//! expanded, to catch unknown template keys.
+
+use std::collections::BTreeMap;
+
+use cargo_toml::Manifest;
+use serde::Deserialize;
+
+use super::{crate_tag, resolve_version, Mode, Release};
+use crate::{CIExit, Client, Error};
+
+impl Release {
+    pub(super) async fn check_binstall(self, client: Client) -> Result<CIExit, Error> {
+        let Mode::CheckBinstall(ref cmd) = self.mode else {
+            return Err(Error::NoPackageSpecified);
+        };
+
+        let cargo_toml_path = format!("crates/{}/Cargo.toml", cmd.package);
+        let manifest = Manifest::<PackageMetadata>::from_path_with_metadata(&cargo_toml_path)?;
+        let package = manifest.package.as_ref().ok_or(Error::NoPackageSpecified)?;
+
+        let binstall = package
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.binstall.clone())
+            .ok_or_else(|| {
+                Error::InvalidBinstallMetadata(format!(
+                    "{cargo_toml_path} has no [package.metadata.binstall]"
+                ))
+            })?;
+
+        let version = match resolve_version(&cmd.version).as_str() {
+            "none" => package.version().to_string(),
+            version => version.to_string(),
+        };
+        let tag = match &cmd.tag {
+            Some(tag) => tag.clone(),
+            None => crate_tag(&client.tag_templates, &cmd.package, &version)?,
+        };
+
+        let targets = match cmd.target.is_empty() {
+            true => binstall.overrides.keys().cloned().collect::<Vec<_>>(),
+            false => cmd.target.clone(),
+        };
+        if targets.is_empty() {
+            return Err(Error::InvalidBinstallMetadata(
+                "no targets to check: pass --target, or list targets under \
+                 [package.metadata.binstall.overrides]"
+                    .to_string(),
+            ));
+        }
+
+        let repo_url = format!("https://github.com/{}/{}", client.owner(), client.repo());
+        let crate_info = CrateInfo {
+            name: package.name.clone(),
+            version,
+            repo: package
+                .repository()
+                .map(normalise_repository)
+                .unwrap_or_else(|| repo_url.clone()),
+            bins: manifest
+                .bin
+                .iter()
+                .filter_map(|bin| bin.name.clone())
+                .collect(),
+        };
+
+        let release = client.find_release_for_tag(&tag).await?.ok_or_else(|| {
+            Error::GitError(format!("no GitHub release found for tag '{tag}' to check"))
+        })?;
+        let download_url = format!("{repo_url}/releases/download/{tag}/");
+
+        let mut problems = Vec::new();
+        for target in &targets {
+            let candidates = match asset_candidates(
+                &binstall.for_target(target),
+                &crate_info,
+                target,
+                &download_url,
+            ) {
+                Ok(candidates) => candidates,
+                Err(problem) => {
+                    problems.push(format!("{target}: {problem}"));
+                    continue;
+                }
+            };
+
+            let mut found = None;
+            for name in &candidates {
+                if client
+                    .find_asset_in_release(release.id, name)
+                    .await?
+                    .is_some()
+                {
+                    found = Some(name);
+                    break;
+                }
+            }
+            match found {
+                Some(name) => log::info!("{target}: {name} is on the release"),
+                None => problems.push(format!(
+                    "{target}: no asset named {}",
+                    candidates.join(" or ")
+                )),
+            }
+        }
+
+        if !problems.is_empty() {
+            let details = problems
+                .iter()
+                .map(|problem| format!("\n  {problem}"))
+                .collect::<String>();
+            return Err(Error::BinstallAssetsMissing(tag, details));
+        }
+
+        Ok(CIExit::BinstallCheckPassed(tag))
+    }
+}
+
+/// The part of `[package.metadata]` that is read here.
+#[derive(Debug, Clone, Default, Deserialize)]
+struct PackageMetadata {
+    binstall: Option<BinstallMeta>,
+}
+
+/// `[package.metadata.binstall]`, or one target's table under its
+/// `overrides`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BinstallMeta {
+    pkg_url: Option<String>,
+    pkg_fmt: Option<String>,
+    bin_dir: Option<String>,
+    #[serde(default)]
+    overrides: BTreeMap<String, BinstallMeta>,
+}
+
+impl BinstallMeta {
+    /// The settings for `target`: its overrides over the crate-wide settings.
+    fn for_target(&self, target: &str) -> BinstallMeta {
+        let overrides = self.overrides.get(target).cloned().unwrap_or_default();
+        BinstallMeta {
+            pkg_url: overrides.pkg_url.or_else(|| self.pkg_url.clone()),
+            pkg_fmt: overrides.pkg_fmt.or_else(|| self.pkg_fmt.clone()),
+            bin_dir: overrides.bin_dir.or_else(|| self.bin_dir.clone()),
+            overrides: BTreeMap::new(),
+        }
+    }
+}
+
+/// What cargo-binstall knows about the crate when it expands the templates.
+#[derive(Debug, Clone)]
+struct CrateInfo {
+    name: String,
+    version: String,
+    repo: String,
+    bins: Vec<String>,
+}
+
+/// The repository URL as cargo-binstall uses it for `{ repo }`.
+fn normalise_repository(repository: &str) -> String {
+    let repository = repository.trim_end_matches('/');
+    repository
+        .strip_suffix(".git")
+        .unwrap_or(repository)
+        .to_string()
+}
+
+/// The archive suffixes cargo-binstall tries for a `pkg-fmt`, in its order.
+fn archive_suffixes(pkg_fmt: &str) -> Result<&'static [&'static str], String> {
+    match pkg_fmt {
+        "tar" => Ok(&[".tar"]),
+        "tbz2" => Ok(&[".tbz2", ".tar.bz2", ".tbz"]),
+        "tgz" => Ok(&[".tgz", ".tar.gz"]),
+        "txz" => Ok(&[".txz", ".tar.xz"]),
+        "tzstd" => Ok(&[".tzstd", ".tzst", ".tar.zst"]),
+        "zip" => Ok(&[".zip"]),
+        "bin" => Ok(&[".bin", ".exe", ""]),
+        other => Err(format!("unknown pkg-fmt `{other}`")),
+    }
+}
+
+/// The template keys that describe the target triple.
+fn target_vars(target: &str) -> BTreeMap<&'static str, String> {
+    let parts = target.split('-').collect::<Vec<_>>();
+    let family = if target.contains("windows") {
+        "windows"
+    } else if target.starts_with("wasm") {
+        "wasm"
+    } else {
+        "unix"
+    };
+    let libc = parts
+        .last()
+        .filter(|env| ["gnu", "musl", "msvc"].iter().any(|l| env.starts_with(l)))
+        .map_or("unknown", |env| env);
+
+    BTreeMap::from([
+        ("target", target.to_string()),
+        ("target-arch", parts[0].to_string()),
+        (
+            "target-vendor",
+            parts.get(1).copied().unwrap_or("unknown").to_string(),
+        ),
+        ("target-family", family.to_string()),
+        ("target-libc", libc.to_string()),
+        (
+            "binary-ext",
+            if family == "windows" { ".exe" } else { "" }.to_string(),
+        ),
+    ])
+}
+
+/// Expand a cargo-binstall template: `{ key }`, with or without the spaces.
+fn render(template: &str, vars: &BTreeMap<&str, String>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed `{{` in `{template}`"))?;
+        let key = rest[start + 1..start + end].trim();
+        let value = vars
+            .get(key)
+            .ok_or_else(|| format!("unknown key `{key}` in `{template}`"))?;
+        out.push_str(value);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+/// The asset names `pkg-url` can resolve to for `target`, one per archive
+/// suffix of its `pkg-fmt`. The URL must download from the release, i.e.
+/// start with `download_url`.
+fn asset_candidates(
+    meta: &BinstallMeta,
+    crate_info: &CrateInfo,
+    target: &str,
+    download_url: &str,
+) -> Result<Vec<String>, String> {
+    let pkg_url = meta
+        .pkg_url
+        .as_deref()
+        .ok_or("no pkg-url, so cargo-binstall would guess the asset name")?;
+    let suffixes = archive_suffixes(meta.pkg_fmt.as_deref().unwrap_or("tgz"))?;
+
+    let mut vars = target_vars(target);
+    vars.insert("name", crate_info.name.clone());
+    vars.insert("version", crate_info.version.clone());
+    vars.insert("repo", crate_info.repo.clone());
+
+    let mut candidates = Vec::new();
+    for suffix in suffixes {
+        vars.insert("archive-suffix", suffix.to_string());
+        vars.insert("archive-format", suffix.trim_start_matches('.').to_string());
+
+        if let Some(bin_dir) = &meta.bin_dir {
+            for bin in &crate_info.bins {
+                vars.insert("bin", bin.clone());
+                render(bin_dir, &vars)?;
+            }
+        }
+
+        let url = render(pkg_url, &vars)?;
+        let name = url.strip_prefix(download_url).ok_or_else(|| {
+            format!("pkg-url expands to {url}, which is not an asset of the release")
+        })?;
+        if !candidates.iter().any(|c| c == name) {
+            candidates.push(name.to_string());
+        }
+    }
+
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const DOWNLOAD_URL: &str = "https://github.com/jerus-org/pcu/releases/download/pcu-v1.2.0/";
+
+    fn crate_info() -> CrateInfo {
+        CrateInfo {
+            name: "pcu".to_string(),
+            version: "1.2.0".to_string(),
+            repo: normalise_repository("https://github.com/jerus-org/pcu.git"),
+            bins: vec!["pcu".to_string()],
+        }
+    }
+
+    fn binstall(toml: &str) -> BinstallMeta {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[rstest]
+    #[case::linux("x86_64-unknown-linux-gnu", vec!["pcu-x86_64-unknown-linux-gnu.tgz", "pcu-x86_64-unknown-linux-gnu.tar.gz"])]
+    #[case::windows_override("x86_64-pc-windows-msvc", vec!["pcu-x86_64-pc-windows-msvc.zip"])]
+    fn test_asset_candidates(#[case] target: &str, #[case] expected: Vec<&str>) {
+        let meta = binstall(
+            r#"
+pkg-url = "{ repo }/releases/download/{ name }-v{ version }/{ name }-{ target }{ archive-suffix }"
+bin-dir = "{ bin }{ binary-ext }"
+
+[overrides.x86_64-pc-windows-msvc]
+pkg-fmt = "zip"
+"#,
+        );
+
+        let candidates = asset_candidates(
+            &meta.for_target(target),
+            &crate_info(),
+            target,
+            DOWNLOAD_URL,
+        )
+        .unwrap();
+        assert_eq!(candidates, expected);
+    }
+
+    #[rstest]
+    #[case::unknown_key(r#"pkg-url = "{ repo }/releases/download/v{ version }/{ nmae }.tgz""#)]
+    #[case::wrong_tag(r#"pkg-url = "{ repo }/releases/download/v{ version }/{ name }.tgz""#)]
+    #[case::unknown_format(
+        r#"pkg-url = "{ repo }/releases/download/{ name }-v{ version }/{ name }{ archive-suffix }"
+pkg-fmt = "rar""#
+    )]
+    #[case::no_pkg_url(r#"bin-dir = "{ bin }""#)]
+    fn test_asset_candidates_reports_broken_templates(#[case] toml: &str) {
+        let meta = binstall(toml);
+        let result = asset_candidates(
+            &meta.for_target("x86_64-unknown-linux-gnu"),
+            &crate_info(),
+            "x86_64-unknown-linux-gnu",
+            DOWNLOAD_URL,
+        );
+        assert!(result.is_err(), "{result:?}");
+    }
+
+    #[test]
+    fn test_target_vars() {
+        let vars = target_vars("aarch64-unknown-linux-musl");
+        assert_eq!(vars["target-arch"], "aarch64");
+        assert_eq!(vars["target-family"], "unix");
+        assert_eq!(vars["target-libc"], "musl");
+        assert_eq!(vars["binary-ext"], "");
+
+        let vars = target_vars("x86_64-pc-windows-msvc");
+        assert_eq!(vars["target-vendor"], "pc");
+        assert_eq!(vars["binary-ext"], ".exe");
+    }
+
+    #[test]
+    fn test_manifest_metadata_is_read_from_cargo_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(
+            &path,
+            r#"
+[package]
+name = "pcu"
+version = "1.2.0"
+repository = "https://github.com/jerus-org/pcu"
+
+[package.metadata.binstall]
+pkg-url = "{ repo }/releases/download/v{ version }/{ name }-{ target }.tgz"
+
+[package.metadata.binstall.signing]
+algorithm = "minisign"
+pubkey = "RWTexample"
+"#,
+        )
+        .unwrap();
+
+        let manifest = Manifest::<PackageMetadata>::from_path_with_metadata(&path).unwrap();
+        let package = manifest.package.unwrap();
+        let binstall = package.metadata.unwrap().binstall.unwrap();
+        assert!(binstall.pkg_url.is_some());
+        assert_eq!(
+            manifest
+                .bin
+                .iter()
+                .filter_map(|bin| bin.name.clone())
+                .collect::<Vec<_>>(),
+            vec!["pcu"]
+        );
+    }
+}
@@ -0,0 +1,707 @@
+//! Where `pcu release attest` gets its signing identity and build metadata.
+//!
+//! Each CI provider hands out an OIDC token with the `sigstore` audience in
+//! its own way, and describes the build in its own variables. The provider
+//! is detected from the environment, so the same `pcu release attest` line
+//! works on CircleCI, GitHub Actions and GitLab. Anywhere else, an env file
+//! supplies both.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::Error;
+
+/// Audience Fulcio expects of the identity token.
+const AUDIENCE: &str = "sigstore";
+/// Environment variable naming an env file, as `--env-file` does.
+pub(super) const ENV_FILE_ENV: &str = "PCU_ATTEST_ENV_FILE";
+
+/// What the provenance records about the build that is attested.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct BuildInfo {
+    /// Identity of the build platform
+    pub(super) builder_id: String,
+    /// URL of this run of the build
+    pub(super) invocation_id: String,
+    /// Commit the build ran from
+    pub(super) commit: String,
+    /// CI configuration the build ran from
+    pub(super) entry_point: String,
+    /// Provider variables describing the run
+    pub(super) environment: BTreeMap<String, String>,
+    /// Repository URL as the provider reports it, which the v0.2 document
+    /// has always recorded
+    pub(super) repository_url: Option<String>,
+}
+
+/// A source of the Sigstore identity token and build metadata.
+pub(super) trait CiProvider {
+    /// Name of the provider, for logs.
+    fn name(&self) -> &'static str;
+    /// An OIDC token with the `sigstore` audience.
+    async fn id_token(&self) -> Result<String, Error>;
+    /// The build metadata the provenance records.
+    fn build_info(&self) -> BuildInfo;
+    /// The OIDC issuer and identity pattern that verify what this provider
+    /// signed, for the hint logged after signing.
+    fn identity(&self) -> (String, String);
+}
+
+/// Reads a variable; the process environment outside tests.
+type Env<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// Snapshot of the variables a provider uses.
+fn vars(env: Env, names: &[&str]) -> BTreeMap<String, String> {
+    names
+        .iter()
+        .filter_map(|name| env(name).map(|value| (name.to_string(), value)))
+        .collect()
+}
+
+fn var(vars: &BTreeMap<String, String>, name: &str) -> String {
+    vars.get(name).cloned().unwrap_or_default()
+}
+
+/// The provider `pcu release attest` runs on.
+#[derive(Debug, Clone)]
+pub(super) enum Provider {
+    CircleCi(CircleCi),
+    GitHubActions(GitHubActions),
+    GitLab(GitLab),
+    EnvFile(EnvFile),
+}
+
+impl Provider {
+    /// Detect the provider from the process environment. An env file, named
+    /// by `env_file` or `$PCU_ATTEST_ENV_FILE`, wins over detection.
+    pub(super) fn detect(env_file: Option<&Path>) -> Result<Self, Error> {
+        Self::detect_from(env_file, &|name| std::env::var(name).ok())
+    }
+
+    fn detect_from(env_file: Option<&Path>, env: Env) -> Result<Self, Error> {
+        let env_file = env_file
+            .map(Path::to_path_buf)
+            .or_else(|| env(ENV_FILE_ENV).map(PathBuf::from));
+        if let Some(path) = env_file {
+            return Ok(Self::EnvFile(EnvFile::load(&path)?));
+        }
+
+        let is_set = |name: &str| env(name).is_some_and(|value| value == "true");
+        if is_set("GITHUB_ACTIONS") {
+            Ok(Self::GitHubActions(GitHubActions::from_env(env)))
+        } else if is_set("GITLAB_CI") {
+            Ok(Self::GitLab(GitLab::from_env(env)))
+        } else if is_set("CIRCLECI") || env(CircleCi::TOKEN).is_some() {
+            Ok(Self::CircleCi(CircleCi::from_env(env)))
+        } else {
+            Err(Error::Attestation(format!(
+                "no CI provider detected: attest runs on CircleCI, GitHub Actions or \
+                 GitLab, or with an env file named by --env-file or ${ENV_FILE_ENV}"
+            )))
+        }
+    }
+}
+
+impl CiProvider for Provider {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::CircleCi(p) => p.name(),
+            Self::GitHubActions(p) => p.name(),
+            Self::GitLab(p) => p.name(),
+            Self::EnvFile(p) => p.name(),
+        }
+    }
+
+    async fn id_token(&self) -> Result<String, Error> {
+        match self {
+            Self::CircleCi(p) => p.id_token().await,
+            Self::GitHubActions(p) => p.id_token().await,
+            Self::GitLab(p) => p.id_token().await,
+            Self::EnvFile(p) => p.id_token().await,
+        }
+    }
+
+    fn build_info(&self) -> BuildInfo {
+        match self {
+            Self::CircleCi(p) => p.build_info(),
+            Self::GitHubActions(p) => p.build_info(),
+            Self::GitLab(p) => p.build_info(),
+            Self::EnvFile(p) => p.build_info(),
+        }
+    }
+
+    fn identity(&self) -> (String, String) {
+        match self {
+            Self::CircleCi(p) => p.identity(),
+            Self::GitHubActions(p) => p.identity(),
+            Self::GitLab(p) => p.identity(),
+            Self::EnvFile(p) => p.identity(),
+        }
+    }
+}
+
+/// CircleCI: the token is `$CIRCLE_OIDC_TOKEN_V2`, which must be minted with
+/// the `sigstore` audience.
+#[derive(Debug, Clone)]
+pub(super) struct CircleCi {
+    vars: BTreeMap<String, String>,
+}
+
+impl CircleCi {
+    const TOKEN: &str = "CIRCLE_OIDC_TOKEN_V2";
+    const ENVIRONMENT: [&str; 4] = [
+        "CIRCLE_BUILD_URL",
+        "CIRCLE_WORKFLOW_ID",
+        "CIRCLE_PROJECT_USERNAME",
+        "CIRCLE_PROJECT_REPONAME",
+    ];
+
+    fn from_env(env: Env) -> Self {
+        let mut names = vec![Self::TOKEN, "CIRCLE_SHA1", "CIRCLE_REPOSITORY_URL"];
+        names.extend(Self::ENVIRONMENT);
+        Self {
+            vars: vars(env, &names),
+        }
+    }
+}
+
+impl CiProvider for CircleCi {
+    fn name(&self) -> &'static str {
+        "CircleCI"
+    }
+
+    async fn id_token(&self) -> Result<String, Error> {
+        self.vars.get(Self::TOKEN).cloned().ok_or_else(|| {
+            Error::Attestation(format!(
+                "{} is not set. Set it to a CircleCI OIDC token with audience '{AUDIENCE}'. \
+                 Use `circleci run oidc get --claims '{{\"aud\":\"{AUDIENCE}\"}}'` to obtain one.",
+                Self::TOKEN
+            ))
+        })
+    }
+
+    fn build_info(&self) -> BuildInfo {
+        BuildInfo {
+            builder_id: format!(
+                "https://circleci.com/gh/{}/{}",
+                var(&self.vars, "CIRCLE_PROJECT_USERNAME"),
+                var(&self.vars, "CIRCLE_PROJECT_REPONAME")
+            ),
+            invocation_id: var(&self.vars, "CIRCLE_BUILD_URL"),
+            commit: var(&self.vars, "CIRCLE_SHA1"),
+            entry_point: ".circleci/release.yml".to_string(),
+            environment: Self::ENVIRONMENT
+                .iter()
+                .map(|name| (name.to_string(), var(&self.vars, name)))
+                .collect(),
+            repository_url: self.vars.get("CIRCLE_REPOSITORY_URL").cloned(),
+        }
+    }
+
+    fn identity(&self) -> (String, String) {
+        (
+            "https://oidc.circleci.com/org/<ORG_ID>".to_string(),
+            format!(
+                "https://circleci.com/gh/{}/.*",
+                var(&self.vars, "CIRCLE_PROJECT_USERNAME")
+            ),
+        )
+    }
+}
+
+/// GitHub Actions: the token is requested from `$ACTIONS_ID_TOKEN_REQUEST_URL`,
+/// which the job gets with `permissions: id-token: write`.
+#[derive(Debug, Clone)]
+pub(super) struct GitHubActions {
+    vars: BTreeMap<String, String>,
+}
+
+impl GitHubActions {
+    const ENVIRONMENT: [&str; 6] = [
+        "GITHUB_REPOSITORY",
+        "GITHUB_REF",
+        "GITHUB_WORKFLOW",
+        "GITHUB_RUN_ID",
+        "GITHUB_RUN_ATTEMPT",
+        "GITHUB_EVENT_NAME",
+    ];
+
+    fn from_env(env: Env) -> Self {
+        let mut names = vec![
+            "ACTIONS_ID_TOKEN_REQUEST_URL",
+            "ACTIONS_ID_TOKEN_REQUEST_TOKEN",
+            "GITHUB_SERVER_URL",
+            "GITHUB_SHA",
+            "GITHUB_WORKFLOW_REF",
+        ];
+        names.extend(Self::ENVIRONMENT);
+        Self {
+            vars: vars(env, &names),
+        }
+    }
+
+    fn server_url(&self) -> String {
+        self.vars
+            .get("GITHUB_SERVER_URL")
+            .cloned()
+            .unwrap_or_else(|| "https://github.com".to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct GitHubIdToken {
+    value: String,
+}
+
+impl CiProvider for GitHubActions {
+    fn name(&self) -> &'static str {
+        "GitHub Actions"
+    }
+
+    async fn id_token(&self) -> Result<String, Error> {
+        let (Some(url), Some(token)) = (
+            self.vars.get("ACTIONS_ID_TOKEN_REQUEST_URL"),
+            self.vars.get("ACTIONS_ID_TOKEN_REQUEST_TOKEN"),
+        ) else {
+            return Err(Error::Attestation(
+                "ACTIONS_ID_TOKEN_REQUEST_URL is not set. \
+                 Give the job `permissions: id-token: write`."
+                    .to_string(),
+            ));
+        };
+
+        let response = reqwest::Client::new()
+            .get(url)
+            .query(&[("audience", AUDIENCE)])
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| Error::Attestation(format!("OIDC token request failed: {e}")))?;
+        if !response.status().is_success() {
+            return Err(Error::Attestation(format!(
+                "OIDC token request failed: HTTP {}",
+                response.status()
+            )));
+        }
+
+        response
+            .json::<GitHubIdToken>()
+            .await
+            .map(|token| token.value)
+            .map_err(|e| Error::Attestation(format!("malformed OIDC token response: {e}")))
+    }
+
+    fn build_info(&self) -> BuildInfo {
+        let server = self.server_url();
+        BuildInfo {
+            builder_id: format!("{server}/{}", var(&self.vars, "GITHUB_WORKFLOW_REF")),
+            invocation_id: format!(
+                "{server}/{}/actions/runs/{}/attempts/{}",
+                var(&self.vars, "GITHUB_REPOSITORY"),
+                var(&self.vars, "GITHUB_RUN_ID"),
+                var(&self.vars, "GITHUB_RUN_ATTEMPT")
+            ),
+            commit: var(&self.vars, "GITHUB_SHA"),
+            entry_point: var(&self.vars, "GITHUB_WORKFLOW_REF"),
+            environment: Self::ENVIRONMENT
+                .iter()
+                .map(|name| (name.to_string(), var(&self.vars, name)))
+                .collect(),
+            repository_url: None,
+        }
+    }
+
+    fn identity(&self) -> (String, String) {
+        (
+            "https://token.actions.githubusercontent.com".to_string(),
+            format!(
+                "{}/{}/.*",
+                self.server_url(),
+                var(&self.vars, "GITHUB_REPOSITORY")
+            ),
+        )
+    }
+}
+
+/// GitLab: the token is `$SIGSTORE_ID_TOKEN`, declared in the job's
+/// `id_tokens` with `aud: sigstore`.
+#[derive(Debug, Clone)]
+pub(super) struct GitLab {
+    vars: BTreeMap<String, String>,
+}
+
+impl GitLab {
+    const TOKEN: &str = "SIGSTORE_ID_TOKEN";
+    const ENVIRONMENT: [&str; 5] = [
+        "CI_PROJECT_PATH",
+        "CI_PIPELINE_ID",
+        "CI_JOB_ID",
+        "CI_COMMIT_REF_NAME",
+        "CI_RUNNER_ID",
+    ];
+
+    fn from_env(env: Env) -> Self {
+        let mut names = vec![
+            Self::TOKEN,
+            "CI_SERVER_URL",
+            "CI_JOB_URL",
+            "CI_COMMIT_SHA",
+            "CI_CONFIG_PATH",
+        ];
+        names.extend(Self::ENVIRONMENT);
+        Self {
+            vars: vars(env, &names),
+        }
+    }
+
+    fn project_url(&self) -> String {
+        format!(
+            "{}/{}",
+            var(&self.vars, "CI_SERVER_URL"),
+            var(&self.vars, "CI_PROJECT_PATH")
+        )
+    }
+}
+
+impl CiProvider for GitLab {
+    fn name(&self) -> &'static str {
+        "GitLab"
+    }
+
+    async fn id_token(&self) -> Result<String, Error> {
+        self.vars.get(Self::TOKEN).cloned().ok_or_else(|| {
+            Error::Attestation(format!(
+                "{} is not set. Declare it under the job's `id_tokens` with `aud: {AUDIENCE}`.",
+                Self::TOKEN
+            ))
+        })
+    }
+
+    fn build_info(&self) -> BuildInfo {
+        BuildInfo {
+            builder_id: format!(
+                "{}/-/runners/{}",
+                self.project_url(),
+                var(&self.vars, "CI_RUNNER_ID")
+            ),
+            invocation_id: var(&self.vars, "CI_JOB_URL"),
+            commit: var(&self.vars, "CI_COMMIT_SHA"),
+            entry_point: self
+                .vars
+                .get("CI_CONFIG_PATH")
+                .cloned()
+                .unwrap_or_else(|| ".gitlab-ci.yml".to_string()),
+            environment: Self::ENVIRONMENT
+                .iter()
+                .map(|name| (name.to_string(), var(&self.vars, name)))
+                .collect(),
+            repository_url: None,
+        }
+    }
+
+    fn identity(&self) -> (String, String) {
+        (
+            var(&self.vars, "CI_SERVER_URL"),
+            format!("{}//.*", self.project_url()),
+        )
+    }
+}
+
+/// Any other CI: `KEY=value` lines holding `ID_TOKEN` and the build
+/// metadata. `BUILDER_ID`, `INVOCATION_ID`, `COMMIT`, `ENTRY_POINT`,
+/// `OIDC_ISSUER` and `IDENTITY` have their own places; every other key is
+/// recorded as the build's environment.
+#[derive(Debug, Clone)]
+pub(super) struct EnvFile {
+    vars: BTreeMap<String, String>,
+}
+
+impl EnvFile {
+    const KEYS: [&str; 7] = [
+        "ID_TOKEN",
+        "BUILDER_ID",
+        "INVOCATION_ID",
+        "COMMIT",
+        "ENTRY_POINT",
+        "OIDC_ISSUER",
+        "IDENTITY",
+    ];
+
+    fn load(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            Error::Attestation(format!("cannot read env file {}: {e}", path.display()))
+        })?;
+        Ok(Self::parse(&content))
+    }
+
+    fn parse(content: &str) -> Self {
+        let vars = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.strip_prefix("export ").unwrap_or(line).split_once('='))
+            .map(|(key, value)| {
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                (key.trim().to_string(), value.to_string())
+            })
+            .collect();
+        Self { vars }
+    }
+}
+
+impl CiProvider for EnvFile {
+    fn name(&self) -> &'static str {
+        "env file"
+    }
+
+    async fn id_token(&self) -> Result<String, Error> {
+        self.vars
+            .get("ID_TOKEN")
+            .cloned()
+            .ok_or_else(|| Error::Attestation("the env file has no ID_TOKEN".to_string()))
+    }
+
+    fn build_info(&self) -> BuildInfo {
+        BuildInfo {
+            builder_id: var(&self.vars, "BUILDER_ID"),
+            invocation_id: var(&self.vars, "INVOCATION_ID"),
+            commit: var(&self.vars, "COMMIT"),
+            entry_point: var(&self.vars, "ENTRY_POINT"),
+            environment: self
+                .vars
+                .iter()
+                .filter(|(key, _)| !Self::KEYS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            repository_url: None,
+        }
+    }
+
+    fn identity(&self) -> (String, String) {
+        let or = |key, placeholder: &str| {
+            self.vars
+                .get(key)
+                .cloned()
+                .unwrap_or_else(|| placeholder.to_string())
+        };
+        (or("OIDC_ISSUER", "<ISSUER>"), or("IDENTITY", "<IDENTITY>"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use wiremock::{
+        matchers::{header, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn detect(vars: &[(&str, &str)]) -> Result<Provider, Error> {
+        Provider::detect_from(None, &env(vars))
+    }
+
+    #[test]
+    fn test_detects_the_provider_from_its_variables() {
+        let name = |vars: &[(&str, &str)]| detect(vars).unwrap().name();
+
+        assert_eq!(name(&[("CIRCLECI", "true")]), "CircleCI");
+        assert_eq!(name(&[("CIRCLE_OIDC_TOKEN_V2", "jwt")]), "CircleCI");
+        assert_eq!(name(&[("GITHUB_ACTIONS", "true")]), "GitHub Actions");
+        assert_eq!(name(&[("GITLAB_CI", "true")]), "GitLab");
+        assert!(matches!(detect(&[]), Err(Error::Attestation(_))));
+    }
+
+    #[test]
+    fn test_env_file_wins_over_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("attest.env");
+        fs::write(&file, "ID_TOKEN=jwt\n").unwrap();
+        let file = file.to_string_lossy().into_owned();
+
+        let provider = detect(&[("CIRCLECI", "true"), (ENV_FILE_ENV, &file)]).unwrap();
+
+        assert_eq!(provider.name(), "env file");
+    }
+
+    #[tokio::test]
+    async fn test_circleci_token_and_build_info() {
+        let provider = CircleCi::from_env(&env(&[
+            ("CIRCLE_OIDC_TOKEN_V2", "some-token"),
+            ("CIRCLE_SHA1", "cafe"),
+            ("CIRCLE_REPOSITORY_URL", "git@github.com:jerus-org/pcu.git"),
+            (
+                "CIRCLE_BUILD_URL",
+                "https://circleci.com/gh/jerus-org/pcu/42",
+            ),
+            ("CIRCLE_PROJECT_USERNAME", "jerus-org"),
+            ("CIRCLE_PROJECT_REPONAME", "pcu"),
+        ]));
+
+        assert_eq!(provider.id_token().await.unwrap(), "some-token");
+        let info = provider.build_info();
+        assert_eq!(info.builder_id, "https://circleci.com/gh/jerus-org/pcu");
+        assert_eq!(
+            info.invocation_id,
+            "https://circleci.com/gh/jerus-org/pcu/42"
+        );
+        assert_eq!(info.commit, "cafe");
+        assert_eq!(info.environment.len(), 4);
+        assert_eq!(
+            info.repository_url.as_deref(),
+            Some("git@github.com:jerus-org/pcu.git")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_circleci_token_error_names_the_variable() {
+        let provider = CircleCi::from_env(&env(&[("CIRCLECI", "true")]));
+
+        let msg = provider.id_token().await.unwrap_err().to_string();
+
+        assert!(msg.contains("CIRCLE_OIDC_TOKEN_V2"), "{msg}");
+    }
+
+    #[tokio::test]
+    async fn test_github_actions_requests_a_sigstore_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .and(query_param("api-version", "2.0"))
+            .and(query_param("audience", "sigstore"))
+            .and(header("authorization", "Bearer request-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "count": 1,
+                "value": "github-jwt"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let url = format!("{}/token?api-version=2.0", server.uri());
+        let provider = GitHubActions::from_env(&env(&[
+            ("ACTIONS_ID_TOKEN_REQUEST_URL", &url),
+            ("ACTIONS_ID_TOKEN_REQUEST_TOKEN", "request-token"),
+        ]));
+
+        assert_eq!(provider.id_token().await.unwrap(), "github-jwt");
+    }
+
+    #[tokio::test]
+    async fn test_github_actions_reports_a_refused_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&server)
+            .await;
+
+        let url = format!("{}/token", server.uri());
+        let provider = GitHubActions::from_env(&env(&[
+            ("ACTIONS_ID_TOKEN_REQUEST_URL", &url),
+            ("ACTIONS_ID_TOKEN_REQUEST_TOKEN", "request-token"),
+        ]));
+
+        let msg = provider.id_token().await.unwrap_err().to_string();
+        assert!(msg.contains("403"), "{msg}");
+    }
+
+    #[tokio::test]
+    async fn test_github_actions_without_id_token_permission() {
+        let provider = GitHubActions::from_env(&env(&[("GITHUB_ACTIONS", "true")]));
+
+        let msg = provider.id_token().await.unwrap_err().to_string();
+
+        assert!(msg.contains("id-token: write"), "{msg}");
+    }
+
+    #[test]
+    fn test_github_actions_build_info() {
+        let provider = GitHubActions::from_env(&env(&[
+            ("GITHUB_REPOSITORY", "jerus-org/pcu"),
+            (
+                "GITHUB_WORKFLOW_REF",
+                "jerus-org/pcu/.github/workflows/release.yml@refs/tags/v1",
+            ),
+            ("GITHUB_SHA", "cafe"),
+            ("GITHUB_RUN_ID", "7"),
+            ("GITHUB_RUN_ATTEMPT", "2"),
+        ]));
+
+        let info = provider.build_info();
+
+        assert_eq!(
+            info.builder_id,
+            "https://github.com/jerus-org/pcu/.github/workflows/release.yml@refs/tags/v1"
+        );
+        assert_eq!(
+            info.invocation_id,
+            "https://github.com/jerus-org/pcu/actions/runs/7/attempts/2"
+        );
+        assert_eq!(info.commit, "cafe");
+    }
+
+    #[tokio::test]
+    async fn test_gitlab_token_and_build_info() {
+        let provider = GitLab::from_env(&env(&[
+            ("SIGSTORE_ID_TOKEN", "gitlab-jwt"),
+            ("CI_SERVER_URL", "https://gitlab.com"),
+            ("CI_PROJECT_PATH", "jerus-org/pcu"),
+            ("CI_RUNNER_ID", "12"),
+            ("CI_JOB_URL", "https://gitlab.com/jerus-org/pcu/-/jobs/3"),
+            ("CI_COMMIT_SHA", "cafe"),
+        ]));
+
+        assert_eq!(provider.id_token().await.unwrap(), "gitlab-jwt");
+        let info = provider.build_info();
+        assert_eq!(
+            info.builder_id,
+            "https://gitlab.com/jerus-org/pcu/-/runners/12"
+        );
+        assert_eq!(
+            info.invocation_id,
+            "https://gitlab.com/jerus-org/pcu/-/jobs/3"
+        );
+        assert_eq!(info.entry_point, ".gitlab-ci.yml");
+    }
+
+    #[tokio::test]
+    async fn test_env_file() {
+        let provider = EnvFile::parse(
+            "# attestation\n\
+             export ID_TOKEN=file-jwt\n\
+             BUILDER_ID=\"https://ci.example/builder\"\n\
+             COMMIT=cafe\n\
+             RUN_NUMBER=9\n",
+        );
+
+        assert_eq!(provider.id_token().await.unwrap(), "file-jwt");
+        assert_eq!(
+            provider.build_info(),
+            BuildInfo {
+                builder_id: "https://ci.example/builder".to_string(),
+                commit: "cafe".to_string(),
+                environment: BTreeMap::from([("RUN_NUMBER".to_string(), "9".to_string())]),
+                ..Default::default()
+            }
+        );
+    }
+}
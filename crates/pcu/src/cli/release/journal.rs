@@ -0,0 +1,309 @@
+//! Release journal: a JSON file recording, per tag, which steps of a release
+//! pipeline have run and how they ended.
+//!
+//! A release runs as separate CI steps — `version`, `upload-asset`,
+//! `inject-pubkey`, `sign`, `sbom`, `attest`, `publish` — and each step used to
+//! re-derive where the release had got to from GitHub. With `--journal <PATH>` (or
+//! `$PCU_RELEASE_JOURNAL`) every step records its command line when it starts
+//! and its outcome when it ends, so `pcu release status` can show a broken
+//! release at a glance and `pcu release resume` can run what is left.
+//!
+//! Journalling is opt-in: the file is written as the steps run, and a release
+//! step that commits changed files must not pick up a journal kept in the
+//! working tree by default.
+
+use std::{collections::BTreeMap, fmt::Display, fs, path::Path};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// Environment variable naming the journal when `--journal` is not given.
+pub(crate) const JOURNAL_ENV: &str = "PCU_RELEASE_JOURNAL";
+
+/// A step of the release pipeline, in the order the pipeline runs them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Step {
+    Version,
+    UploadAsset,
+    InjectPubkey,
+    Sign,
+    Sbom,
+    Attest,
+    Publish,
+}
+
+impl Step {
+    pub(crate) const ALL: [Step; 7] = [
+        Step::Version,
+        Step::UploadAsset,
+        Step::InjectPubkey,
+        Step::Sign,
+        Step::Sbom,
+        Step::Attest,
+        Step::Publish,
+    ];
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::Version => write!(f, "version"),
+            Step::UploadAsset => write!(f, "upload-asset"),
+            Step::InjectPubkey => write!(f, "inject-pubkey"),
+            Step::Sign => write!(f, "sign"),
+            Step::Sbom => write!(f, "sbom"),
+            Step::Attest => write!(f, "attest"),
+            Step::Publish => write!(f, "publish"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum StepStatus {
+    /// The step started and has not recorded an outcome: it is still running,
+    /// or its job was killed part-way.
+    Started,
+    Done,
+    Failed,
+}
+
+impl Display for StepStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StepStatus::Started => write!(f, "started"),
+            StepStatus::Done => write!(f, "done"),
+            StepStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// One run of a step for a tag.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct StepRecord {
+    pub(crate) step: Step,
+    /// Tells apart several runs of the same step, e.g. the asset name of each
+    /// `upload-asset`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) label: Option<String>,
+    /// The pcu command line of the step, without the program name, so that
+    /// `resume` can run it again.
+    pub(crate) args: Vec<String>,
+    pub(crate) status: StepStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    pub(crate) updated: DateTime<Utc>,
+}
+
+impl StepRecord {
+    fn name(&self) -> String {
+        match &self.label {
+            Some(label) => format!("{} ({label})", self.step),
+            None => self.step.to_string(),
+        }
+    }
+}
+
+/// The step records of every tag in the journal.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct Journal {
+    releases: BTreeMap<String, Vec<StepRecord>>,
+}
+
+impl Journal {
+    /// Read the journal at `path`; a journal not yet written is empty.
+    pub(crate) fn load(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Record the latest state of `step` for `tag`, replacing any earlier
+    /// record of the same step and label.
+    pub(crate) fn record(
+        &mut self,
+        tag: &str,
+        step: Step,
+        label: Option<String>,
+        args: Vec<String>,
+        status: StepStatus,
+        error: Option<String>,
+    ) {
+        let record = StepRecord {
+            step,
+            label,
+            args,
+            status,
+            error,
+            updated: Utc::now(),
+        };
+
+        let records = self.releases.entry(tag.to_string()).or_default();
+        match records
+            .iter_mut()
+            .find(|r| r.step == record.step && r.label == record.label)
+        {
+            Some(existing) => *existing = record,
+            None => records.push(record),
+        }
+        records.sort_by(|a, b| a.step.cmp(&b.step).then_with(|| a.label.cmp(&b.label)));
+    }
+
+    pub(crate) fn steps(&self, tag: &str) -> &[StepRecord] {
+        self.releases
+            .get(tag)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The recorded steps for `tag` that have not completed, in pipeline
+    /// order.
+    pub(crate) fn unfinished(&self, tag: &str) -> Vec<&StepRecord> {
+        self.steps(tag)
+            .iter()
+            .filter(|r| r.status != StepStatus::Done)
+            .collect()
+    }
+
+    /// One line per step of the pipeline: each recorded run with its status,
+    /// and `pending` for a step with no record.
+    pub(crate) fn status_report(&self, tag: &str) -> String {
+        let records = self.steps(tag);
+        let mut lines = vec![format!("Release {tag}:")];
+
+        for step in Step::ALL {
+            let mut runs = records.iter().filter(|r| r.step == step).peekable();
+            if runs.peek().is_none() {
+                lines.push(format!("  {:<24} pending", step.to_string()));
+                continue;
+            }
+            for run in runs {
+                let mut line = format!("  {:<24} {}", run.name(), run.status);
+                if let Some(error) = &run.error {
+                    line.push_str(&format!(": {error}"));
+                }
+                lines.push(line);
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_record_replaces_an_earlier_run_of_the_step() {
+        let mut journal = Journal::default();
+        let version_args = args("release version 1.2.0");
+        journal.record(
+            "v1.2.0",
+            Step::Version,
+            None,
+            version_args.clone(),
+            StepStatus::Started,
+            None,
+        );
+        journal.record(
+            "v1.2.0",
+            Step::Version,
+            None,
+            version_args,
+            StepStatus::Done,
+            None,
+        );
+
+        let steps = journal.steps("v1.2.0");
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].status, StepStatus::Done);
+    }
+
+    #[test]
+    fn test_status_report_and_unfinished_steps() {
+        let mut journal = Journal::default();
+        journal.record(
+            "v1.2.0",
+            Step::Publish,
+            None,
+            args("release publish --tag v1.2.0"),
+            StepStatus::Failed,
+            Some("no GitHub release found".to_string()),
+        );
+        journal.record(
+            "v1.2.0",
+            Step::UploadAsset,
+            Some("pcu.tar.gz".to_string()),
+            args("release upload-asset --tag v1.2.0 --asset-path pcu.tar.gz"),
+            StepStatus::Started,
+            None,
+        );
+        journal.record(
+            "v1.2.0",
+            Step::Version,
+            None,
+            args("release version 1.2.0"),
+            StepStatus::Done,
+            None,
+        );
+
+        let expected = [
+            "Release v1.2.0:",
+            "  version                  done",
+            "  upload-asset (pcu.tar.gz) started",
+            "  inject-pubkey            pending",
+            "  sign                     pending",
+            "  sbom                     pending",
+            "  attest                   pending",
+            "  publish                  failed: no GitHub release found",
+        ]
+        .join("\n");
+        assert_eq!(journal.status_report("v1.2.0"), expected);
+
+        let unfinished = journal
+            .unfinished("v1.2.0")
+            .iter()
+            .map(|r| r.step)
+            .collect::<Vec<_>>();
+        assert_eq!(unfinished, vec![Step::UploadAsset, Step::Publish]);
+    }
+
+    #[test]
+    fn test_journal_round_trips_through_its_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal/release.json");
+        assert_eq!(Journal::load(&path).unwrap(), Journal::default());
+
+        let mut journal = Journal::default();
+        journal.record(
+            "pcu-v0.7.0",
+            Step::Attest,
+            None,
+            args("release attest pcu --crate-tag-prefix pcu-v"),
+            StepStatus::Done,
+            None,
+        );
+        journal.save(&path).unwrap();
+
+        assert_eq!(Journal::load(&path).unwrap(), journal);
+    }
+}
@@ -2,6 +2,10 @@
 
 use clap::{Parser, Subcommand};
 
+use super::notes::NotesFormat;
+#[cfg(feature = "attest")]
+use super::provenance::ProvenanceFormat;
+
 #[derive(Debug, Parser, Clone)]
 pub struct Version {
     /// Specific package to release
@@ -63,31 +67,108 @@ pub struct InjectPubkey {
     pub no_github_release: bool,
 }
 
-/// Attest a published crate with SLSA v0.2 provenance, signed via Sigstore
-/// keyless (CircleCI OIDC → Fulcio → Rekor), and upload the bundle to the
-/// GitHub release.
+/// Check that every asset cargo-binstall would download, as named by
+/// `pkg-url` and `pkg-fmt` in `[package.metadata.binstall]` and its per-target
+/// overrides, exists on the (draft) GitHub release.
+#[derive(Debug, Parser, Clone)]
+pub struct CheckBinstall {
+    /// Package whose metadata is checked (locates crates/`<package>`/Cargo.toml)
+    pub package: String,
+    /// Version of the release (reads $SEMVER or $NEXT_VERSION if not provided,
+    /// else the version in Cargo.toml)
+    #[arg(short, long)]
+    pub version: Option<String>,
+    /// Git tag of the release (default: from the tag template, or
+    /// `<package>`-v`<version>`)
+    #[arg(long)]
+    pub tag: Option<String>,
+    /// Target triple to check; may be repeated (default: the targets under
+    /// `[package.metadata.binstall.overrides]`)
+    #[arg(long)]
+    pub target: Vec<String>,
+}
+
+/// Attest a published crate, or local release artifacts, with SLSA v1
+/// provenance, signed via Sigstore keyless (CI OIDC → Fulcio → Rekor),
+/// and upload the bundle to the GitHub release.
 ///
 /// Gated on the `attest` feature: compiled out, the subcommand does not exist,
 /// and neither do `openidconnect`, `sigstore` or `rsa`.
 ///
-/// Requires CIRCLE_OIDC_TOKEN_V2 with audience "sigstore" in the environment.
+/// The identity token and build metadata come from the CI provider, detected
+/// from the environment: CircleCI, GitHub Actions or GitLab, or an env file.
 #[cfg(feature = "attest")]
 #[derive(Debug, Parser, Clone)]
 pub struct Attest {
     /// Package name on crates.io (e.g. gen-changelog)
     pub package: String,
-    /// Tag prefix for the GitHub release (e.g. "gen-changelog-v")
+    /// Tag prefix for the GitHub release (e.g. "gen-changelog-v"; default:
+    /// from the tag template, or `<package>`-v)
     #[arg(long)]
-    pub crate_tag_prefix: String,
+    pub crate_tag_prefix: Option<String>,
     /// Version to attest (reads $SEMVER or $NEXT_VERSION if not provided)
     #[arg(short, long)]
     pub version: Option<String>,
     /// Seconds to wait before downloading (allows crates.io indexing after publish)
     #[arg(long, default_value_t = 30)]
     pub crates_io_delay: u64,
-    /// Maximum download retry attempts
+    /// Maximum attempts at downloading the crate and at each upload
     #[arg(long, default_value_t = 5)]
     pub max_attempts: u32,
+    /// Provenance document to write: SLSA v1 in an in-toto Statement, or the
+    /// legacy SLSA v0.2 document
+    #[arg(long, value_enum, default_value_t)]
+    pub provenance_format: ProvenanceFormat,
+    /// Attest these files, directories or glob patterns instead of the crate
+    /// on crates.io; one provenance lists them all and is signed once
+    #[arg(long, num_args = 1..)]
+    pub artifact: Vec<String>,
+    /// Release asset name of the provenance (default:
+    /// `<package>-<version>.provenance.json`, or
+    /// `<package>-<version>-artifacts.provenance.json` with `--artifact`)
+    #[arg(long)]
+    pub provenance_name: Option<String>,
+    /// Env file with the identity token and build metadata, for CI providers
+    /// that are not detected (default: $PCU_ATTEST_ENV_FILE)
+    #[arg(long)]
+    pub env_file: Option<std::path::PathBuf>,
+    /// SBOM written by `sbom --output`, added as a subject of the provenance.
+    /// Needs `--artifact`: only then is the provenance what gets signed
+    #[arg(long, requires = "artifact")]
+    pub sbom: Option<std::path::PathBuf>,
+}
+
+/// Verify an artifact against the Sigstore bundle `attest` made for it, using
+/// a trusted root supplied as a file rather than fetched, so it runs offline.
+///
+/// Checks the Fulcio certificate chain, that the certificate was issued to the
+/// expected identity, the signature, and the Rekor inclusion proof.
+#[cfg(feature = "attest")]
+#[derive(Debug, Parser, Clone)]
+pub struct VerifyAttestation {
+    /// The `.crate`, or any other artifact, that was signed
+    pub artifact: std::path::PathBuf,
+    /// The Sigstore bundle (default: `<artifact>.sigstore.json`)
+    #[arg(long)]
+    pub bundle: Option<std::path::PathBuf>,
+    /// Sigstore trusted-root JSON holding the Fulcio, CT log and Rekor keys
+    #[arg(long)]
+    pub trusted_root: std::path::PathBuf,
+    /// OIDC issuer the signing certificate must name
+    /// (e.g. https://oidc.circleci.com/org/<ORG_ID>)
+    #[arg(long)]
+    pub certificate_oidc_issuer: String,
+    /// Pattern a subject alternative name of the signing certificate must
+    /// match in full (e.g. "https://circleci.com/gh/jerus-org/pcu/.*")
+    #[arg(long)]
+    pub certificate_identity_regexp: String,
+    /// Provenance whose subject must carry the artifact's SHA-256
+    #[arg(long)]
+    pub provenance: Option<std::path::PathBuf>,
+    /// File whose SHA-256 must be a subject of the artifact, when the
+    /// artifact is a provenance signed by `attest --artifact`; may be repeated
+    #[arg(long)]
+    pub subject: Vec<std::path::PathBuf>,
 }
 
 /// Publish a draft GitHub release, making it visible and marking it latest.
@@ -101,20 +182,185 @@ pub struct Publish {
     /// Git tag of the release to publish
     #[arg(long)]
     pub tag: String,
+    /// Crate whose `required_assets` the draft is checked against (default:
+    /// the `default` list)
+    #[arg(long)]
+    pub package: Option<String>,
+    /// Publish even when required assets are missing from the draft
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
 }
 
-/// Upload a binary asset to an existing GitHub release.
+/// Upload files to an existing GitHub release, with a `SHA256SUMS` covering
+/// them.
+///
+/// Files already on the release with the same name and size are skipped, so a
+/// re-run after a partial failure uploads only what is missing.
 #[derive(Debug, Parser, Clone)]
 pub struct UploadAsset {
     /// Git tag for the GitHub release
     #[arg(long)]
     pub tag: String,
-    /// Path to the asset file to upload
-    #[arg(long)]
-    pub asset_path: std::path::PathBuf,
-    /// Name for the asset in the release (default: filename from asset_path)
+    /// Files to upload: a file, a directory (the files directly in it) or a
+    /// glob; takes several values and may be repeated
+    #[arg(long, required = true, num_args = 1..)]
+    pub asset_path: Vec<String>,
+    /// Name for the asset in the release (default: the file name); only for a
+    /// single file
     #[arg(long)]
     pub asset_name: Option<String>,
+    /// How many files to upload at once
+    #[arg(long, default_value_t = 4)]
+    pub concurrency: usize,
+    /// Attempts per file before its upload fails
+    #[arg(long, default_value_t = 3)]
+    pub max_attempts: u32,
+    /// Do not generate and upload `SHA256SUMS`
+    #[arg(long, default_value_t = false)]
+    pub no_checksums: bool,
+}
+
+impl UploadAsset {
+    /// Tells this run apart from other uploads in the release journal:
+    /// `--asset-name`, or else the file names of the asset paths.
+    pub fn label(&self) -> Option<String> {
+        if let Some(name) = &self.asset_name {
+            return Some(name.clone());
+        }
+        let names = self
+            .asset_path
+            .iter()
+            .map(|path| {
+                std::path::Path::new(path)
+                    .file_name()
+                    .map_or_else(|| path.clone(), |n| n.to_string_lossy().into_owned())
+            })
+            .collect::<Vec<_>>();
+        (!names.is_empty()).then(|| names.join(" "))
+    }
+}
+
+/// Sign release assets and the release's `SHA256SUMS` with a minisign secret
+/// key, and upload a `<name>.minisig` next to each.
+///
+/// Refuses to sign unless the key matches the pubkey committed under
+/// `[package.metadata.binstall.signing]`, which is what cargo-binstall checks
+/// the signatures against.
+#[derive(Debug, Parser, Clone)]
+pub struct Sign {
+    /// Package whose Cargo.toml carries the signing pubkey (locates
+    /// crates/`<package>`/Cargo.toml)
+    pub package: String,
+    /// Version of the release (reads $SEMVER or $NEXT_VERSION if not provided)
+    #[arg(short, long)]
+    pub version: Option<String>,
+    /// Git tag of the release (default: from the tag template, or
+    /// `<package>`-v`<version>`)
+    #[arg(long)]
+    pub tag: Option<String>,
+    /// Files to sign, as for upload-asset; the release's SHA256SUMS is always
+    /// signed
+    #[arg(long, num_args = 1..)]
+    pub asset_path: Vec<String>,
+    /// Minisign secret key file (reads the key from $MINISIGN_SECRET_KEY if
+    /// not provided); an encrypted key takes its password from
+    /// $MINISIGN_PASSWORD
+    #[arg(long)]
+    pub secret_key: Option<std::path::PathBuf>,
+    /// How many signatures to upload at once
+    #[arg(long, default_value_t = 4)]
+    pub concurrency: usize,
+    /// Attempts per signature before its upload fails
+    #[arg(long, default_value_t = 3)]
+    pub max_attempts: u32,
+}
+
+/// Generate a CycloneDX SBOM of a package from Cargo.lock and the workspace
+/// manifests, and upload it to the release before it is published.
+///
+/// The SBOM lists every package the package ships with — its dependencies,
+/// not its dev-dependencies — with purls, SHA-256 checksums and, where the
+/// manifests record them, licenses.
+#[derive(Debug, Parser, Clone)]
+pub struct Sbom {
+    /// Workspace package to describe
+    pub package: String,
+    /// Version of the release, which must be the package's version in
+    /// Cargo.lock (reads $SEMVER or $NEXT_VERSION if not provided, else the
+    /// version in Cargo.lock)
+    #[arg(long)]
+    pub version: Option<String>,
+    /// Git tag of the release (default: from the tag template, or
+    /// `<package>`-v`<version>`)
+    #[arg(long)]
+    pub tag: Option<String>,
+    /// Also write the SBOM to this file, e.g. to pass to `attest --sbom`
+    #[arg(long)]
+    pub output: Option<std::path::PathBuf>,
+    /// Only write the SBOM, to --output or standard output, without uploading
+    /// it; runs offline
+    #[arg(long, default_value_t = false)]
+    pub no_upload: bool,
+    /// Attempts before the upload fails
+    #[arg(long, default_value_t = 3)]
+    pub max_attempts: u32,
+}
+
+/// Unwind a release that failed part-way: delete its draft GitHub release and
+/// its local and remote tag, and optionally revert its prlog commit.
+///
+/// Refuses, before touching anything, when the release is published or
+/// immutable, or when the crate version is already on crates.io.
+#[derive(Debug, Parser, Clone)]
+pub struct Abort {
+    /// Package name on crates.io
+    pub package: String,
+    /// Version of the release (reads $SEMVER or $NEXT_VERSION if not provided)
+    #[arg(short, long)]
+    pub version: Option<String>,
+    /// Git tag of the release (default: from the tag template, or
+    /// `<package>`-v`<version>`)
+    #[arg(long)]
+    pub tag: Option<String>,
+    /// Also revert the `chore: update prlog for release <version>` commit and
+    /// push the revert
+    #[arg(long, default_value_t = false)]
+    pub revert_prlog: bool,
+}
+
+/// Render the release notes `make_release` would send to GitHub for a
+/// version, from the local PRLOG and without creating a release.
+#[derive(Debug, Parser, Clone)]
+pub struct Notes {
+    /// Version whose PRLOG section is rendered
+    pub version: String,
+    /// Crate of a workspace release, whose tag names the release
+    #[arg(long)]
+    pub package: Option<String>,
+    /// Output format
+    #[arg(long, value_enum, default_value_t)]
+    pub format: NotesFormat,
+    /// Write the notes to this file instead of standard output
+    #[arg(long)]
+    pub output: Option<std::path::PathBuf>,
+}
+
+/// Show which steps of a release are done, pending or failed, as recorded in
+/// the release journal.
+#[derive(Debug, Parser, Clone)]
+pub struct Status {
+    /// Git tag of the release
+    #[arg(long)]
+    pub tag: String,
+}
+
+/// Run the steps of a release that the release journal does not record as
+/// done, finishing with `publish`.
+#[derive(Debug, Parser, Clone)]
+pub struct Resume {
+    /// Git tag of the release
+    #[arg(long)]
+    pub tag: String,
 }
 
 #[derive(Debug, Subcommand, Clone)]
@@ -129,13 +375,30 @@ pub enum Mode {
     CheckTag(CheckTag),
     /// Inject signing pubkey into Cargo.toml and amend the release commit
     InjectPubkey(InjectPubkey),
-    /// Upload a binary asset to a GitHub release
+    /// Check that the assets named by the cargo-binstall metadata are on the release
+    CheckBinstall(CheckBinstall),
+    /// Upload files, and a SHA256SUMS covering them, to a GitHub release
     UploadAsset(UploadAsset),
+    /// Sign release assets and SHA256SUMS with minisign and upload the signatures
+    Sign(Sign),
+    /// Generate a CycloneDX SBOM from Cargo.lock and upload it to the release
+    Sbom(Sbom),
     /// Publish a draft GitHub release (no-op if already published)
     Publish(Publish),
-    /// Attest a published crate with SLSA provenance via Sigstore keyless signing
+    /// Attest a crate or local artifacts with SLSA provenance via Sigstore keyless signing
     #[cfg(feature = "attest")]
     Attest(Attest),
+    /// Verify a Sigstore bundle, and optionally its provenance, offline
+    #[cfg(feature = "attest")]
+    VerifyAttestation(VerifyAttestation),
+    /// Delete a half-finished release's draft, tags and, optionally, prlog commit
+    Abort(Abort),
+    /// Render the release notes of a version from the local PRLOG
+    Notes(Notes),
+    /// Show the progress of a release from the release journal
+    Status(Status),
+    /// Run the unfinished steps of a release from the release journal
+    Resume(Resume),
 }
 
 // impl FromStr for Mode {
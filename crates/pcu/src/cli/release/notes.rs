@@ -0,0 +1,148 @@
+//! `pcu release notes`: render the release notes of a version from the local
+//! PRLOG, exactly as `make_release` would send them to GitHub, without
+//! creating a release.
+//!
+//! Runs offline: no GitHub client is constructed, so it works on a laptop
+//! without a token as well as in CI.
+
+use std::{fs, path::Path};
+
+use clap::ValueEnum;
+use keep_a_changelog::Changelog;
+use serde::Serialize;
+
+use super::{mode::Notes, Commands, Release};
+use crate::{
+    cli::prlog::prlog_and_options, ops::is_prerelease, utilities::ReleaseNotesProvider, CIExit,
+    Error, TagTemplates,
+};
+
+/// How `pcu release notes` writes the notes.
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NotesFormat {
+    /// The release body, as sent to GitHub
+    #[default]
+    Markdown,
+    /// The name, tag, prerelease flag and body of the release
+    Json,
+    /// The release body rendered to HTML
+    Html,
+}
+
+/// The release fields `make_release` derives from the PRLOG.
+#[derive(Debug, Serialize)]
+struct NotesJson<'a> {
+    name: &'a str,
+    tag_name: &'a str,
+    prerelease: bool,
+    body: &'a str,
+}
+
+impl Release {
+    pub(super) fn notes(&self, cmd: &Notes) -> Result<CIExit, Error> {
+        let settings = Commands::Release(self.clone()).get_settings()?;
+        let templates = TagTemplates::from_settings(&settings)?;
+        let prefix = match &cmd.package {
+            Some(package) => {
+                templates.prefix(Some(package), &format!("{package}-{}", self.prefix))?
+            }
+            None => templates.prefix(None, &self.prefix)?,
+        };
+
+        let (prlog, opts) = prlog_and_options(&settings)?;
+        let changelog = Changelog::parse_from_file(&prlog, Some(opts))
+            .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+
+        let template = settings.get::<String>("release_notes_template").ok();
+        let notes = changelog.release_notes_with(&prefix, &cmd.version, template.as_deref())?;
+
+        let tag = format!("{prefix}{}", cmd.version);
+        let output = format_notes(
+            cmd.format,
+            &notes.name,
+            &tag,
+            is_prerelease(&cmd.version)?,
+            &notes.body,
+        )?;
+
+        match &cmd.output {
+            Some(path) => write_notes(path, &output)?,
+            None => print!("{output}"),
+        }
+
+        Ok(CIExit::ReleaseNotes)
+    }
+}
+
+fn format_notes(
+    format: NotesFormat,
+    name: &str,
+    tag: &str,
+    prerelease: bool,
+    body: &str,
+) -> Result<String, Error> {
+    let output = match format {
+        NotesFormat::Markdown => body.to_string(),
+        NotesFormat::Json => {
+            let json = NotesJson {
+                name,
+                tag_name: tag,
+                prerelease,
+                body,
+            };
+            format!("{}\n", serde_json::to_string_pretty(&json)?)
+        }
+        NotesFormat::Html => {
+            use pulldown_cmark::{html, Options, Parser};
+
+            let options =
+                Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+            let mut html = String::new();
+            html::push_html(&mut html, Parser::new_ext(body, options));
+            html
+        }
+    };
+
+    Ok(output)
+}
+
+fn write_notes(path: &Path, notes: &str) -> Result<(), Error> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, notes)?;
+    log::info!("Wrote release notes to {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const BODY: &str = "## What's Changed\n\n### Added\n\n- release notes command(pr [#7])\n";
+
+    #[rstest]
+    #[case::markdown(NotesFormat::Markdown, BODY)]
+    #[case::html(
+        NotesFormat::Html,
+        "<h2>What's Changed</h2>\n<h3>Added</h3>\n<ul>\n<li>release notes command(pr [#7])</li>\n</ul>\n"
+    )]
+    fn test_format_notes(#[case] format: NotesFormat, #[case] expected: &str) {
+        let output = format_notes(format, "v1.2.0", "v1.2.0", false, BODY).unwrap();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_format_notes_as_json() {
+        let output =
+            format_notes(NotesFormat::Json, "v1.2.0-rc.1", "v1.2.0-rc.1", true, BODY).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(json["name"], "v1.2.0-rc.1");
+        assert_eq!(json["tag_name"], "v1.2.0-rc.1");
+        assert_eq!(json["prerelease"], true);
+        assert_eq!(json["body"], BODY);
+    }
+}
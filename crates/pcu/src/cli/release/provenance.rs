@@ -0,0 +1,452 @@
+//! SLSA provenance for `pcu release attest`.
+//!
+//! The default is an in-toto Statement v1 carrying a SLSA Provenance v1
+//! predicate. The v0.2 document earlier releases were attested with is kept
+//! behind `--provenance-format v0.2` for consumers that still parse it.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::Error;
+
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+/// What `buildDefinition` describes: a crate published to crates.io and
+/// attested by `pcu release attest`.
+const BUILD_TYPE: &str = "https://github.com/jerus-org/pcu/release-attest/v1";
+/// The `buildType` of the v0.2 document, unchanged for its consumers.
+const LEGACY_BUILD_TYPE: &str = "https://github.com/jerus-org/circleci-toolkit";
+
+/// Which provenance document `pcu release attest` writes.
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ProvenanceFormat {
+    /// in-toto Statement v1 with a SLSA Provenance v1 predicate
+    #[default]
+    #[value(name = "v1")]
+    V1,
+    /// The legacy SLSA v0.2 document
+    #[value(name = "v0.2")]
+    V0_2,
+}
+
+/// Algorithm name to hex digest, as in-toto and SLSA spell digests.
+type DigestSet = BTreeMap<String, String>;
+
+fn digest_set(algorithm: &str, hex: &str) -> DigestSet {
+    BTreeMap::from([(algorithm.to_string(), hex.to_string())])
+}
+
+fn timestamp(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Everything the provenance records, gathered once so that both formats
+/// describe the same build.
+#[derive(Debug, Clone)]
+pub(super) struct BuildContext {
+    pub(super) package: String,
+    pub(super) version: String,
+    /// Each attested artifact and its SHA-256
+    pub(super) subjects: Vec<(String, String)>,
+    /// Git URL of the source repository
+    pub(super) repository: String,
+    /// Source URI of the v0.2 document: `$CIRCLE_REPOSITORY_URL` on CircleCI,
+    /// as earlier releases recorded it, and `repository` elsewhere
+    pub(super) legacy_repository: String,
+    /// Commit the release was built from
+    pub(super) commit: String,
+    /// CI configuration the build ran from
+    pub(super) entry_point: String,
+    /// Identity of the build platform
+    pub(super) builder_id: String,
+    /// URL of this run of the build
+    pub(super) invocation_id: String,
+    /// CI variables describing the run, recorded by the v0.2 document
+    pub(super) environment: BTreeMap<String, String>,
+    pub(super) rust_version: String,
+    /// SHA-256 of the workspace Cargo.lock, when there is one
+    pub(super) cargo_lock: Option<String>,
+    pub(super) started_on: DateTime<Utc>,
+    pub(super) finished_on: DateTime<Utc>,
+}
+
+impl BuildContext {
+    /// The provenance document in `format`, pretty-printed.
+    pub(super) fn render(&self, format: ProvenanceFormat) -> Result<String, Error> {
+        let json = match format {
+            ProvenanceFormat::V1 => serde_json::to_string_pretty(&self.statement())?,
+            ProvenanceFormat::V0_2 => serde_json::to_string_pretty(&self.legacy())?,
+        };
+        Ok(json)
+    }
+
+    fn statement(&self) -> Statement<'_> {
+        let mut resolved_dependencies = vec![ResourceDescriptor {
+            uri: Some(format!("git+{}", self.repository)),
+            digest: digest_set("gitCommit", &self.commit),
+            name: None,
+        }];
+        if let Some(cargo_lock) = &self.cargo_lock {
+            resolved_dependencies.push(ResourceDescriptor {
+                uri: None,
+                digest: digest_set("sha256", cargo_lock),
+                name: Some("Cargo.lock"),
+            });
+        }
+
+        Statement {
+            statement_type: STATEMENT_TYPE,
+            subject: self
+                .subjects
+                .iter()
+                .map(|(name, sha256)| ResourceDescriptor {
+                    uri: None,
+                    digest: digest_set("sha256", sha256),
+                    name: Some(name),
+                })
+                .collect(),
+            predicate_type: PREDICATE_TYPE,
+            predicate: Provenance {
+                build_definition: BuildDefinition {
+                    build_type: BUILD_TYPE,
+                    external_parameters: ExternalParameters {
+                        package: &self.package,
+                        version: &self.version,
+                        source: &self.repository,
+                        entry_point: &self.entry_point,
+                    },
+                    internal_parameters: InternalParameters {
+                        rust_version: &self.rust_version,
+                    },
+                    resolved_dependencies,
+                },
+                run_details: RunDetails {
+                    builder: Builder {
+                        id: &self.builder_id,
+                    },
+                    metadata: BuildMetadata {
+                        invocation_id: &self.invocation_id,
+                        started_on: timestamp(&self.started_on),
+                        finished_on: timestamp(&self.finished_on),
+                    },
+                },
+            },
+        }
+    }
+
+    fn legacy(&self) -> LegacyProvenance<'_> {
+        LegacyProvenance {
+            builder: Builder {
+                id: &self.invocation_id,
+            },
+            build_type: LEGACY_BUILD_TYPE,
+            invocation: LegacyInvocation {
+                config_source: LegacyConfigSource {
+                    uri: &self.legacy_repository,
+                    digest: digest_set("sha1", &self.commit),
+                    entry_point: &self.entry_point,
+                },
+                parameters: LegacyParameters {
+                    package: &self.package,
+                    version: &self.version,
+                    rust_version: &self.rust_version,
+                },
+                environment: &self.environment,
+            },
+            metadata: LegacyMetadata {
+                build_started_on: timestamp(&self.started_on),
+                completeness: LegacyCompleteness {
+                    parameters: true,
+                    environment: true,
+                    materials: true,
+                },
+                reproducible: false,
+            },
+            materials: vec![LegacyMaterial {
+                uri: &self.legacy_repository,
+                digest: digest_set("sha1", &self.commit),
+            }],
+            subject: self
+                .subjects
+                .iter()
+                .map(|(name, sha256)| LegacySubject {
+                    name,
+                    digest: digest_set("sha256", sha256),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Statement<'a> {
+    #[serde(rename = "_type")]
+    statement_type: &'static str,
+    subject: Vec<ResourceDescriptor<'a>>,
+    predicate_type: &'static str,
+    predicate: Provenance<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceDescriptor<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri: Option<String>,
+    digest: DigestSet,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Provenance<'a> {
+    build_definition: BuildDefinition<'a>,
+    run_details: RunDetails<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildDefinition<'a> {
+    build_type: &'static str,
+    external_parameters: ExternalParameters<'a>,
+    internal_parameters: InternalParameters<'a>,
+    resolved_dependencies: Vec<ResourceDescriptor<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExternalParameters<'a> {
+    package: &'a str,
+    version: &'a str,
+    source: &'a str,
+    entry_point: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InternalParameters<'a> {
+    rust_version: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunDetails<'a> {
+    builder: Builder<'a>,
+    metadata: BuildMetadata<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct Builder<'a> {
+    id: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildMetadata<'a> {
+    invocation_id: &'a str,
+    started_on: String,
+    finished_on: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyProvenance<'a> {
+    builder: Builder<'a>,
+    build_type: &'static str,
+    invocation: LegacyInvocation<'a>,
+    metadata: LegacyMetadata,
+    materials: Vec<LegacyMaterial<'a>>,
+    subject: Vec<LegacySubject<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyInvocation<'a> {
+    config_source: LegacyConfigSource<'a>,
+    parameters: LegacyParameters<'a>,
+    environment: &'a BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyConfigSource<'a> {
+    uri: &'a str,
+    digest: DigestSet,
+    entry_point: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct LegacyParameters<'a> {
+    package: &'a str,
+    version: &'a str,
+    rust_version: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyMetadata {
+    build_started_on: String,
+    completeness: LegacyCompleteness,
+    reproducible: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct LegacyCompleteness {
+    parameters: bool,
+    environment: bool,
+    materials: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct LegacyMaterial<'a> {
+    uri: &'a str,
+    digest: DigestSet,
+}
+
+#[derive(Debug, Serialize)]
+struct LegacySubject<'a> {
+    name: &'a str,
+    digest: DigestSet,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use serde_json::Value;
+
+    use super::*;
+
+    fn context(cargo_lock: Option<&str>) -> BuildContext {
+        BuildContext {
+            package: "pcu".to_string(),
+            version: "0.6.34".to_string(),
+            subjects: vec![("pcu-0.6.34.crate".to_string(), "ab12".to_string())],
+            repository: "https://github.com/jerus-org/pcu".to_string(),
+            legacy_repository: "git@github.com:jerus-org/pcu.git".to_string(),
+            commit: "cafe".to_string(),
+            entry_point: ".circleci/release.yml".to_string(),
+            builder_id: "https://circleci.com/gh/jerus-org/pcu".to_string(),
+            invocation_id: "https://circleci.com/gh/jerus-org/pcu/42".to_string(),
+            environment: BTreeMap::from([("CIRCLE_WORKFLOW_ID".to_string(), "wf".to_string())]),
+            rust_version: "rustc 1.90.0".to_string(),
+            cargo_lock: cargo_lock.map(String::from),
+            started_on: Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap(),
+            finished_on: Utc.with_ymd_and_hms(2026, 1, 2, 3, 6, 7).unwrap(),
+        }
+    }
+
+    fn render(context: &BuildContext, format: ProvenanceFormat) -> Value {
+        serde_json::from_str(&context.render(format).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_v1_statement() {
+        let json = render(&context(Some("10ck")), ProvenanceFormat::V1);
+
+        assert_eq!(json["_type"], STATEMENT_TYPE);
+        assert_eq!(json["predicateType"], PREDICATE_TYPE);
+        assert_eq!(json["subject"][0]["name"], "pcu-0.6.34.crate");
+        assert_eq!(json["subject"][0]["digest"]["sha256"], "ab12");
+
+        let definition = &json["predicate"]["buildDefinition"];
+        assert_eq!(definition["buildType"], BUILD_TYPE);
+        assert_eq!(definition["externalParameters"]["package"], "pcu");
+        assert_eq!(definition["externalParameters"]["version"], "0.6.34");
+        assert_eq!(
+            definition["internalParameters"]["rustVersion"],
+            "rustc 1.90.0"
+        );
+        assert_eq!(
+            definition["resolvedDependencies"],
+            serde_json::json!([
+                {
+                    "uri": "git+https://github.com/jerus-org/pcu",
+                    "digest": { "gitCommit": "cafe" }
+                },
+                { "name": "Cargo.lock", "digest": { "sha256": "10ck" } }
+            ])
+        );
+
+        let run = &json["predicate"]["runDetails"];
+        assert_eq!(
+            run["builder"]["id"],
+            "https://circleci.com/gh/jerus-org/pcu"
+        );
+        assert_eq!(
+            run["metadata"]["invocationId"],
+            "https://circleci.com/gh/jerus-org/pcu/42"
+        );
+        assert_eq!(run["metadata"]["startedOn"], "2026-01-02T03:04:05Z");
+        assert_eq!(run["metadata"]["finishedOn"], "2026-01-02T03:06:07Z");
+    }
+
+    #[test]
+    fn test_v1_statement_lists_every_subject() {
+        let mut context = context(None);
+        context.subjects.push((
+            "pcu-x86_64-unknown-linux-gnu.tar.gz".to_string(),
+            "cd34".to_string(),
+        ));
+
+        let json = render(&context, ProvenanceFormat::V1);
+
+        assert_eq!(
+            json["subject"],
+            serde_json::json!([
+                { "name": "pcu-0.6.34.crate", "digest": { "sha256": "ab12" } },
+                { "name": "pcu-x86_64-unknown-linux-gnu.tar.gz", "digest": { "sha256": "cd34" } }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_v1_statement_without_cargo_lock() {
+        let json = render(&context(None), ProvenanceFormat::V1);
+
+        let dependencies = json["predicate"]["buildDefinition"]["resolvedDependencies"]
+            .as_array()
+            .unwrap();
+        assert_eq!(dependencies.len(), 1);
+    }
+
+    #[test]
+    fn test_legacy_v0_2_document_keeps_its_shape() {
+        let json = render(&context(Some("10ck")), ProvenanceFormat::V0_2);
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "builder": { "id": "https://circleci.com/gh/jerus-org/pcu/42" },
+                "buildType": LEGACY_BUILD_TYPE,
+                "invocation": {
+                    "configSource": {
+                        "uri": "git@github.com:jerus-org/pcu.git",
+                        "digest": { "sha1": "cafe" },
+                        "entryPoint": ".circleci/release.yml"
+                    },
+                    "parameters": {
+                        "package": "pcu",
+                        "version": "0.6.34",
+                        "rust_version": "rustc 1.90.0"
+                    },
+                    "environment": { "CIRCLE_WORKFLOW_ID": "wf" }
+                },
+                "metadata": {
+                    "buildStartedOn": "2026-01-02T03:04:05Z",
+                    "completeness": { "parameters": true, "environment": true, "materials": true },
+                    "reproducible": false
+                },
+                "materials": [
+                    { "uri": "git@github.com:jerus-org/pcu.git", "digest": { "sha1": "cafe" } }
+                ],
+                "subject": [
+                    { "name": "pcu-0.6.34.crate", "digest": { "sha256": "ab12" } }
+                ]
+            })
+        );
+    }
+}
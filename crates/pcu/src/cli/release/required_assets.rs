@@ -0,0 +1,139 @@
+//! The assets a release must carry before `pcu release publish` un-drafts it.
+//!
+//! On a repository with immutable releases, publishing freezes the assets, so
+//! a release published without its binary, signature or attestation can never
+//! receive them. pcu.toml lists the assets as glob patterns, for every crate
+//! under `default` or for one crate under its name:
+//!
+//! ```toml
+//! [required_assets]
+//! default = ["*.tar.gz", "*.minisig"]
+//! pcu = ["pcu-*.tar.gz", "pcu-*.minisig", "*.sigstore.json"]
+//! ```
+//!
+//! A crate's own list replaces the default list.
+
+use std::collections::BTreeMap;
+
+use config::Config;
+use glob::Pattern;
+
+use crate::Error;
+
+const DEFAULT: &str = "default";
+
+/// Glob patterns of the assets required on a release, by crate.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RequiredAssets {
+    patterns: BTreeMap<String, Vec<Pattern>>,
+}
+
+impl RequiredAssets {
+    /// The `required_assets` table from `settings`; none are required without
+    /// one.
+    pub(crate) fn from_settings(settings: &Config) -> Result<Self, Error> {
+        let table = match settings.get::<BTreeMap<String, Vec<String>>>("required_assets") {
+            Ok(table) => table,
+            Err(config::ConfigError::NotFound(_)) => return Ok(Self::default()),
+            Err(e) => return Err(Error::InvalidRequiredAssets(e.to_string())),
+        };
+
+        let mut patterns = BTreeMap::new();
+        for (name, globs) in table {
+            let globs = globs
+                .iter()
+                .map(|glob| {
+                    Pattern::new(glob)
+                        .map_err(|e| Error::InvalidRequiredAssets(format!("`{glob}`: {e}")))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            patterns.insert(name, globs);
+        }
+
+        Ok(Self { patterns })
+    }
+
+    /// The patterns required of a release of `package`, or of any release when
+    /// no package is named.
+    pub(crate) fn patterns(&self, package: Option<&str>) -> &[Pattern] {
+        package
+            .and_then(|name| self.patterns.get(name))
+            .or_else(|| self.patterns.get(DEFAULT))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The required patterns that no asset in `asset_names` matches.
+    pub(crate) fn missing(&self, package: Option<&str>, asset_names: &[String]) -> Vec<String> {
+        self.patterns(package)
+            .iter()
+            .filter(|pattern| !asset_names.iter().any(|name| pattern.matches(name)))
+            .map(|pattern| pattern.as_str().to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn required_assets() -> RequiredAssets {
+        let settings = Config::builder()
+            .add_source(config::File::from_str(
+                r#"
+[required_assets]
+default = ["*.tar.gz", "*.minisig"]
+pcu = ["pcu-*.tar.gz", "*.sigstore.json"]
+"#,
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .unwrap();
+        RequiredAssets::from_settings(&settings).unwrap()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_all_required_assets_present() {
+        let assets = names(&["app-x86_64.tar.gz", "app-x86_64.tar.gz.minisig"]);
+        assert!(required_assets().missing(None, &assets).is_empty());
+    }
+
+    #[test]
+    fn test_missing_assets_are_listed() {
+        let assets = names(&["pcu-x86_64.tar.gz"]);
+        assert_eq!(
+            required_assets().missing(Some("pcu"), &assets),
+            vec!["*.sigstore.json"]
+        );
+        assert_eq!(
+            required_assets().missing(Some("other"), &assets),
+            vec!["*.minisig"]
+        );
+    }
+
+    #[test]
+    fn test_nothing_required_without_a_table() {
+        let settings = Config::builder().build().unwrap();
+        let required = RequiredAssets::from_settings(&settings).unwrap();
+        assert!(required.missing(Some("pcu"), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_an_error() {
+        let settings = Config::builder()
+            .add_source(config::File::from_str(
+                r#"required_assets = { default = ["[unclosed"] }"#,
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            RequiredAssets::from_settings(&settings),
+            Err(Error::InvalidRequiredAssets(_))
+        ));
+    }
+}
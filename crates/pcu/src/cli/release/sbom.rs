@@ -0,0 +1,641 @@
+//! `pcu release sbom`: a CycloneDX SBOM of a package, built from Cargo.lock
+//! and the workspace manifests, and uploaded to the release while it is still
+//! a draft.
+//!
+//! The dependency graph is the package's part of Cargo.lock: every package
+//! reachable from it, less the dev-dependencies of workspace members, which
+//! never ship. Cargo.lock resolves every feature and target, so optional and
+//! platform-specific dependencies are listed whether or not a given build
+//! uses them.
+//!
+//! Cargo.lock carries the SHA-256 of each registry download. Licenses come
+//! from the manifests — the workspace's own, and those cargo has unpacked
+//! under `$CARGO_HOME/registry/src` — and are left out for a crate that has
+//! not been fetched on this machine.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cargo_toml::Manifest;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    crate_tag, resolve_version,
+    upload_asset::{mutable_release, upload_all, PlannedUpload, Uploader},
+    Mode, Release,
+};
+use crate::{CIExit, Client, Error};
+
+/// The source Cargo.lock records for a crates.io package.
+const CRATES_IO: &str = "registry+https://github.com/rust-lang/crates.io-index";
+const SPEC_VERSION: &str = "1.5";
+
+impl Release {
+    /// Write the SBOM of the package and, given a client, upload it to the
+    /// release as `<package>-<version>.cdx.json`.
+    pub(super) async fn sbom(self, client: Option<Client>) -> Result<CIExit, Error> {
+        let Mode::Sbom(ref cmd) = self.mode else {
+            return Err(Error::NoPackageSpecified);
+        };
+
+        let lock = Lockfile::parse(&fs::read_to_string("Cargo.lock")?)?;
+        let members = member_manifests(Path::new("Cargo.toml"))?;
+        let bom = Bom::build(&lock, &members, &cmd.package, &registry_src_dirs())?;
+        let json = format!("{}\n", serde_json::to_string_pretty(&bom)?);
+        log::info!(
+            "SBOM of {} lists {} components",
+            cmd.package,
+            bom.components.len()
+        );
+
+        let version = release_version(
+            &cmd.package,
+            &resolve_version(&cmd.version),
+            &bom.metadata.component.version,
+        )?;
+        let name = format!("{}-{version}.cdx.json", cmd.package);
+
+        let Some(client) = client else {
+            match &cmd.output {
+                Some(path) => fs::write(path, &json)?,
+                None => print!("{json}"),
+            }
+            return Ok(CIExit::SbomGenerated(name));
+        };
+
+        let dir = tempfile::tempdir()?;
+        let path = match &cmd.output {
+            Some(path) => path.clone(),
+            None => dir.path().join(&name),
+        };
+        fs::write(&path, &json)?;
+
+        let tag = match &cmd.tag {
+            Some(tag) => tag.clone(),
+            None => crate_tag(&client.tag_templates, &cmd.package, &version)?,
+        };
+        let release_ref = mutable_release(&client, &tag).await?;
+        let existing = client.release_assets(release_ref.id).await?;
+        let upload = PlannedUpload {
+            replace: existing.iter().find(|a| a.name == name).map(|a| a.id),
+            path,
+            name,
+        };
+
+        let uploader = Uploader::new(&client, &tag, release_ref.id, cmd.max_attempts);
+        upload_all(&uploader, vec![upload], 1).await?;
+
+        Ok(CIExit::Released)
+    }
+}
+
+/// The version the SBOM is named and uploaded for: the package's version in
+/// Cargo.lock, which `requested` (the release version, or `none`) must match
+/// so the asset name never disagrees with the SBOM's own component version.
+fn release_version(package: &str, requested: &str, locked: &str) -> Result<String, Error> {
+    if requested != "none" && requested != locked {
+        return Err(Error::Sbom(format!(
+            "the release is for version {requested}, but Cargo.lock locks {package} {locked}"
+        )));
+    }
+
+    Ok(locked.to_string())
+}
+
+/// The parts of Cargo.lock the SBOM is built from.
+#[derive(Debug, Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    /// Absent for workspace members and other path dependencies.
+    #[serde(default)]
+    source: Option<String>,
+    /// SHA-256 of the downloaded `.crate`, for registry packages.
+    #[serde(default)]
+    checksum: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+impl Lockfile {
+    fn parse(text: &str) -> Result<Self, Error> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// The package a `dependencies` entry names: `name`, `name version` or
+    /// `name version (source)`, as Cargo.lock writes them when the name alone
+    /// is ambiguous.
+    fn find(&self, spec: &str) -> Option<usize> {
+        let mut parts = spec.splitn(3, ' ');
+        let name = parts.next()?;
+        let version = parts.next();
+        let source = parts
+            .next()
+            .map(|s| s.trim_start_matches('(').trim_end_matches(')'));
+
+        self.package.iter().position(|p| {
+            p.name == name
+                && version.is_none_or(|v| p.version == v)
+                && source.is_none_or(|s| p.source.as_deref() == Some(s))
+        })
+    }
+}
+
+impl LockedPackage {
+    fn purl(&self) -> String {
+        let purl = format!("pkg:cargo/{}@{}", self.name, self.version);
+        match self.source.as_deref() {
+            None | Some(CRATES_IO) => purl,
+            Some(source) => {
+                let (qualifier, url) = match source.split_once('+') {
+                    Some(("git", url)) => ("vcs_url", format!("git+{url}")),
+                    Some((_, url)) => ("repository_url", url.to_string()),
+                    None => ("repository_url", source.to_string()),
+                };
+                let url: String = url::form_urlencoded::byte_serialize(url.as_bytes()).collect();
+                format!("{purl}?{qualifier}={url}")
+            }
+        }
+    }
+}
+
+/// The manifests of the workspace's packages, by package name: the members
+/// named by `[workspace]` and the root package, if there is one.
+fn member_manifests(root: &Path) -> Result<BTreeMap<String, Manifest>, Error> {
+    let manifest = Manifest::from_path(root)?;
+    let dir = root.parent().unwrap_or(Path::new(""));
+
+    let mut paths = Vec::new();
+    if let Some(workspace) = &manifest.workspace {
+        for member in &workspace.members {
+            let pattern = dir.join(member).join("Cargo.toml");
+            let pattern = pattern.to_string_lossy();
+            for path in glob::glob(&pattern).map_err(|e| Error::Sbom(e.to_string()))? {
+                paths.push(path.map_err(|e| Error::Sbom(e.to_string()))?);
+            }
+        }
+    }
+
+    let mut members = BTreeMap::new();
+    if let Some(package) = &manifest.package {
+        members.insert(package.name.clone(), manifest.clone());
+    }
+    for path in paths {
+        let manifest = Manifest::from_path(&path)?;
+        if let Some(package) = &manifest.package {
+            members.insert(package.name.clone(), manifest);
+        }
+    }
+
+    Ok(members)
+}
+
+/// The names of the packages a member depends on outside of its tests,
+/// examples and benches.
+fn shipped_dependencies(manifest: &Manifest) -> BTreeSet<String> {
+    manifest
+        .target
+        .values()
+        .flat_map(|target| [&target.dependencies, &target.build_dependencies])
+        .chain([&manifest.dependencies, &manifest.build_dependencies])
+        .flatten()
+        .map(|(key, dep)| dep.package().unwrap_or(key).to_string())
+        .collect()
+}
+
+/// The directories cargo unpacks registry crates into.
+fn registry_src_dirs() -> Vec<PathBuf> {
+    let cargo_home = std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")));
+    let Some(src) = cargo_home.map(|home| home.join("registry").join("src")) else {
+        return Vec::new();
+    };
+
+    fs::read_dir(src)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default()
+}
+
+/// The SPDX license expression of `package`, where a manifest records one.
+fn license(
+    package: &LockedPackage,
+    members: &BTreeMap<String, Manifest>,
+    registry_dirs: &[PathBuf],
+) -> Option<String> {
+    let license = match package.source.as_deref() {
+        None => members
+            .get(&package.name)?
+            .package
+            .as_ref()?
+            .license
+            .as_ref()?
+            .get()
+            .ok()?
+            .clone(),
+        Some(source) if source.starts_with("registry+") || source.starts_with("sparse+") => {
+            let dir = format!("{}-{}", package.name, package.version);
+            registry_dirs.iter().find_map(|registry| {
+                let bytes = fs::read(registry.join(&dir).join("Cargo.toml")).ok()?;
+                Manifest::from_slice(&bytes)
+                    .ok()?
+                    .package?
+                    .license?
+                    .get()
+                    .ok()
+                    .cloned()
+            })?
+        }
+        Some(_) => return None,
+    };
+
+    // Older crates use `/` where SPDX has `OR`.
+    Some(
+        license
+            .split('/')
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(" OR "),
+    )
+}
+
+/// A CycloneDX document.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Bom {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    version: u32,
+    metadata: Metadata,
+    components: Vec<Component>,
+    dependencies: Vec<Dependency>,
+}
+
+#[derive(Debug, Serialize)]
+struct Metadata {
+    timestamp: String,
+    tools: Tools,
+    component: Component,
+}
+
+#[derive(Debug, Serialize)]
+struct Tools {
+    components: Vec<Component>,
+}
+
+#[derive(Debug, Serialize)]
+struct Component {
+    #[serde(rename = "type")]
+    kind: ComponentType,
+    #[serde(rename = "bom-ref", skip_serializing_if = "Option::is_none")]
+    bom_ref: Option<String>,
+    name: String,
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    purl: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    licenses: Vec<License>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hashes: Vec<Hash>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ComponentType {
+    Application,
+    Library,
+}
+
+#[derive(Debug, Serialize)]
+struct License {
+    expression: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Hash {
+    alg: &'static str,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Dependency {
+    #[serde(rename = "ref")]
+    reference: String,
+    #[serde(rename = "dependsOn")]
+    depends_on: Vec<String>,
+}
+
+impl Bom {
+    /// The SBOM of the workspace package `package`, from the packages
+    /// reachable from it in `lock`.
+    fn build(
+        lock: &Lockfile,
+        members: &BTreeMap<String, Manifest>,
+        package: &str,
+        registry_dirs: &[PathBuf],
+    ) -> Result<Self, Error> {
+        let root = lock
+            .package
+            .iter()
+            .position(|p| p.name == package && p.source.is_none())
+            .ok_or_else(|| Error::Sbom(format!("Cargo.lock has no workspace package {package}")))?;
+
+        let mut edges: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        let mut queue = VecDeque::from([root]);
+        while let Some(index) = queue.pop_front() {
+            if edges.contains_key(&index) {
+                continue;
+            }
+            let locked = &lock.package[index];
+            let shipped = match locked.source {
+                None => members.get(&locked.name).map(shipped_dependencies),
+                Some(_) => None,
+            };
+
+            let mut depends_on = BTreeSet::new();
+            for spec in &locked.dependencies {
+                let dep = lock.find(spec).ok_or_else(|| {
+                    Error::Sbom(format!(
+                        "Cargo.lock lists {spec} as a dependency of {} but does not lock it",
+                        locked.name
+                    ))
+                })?;
+                if shipped
+                    .as_ref()
+                    .is_none_or(|names| names.contains(&lock.package[dep].name))
+                {
+                    depends_on.insert(dep);
+                    queue.push_back(dep);
+                }
+            }
+            edges.insert(index, depends_on);
+        }
+
+        let component = |index: usize, kind: ComponentType| {
+            let locked = &lock.package[index];
+            Component {
+                kind,
+                bom_ref: Some(locked.purl()),
+                name: locked.name.clone(),
+                version: locked.version.clone(),
+                purl: Some(locked.purl()),
+                licenses: license(locked, members, registry_dirs)
+                    .map(|expression| License { expression })
+                    .into_iter()
+                    .collect(),
+                hashes: locked
+                    .checksum
+                    .iter()
+                    .map(|content| Hash {
+                        alg: "SHA-256",
+                        content: content.clone(),
+                    })
+                    .collect(),
+            }
+        };
+
+        let root_kind = match members.get(package) {
+            Some(manifest) if !manifest.bin.is_empty() => ComponentType::Application,
+            _ => ComponentType::Library,
+        };
+        let mut components: Vec<_> = edges
+            .keys()
+            .filter(|&&index| index != root)
+            .map(|&index| component(index, ComponentType::Library))
+            .collect();
+        components.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+        let dependencies = edges
+            .iter()
+            .map(|(&index, depends_on)| Dependency {
+                reference: lock.package[index].purl(),
+                depends_on: depends_on
+                    .iter()
+                    .map(|&dep| lock.package[dep].purl())
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Bom {
+            bom_format: "CycloneDX",
+            spec_version: SPEC_VERSION,
+            version: 1,
+            metadata: Metadata {
+                timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                tools: Tools {
+                    components: vec![Component {
+                        kind: ComponentType::Application,
+                        bom_ref: None,
+                        name: env!("CARGO_PKG_NAME").to_string(),
+                        version: env!("CARGO_PKG_VERSION").to_string(),
+                        purl: None,
+                        licenses: Vec::new(),
+                        hashes: Vec::new(),
+                    }],
+                },
+                component: component(root, root_kind),
+            },
+            components,
+            dependencies,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCK: &str = r#"
+version = 4
+
+[[package]]
+name = "app"
+version = "0.2.0"
+dependencies = [
+ "helper",
+ "rand 0.9.1",
+ "rstest",
+]
+
+[[package]]
+name = "helper"
+version = "0.2.0"
+dependencies = [
+ "rand 0.8.5",
+]
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "34af8d1a0e25924bc5b7c43c079c942339d8f0a8b57c39049bef581b46327404"
+
+[[package]]
+name = "rand"
+version = "0.9.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "9fbfd9d094a40bf3ae768db9361049ace4c0e04a4fd6b359518bd7b73a73dd97"
+dependencies = [
+ "tiny 1.0.0 (git+https://github.com/example/tiny?branch=main#0123abc)",
+]
+
+[[package]]
+name = "rstest"
+version = "0.26.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "f5a3193c063baaa2a95a33f03035c8a72b83d97a54916055ba22d35ed3839d49"
+
+[[package]]
+name = "tiny"
+version = "1.0.0"
+source = "git+https://github.com/example/tiny?branch=main#0123abc"
+"#;
+
+    /// A workspace with `app`, whose tests use rstest, and `helper`.
+    fn workspace(dir: &Path) -> BTreeMap<String, Manifest> {
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n\n\
+             [workspace.package]\nlicense = \"MIT OR Apache-2.0\"\n",
+        )
+        .unwrap();
+        for (name, manifest) in [
+            (
+                "app",
+                "[package]\nname = \"app\"\nversion = \"0.2.0\"\nlicense.workspace = true\n\n\
+                 [[bin]]\nname = \"app\"\npath = \"main.rs\"\n\n\
+                 [dependencies]\nhelper = { path = \"../helper\" }\nrand = \"0.9\"\n\n\
+                 [dev-dependencies]\nrstest = \"0.26\"\n",
+            ),
+            (
+                "helper",
+                "[package]\nname = \"helper\"\nversion = \"0.2.0\"\nlicense = \"MIT\"\n\n\
+                 [lib]\npath = \"lib.rs\"\n\n\
+                 [target.'cfg(unix)'.dependencies]\nold-rand = { package = \"rand\", version = \"0.8\" }\n",
+            ),
+        ] {
+            let crate_dir = dir.join("crates").join(name);
+            fs::create_dir_all(&crate_dir).unwrap();
+            fs::write(crate_dir.join("Cargo.toml"), manifest).unwrap();
+        }
+
+        member_manifests(&dir.join("Cargo.toml")).unwrap()
+    }
+
+    #[test]
+    fn test_find_resolves_every_form_of_dependency_entry() {
+        let lock = Lockfile::parse(LOCK).unwrap();
+
+        assert_eq!(lock.find("helper"), Some(1));
+        assert_eq!(lock.find("rand 0.8.5"), Some(2));
+        assert_eq!(lock.find("rand 0.9.1"), Some(3));
+        assert_eq!(
+            lock.find("tiny 1.0.0 (git+https://github.com/example/tiny?branch=main#0123abc)"),
+            Some(5)
+        );
+        assert_eq!(lock.find("rand 0.7.3"), None);
+    }
+
+    #[test]
+    fn test_purl_qualifies_packages_not_from_crates_io() {
+        let lock = Lockfile::parse(LOCK).unwrap();
+
+        assert_eq!(lock.package[0].purl(), "pkg:cargo/app@0.2.0");
+        assert_eq!(lock.package[3].purl(), "pkg:cargo/rand@0.9.1");
+        assert_eq!(
+            lock.package[5].purl(),
+            "pkg:cargo/tiny@1.0.0?vcs_url=git%2Bhttps%3A%2F%2Fgithub.com%2Fexample%2Ftiny\
+             %3Fbranch%3Dmain%230123abc"
+        );
+    }
+
+    #[test]
+    fn test_build_follows_shipped_dependencies_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let members = workspace(dir.path());
+        let lock = Lockfile::parse(LOCK).unwrap();
+
+        let bom = Bom::build(&lock, &members, "app", &[]).unwrap();
+
+        let names: Vec<_> = bom
+            .components
+            .iter()
+            .map(|c| format!("{}@{}", c.name, c.version))
+            .collect();
+        assert_eq!(
+            names,
+            ["helper@0.2.0", "rand@0.8.5", "rand@0.9.1", "tiny@1.0.0"]
+        );
+        assert_eq!(bom.metadata.component.kind, ComponentType::Application);
+
+        let app = &bom.dependencies[0];
+        assert_eq!(app.reference, "pkg:cargo/app@0.2.0");
+        assert_eq!(
+            app.depends_on,
+            ["pkg:cargo/helper@0.2.0", "pkg:cargo/rand@0.9.1"]
+        );
+    }
+
+    #[test]
+    fn test_build_records_checksums_and_licenses() {
+        let dir = tempfile::tempdir().unwrap();
+        let members = workspace(dir.path());
+        let registry = dir.path().join("registry");
+        fs::create_dir_all(registry.join("rand-0.9.1")).unwrap();
+        fs::write(
+            registry.join("rand-0.9.1").join("Cargo.toml"),
+            "[package]\nname = \"rand\"\nversion = \"0.9.1\"\nlicense = \"MIT/Apache-2.0\"\n",
+        )
+        .unwrap();
+        let lock = Lockfile::parse(LOCK).unwrap();
+
+        let bom = Bom::build(&lock, &members, "app", &[registry]).unwrap();
+        let json = serde_json::to_value(&bom).unwrap();
+
+        assert_eq!(json["bomFormat"], "CycloneDX");
+        assert_eq!(json["specVersion"], "1.5");
+        let root = &json["metadata"]["component"];
+        assert_eq!(root["type"], "application");
+        assert_eq!(root["licenses"][0]["expression"], "MIT OR Apache-2.0");
+        assert!(root.get("hashes").is_none());
+
+        let rand = &json["components"][2];
+        assert_eq!(rand["bom-ref"], "pkg:cargo/rand@0.9.1");
+        assert_eq!(rand["licenses"][0]["expression"], "MIT OR Apache-2.0");
+        assert_eq!(rand["hashes"][0]["alg"], "SHA-256");
+        assert_eq!(
+            rand["hashes"][0]["content"],
+            "9fbfd9d094a40bf3ae768db9361049ace4c0e04a4fd6b359518bd7b73a73dd97"
+        );
+        // Not unpacked under the registry directory, so its license is unknown.
+        assert!(json["components"][1].get("licenses").is_none());
+    }
+
+    #[test]
+    fn test_release_version_must_match_the_locked_version() {
+        assert_eq!(release_version("app", "none", "0.2.0").unwrap(), "0.2.0");
+        assert_eq!(release_version("app", "0.2.0", "0.2.0").unwrap(), "0.2.0");
+        assert!(matches!(
+            release_version("app", "1.2.0", "0.2.0"),
+            Err(Error::Sbom(_))
+        ));
+    }
+
+    #[test]
+    fn test_build_rejects_a_package_outside_the_workspace() {
+        let lock = Lockfile::parse(LOCK).unwrap();
+
+        let err = Bom::build(&lock, &BTreeMap::new(), "rand", &[]).unwrap_err();
+
+        assert!(matches!(err, Error::Sbom(_)));
+    }
+}
@@ -0,0 +1,340 @@
+//! `pcu release sign`: minisign signatures for release assets, made in-process
+//! rather than by external tooling.
+//!
+//! The key is checked against the pubkey committed under
+//! `[package.metadata.binstall.signing]` before anything is signed: a
+//! signature cargo-binstall cannot verify against that pubkey is worse than
+//! none, because it fails the install instead of falling back to a build.
+//! The same table must set `file = "{ url }.minisig"`, as cargo-binstall
+//! otherwise looks for `{ url }.sig` and never finds these signatures.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use minisign::{PublicKey, SecretKey, SecretKeyBox};
+
+use super::{
+    crate_tag, resolve_version,
+    upload_asset::{
+        local_assets, mutable_release, resolve_asset_paths, upload_all, PlannedUpload, Uploader,
+        SHA256SUMS,
+    },
+    Mode, Release,
+};
+use crate::{CIExit, Client, Error};
+
+/// Environment variable holding the secret key when `--secret-key` is not
+/// given: the key file, or just its base64 line.
+const SECRET_KEY_ENV: &str = "MINISIGN_SECRET_KEY";
+/// Environment variable holding the password of an encrypted secret key.
+const PASSWORD_ENV: &str = "MINISIGN_PASSWORD";
+
+impl Release {
+    pub(super) async fn sign(self, client: Client) -> Result<CIExit, Error> {
+        let Mode::Sign(ref cmd) = self.mode else {
+            return Err(Error::NoPackageSpecified);
+        };
+
+        let version = resolve_version(&cmd.version);
+        if version == "none" {
+            return Err(Error::MissingSemver);
+        }
+        let tag = match &cmd.tag {
+            Some(tag) => tag.clone(),
+            None => crate_tag(&client.tag_templates, &cmd.package, &version)?,
+        };
+
+        let secret_key = load_secret_key(cmd.secret_key.as_deref())?;
+        let cargo_toml_path = format!("crates/{}/Cargo.toml", cmd.package);
+        let cargo_toml = fs::read_to_string(&cargo_toml_path)?;
+        let committed = signing_field(&cargo_toml, "pubkey")?.ok_or_else(|| {
+            Error::MissingSigningScaffold(format!(
+                "{cargo_toml_path} has no `pubkey` under \
+                 `[package.metadata.binstall.signing]` to check the signing key against"
+            ))
+        })?;
+        let public_key = check_signing_key(&secret_key, &committed, &cargo_toml_path)?;
+        check_signature_file(signing_field(&cargo_toml, "file")?, &cargo_toml_path)?;
+
+        let mut paths = resolve_asset_paths(&cmd.asset_path)?;
+        // The release's own SHA256SUMS is the one to sign.
+        paths.retain(|path| path.file_name().is_none_or(|name| name != SHA256SUMS));
+        let local = local_assets(paths, None)?;
+
+        let release_ref = mutable_release(&client, &tag).await?;
+        let existing = client.release_assets(release_ref.id).await?;
+
+        let dir = tempfile::tempdir()?;
+        let mut signed = Vec::new();
+        for asset in &local {
+            signed.push(sign_file(
+                &secret_key,
+                &public_key,
+                &asset.path,
+                &asset.name,
+                dir.path(),
+            )?);
+        }
+
+        if existing.iter().any(|asset| asset.name == SHA256SUMS) {
+            let sums = client
+                .download_release_asset(&tag, SHA256SUMS, true)
+                .await?;
+            let sums_path = dir.path().join(SHA256SUMS);
+            fs::write(&sums_path, sums)?;
+            signed.push(sign_file(
+                &secret_key,
+                &public_key,
+                &sums_path,
+                SHA256SUMS,
+                dir.path(),
+            )?);
+        } else {
+            log::warn!("Release {tag} has no {SHA256SUMS} to sign");
+        }
+
+        if signed.is_empty() {
+            return Err(Error::Signing(format!(
+                "nothing to sign: no asset paths given and no {SHA256SUMS} on release {tag}"
+            )));
+        }
+
+        // A fresh signature always replaces the old one, which may be for an
+        // earlier build of the same file.
+        let uploads = signed
+            .into_iter()
+            .map(|(path, name)| PlannedUpload {
+                replace: existing.iter().find(|a| a.name == name).map(|a| a.id),
+                path,
+                name,
+            })
+            .collect();
+
+        let uploader = Uploader::new(&client, &tag, release_ref.id, cmd.max_attempts);
+        upload_all(&uploader, uploads, cmd.concurrency).await?;
+
+        Ok(CIExit::Released)
+    }
+}
+
+/// The secret key from the file at `path`, or else from `$MINISIGN_SECRET_KEY`.
+fn load_secret_key(path: Option<&Path>) -> Result<SecretKey, Error> {
+    let key = match path {
+        Some(path) => fs::read_to_string(path)?,
+        None => std::env::var(SECRET_KEY_ENV).map_err(|_| {
+            Error::Signing(format!(
+                "no minisign secret key: pass --secret-key or set ${SECRET_KEY_ENV}"
+            ))
+        })?,
+    };
+
+    parse_secret_key(&key, std::env::var(PASSWORD_ENV).ok())
+}
+
+/// Parse a minisign secret key file, or just its base64 line — the form a CI
+/// secret usually takes. The password is only used when the key is
+/// encrypted, and is never prompted for.
+fn parse_secret_key(key: &str, password: Option<String>) -> Result<SecretKey, Error> {
+    let key = key.trim();
+    let key = match key.lines().count() {
+        1 => format!("untrusted comment: minisign secret key\n{key}\n"),
+        _ => format!("{key}\n"),
+    };
+
+    let unencrypted = SecretKeyBox::from_string(&key).and_then(SecretKey::from_unencrypted_box);
+    match (unencrypted, password) {
+        (Ok(secret_key), _) => Ok(secret_key),
+        (Err(_), Some(password)) => SecretKeyBox::from_string(&key)
+            .and_then(|key_box| SecretKey::from_box(key_box, Some(password)))
+            .map_err(|e| Error::Signing(format!("cannot read the minisign secret key: {e}"))),
+        (Err(e), None) => Err(Error::Signing(format!(
+            "cannot read the minisign secret key: {e} (an encrypted key needs ${PASSWORD_ENV})"
+        ))),
+    }
+}
+
+/// A string `field` under `[package.metadata.binstall.signing]` in a
+/// Cargo.toml.
+fn signing_field(cargo_toml: &str, field: &str) -> Result<Option<String>, Error> {
+    let manifest: toml::Table = toml::from_str(cargo_toml)?;
+
+    Ok(manifest
+        .get("package")
+        .and_then(|v| v.get("metadata"))
+        .and_then(|v| v.get("binstall"))
+        .and_then(|v| v.get("signing"))
+        .and_then(|v| v.get(field))
+        .and_then(|v| v.as_str())
+        .map(String::from))
+}
+
+/// Check that cargo-binstall will fetch the `<name>.minisig` signatures made
+/// here: its `file` template defaults to `{ url }.sig`.
+fn check_signature_file(file: Option<String>, manifest: &str) -> Result<(), Error> {
+    match file {
+        Some(file) if file.trim_end().ends_with(".minisig") => Ok(()),
+        file => Err(Error::MissingSigningScaffold(format!(
+            "{manifest} sets `file = {}` under `[package.metadata.binstall.signing]`; \
+             set `file = \"{{ url }}.minisig\"` so cargo-binstall finds the signatures",
+            file.map_or("<unset>".to_string(), |f| format!("\"{f}\""))
+        ))),
+    }
+}
+
+/// The public key of `secret_key`, provided it is the `committed` pubkey.
+fn check_signing_key(
+    secret_key: &SecretKey,
+    committed: &str,
+    manifest: &str,
+) -> Result<PublicKey, Error> {
+    let public_key = PublicKey::from_secret_key(secret_key)
+        .map_err(|e| Error::Signing(format!("cannot derive the public key: {e}")))?;
+
+    let key = public_key.to_base64();
+    if key != committed.trim() {
+        return Err(Error::SigningKeyMismatch(manifest.to_string(), key));
+    }
+
+    Ok(public_key)
+}
+
+/// Sign the file at `path`, known on the release as `name`, writing
+/// `<name>.minisig` into `dir`. Returns the signature's path and asset name.
+fn sign_file(
+    secret_key: &SecretKey,
+    public_key: &PublicKey,
+    path: &Path,
+    name: &str,
+    dir: &Path,
+) -> Result<(PathBuf, String), Error> {
+    // The trusted comment minisign itself writes for a prehashed signature.
+    let trusted_comment = format!(
+        "timestamp:{}\tfile:{name}\thashed",
+        chrono::Utc::now().timestamp()
+    );
+    let signature = minisign::sign(
+        Some(public_key),
+        secret_key,
+        fs::File::open(path)?,
+        Some(&trusted_comment),
+        None,
+    )
+    .map_err(|e| Error::Signing(format!("cannot sign {name}: {e}")))?;
+
+    let sig_name = format!("{name}.minisig");
+    let sig_path = dir.join(&sig_name);
+    fs::write(&sig_path, signature.into_string())?;
+    log::info!("Signed {name}");
+
+    Ok((sig_path, sig_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use minisign::{KeyPair, SignatureBox};
+
+    use super::*;
+
+    fn secret_key_file(key_pair: &KeyPair) -> String {
+        key_pair.sk.to_box(None).unwrap().into_string()
+    }
+
+    #[test]
+    fn test_parse_secret_key_from_a_file_or_its_base64_line() {
+        let key_pair = KeyPair::generate_unencrypted_keypair().unwrap();
+        let file = secret_key_file(&key_pair);
+        let line = file.lines().nth(1).unwrap();
+
+        for key in [file.as_str(), line] {
+            let secret_key = parse_secret_key(key, None).unwrap();
+            assert_eq!(
+                PublicKey::from_secret_key(&secret_key).unwrap().to_base64(),
+                key_pair.pk.to_base64()
+            );
+        }
+
+        assert!(matches!(
+            parse_secret_key("not a key", None),
+            Err(Error::Signing(_))
+        ));
+    }
+
+    #[test]
+    fn test_signing_field() {
+        let cargo_toml = r#"
+[package]
+name = "pcu"
+
+[package.metadata.binstall.signing]
+algorithm = "minisign"
+pubkey = "RWTexample"
+file = "{ url }.minisig"
+"#;
+        assert_eq!(
+            signing_field(cargo_toml, "pubkey").unwrap().as_deref(),
+            Some("RWTexample")
+        );
+        assert_eq!(
+            signing_field(cargo_toml, "file").unwrap().as_deref(),
+            Some("{ url }.minisig")
+        );
+        assert_eq!(
+            signing_field("[package]\nname = \"pcu\"\n", "pubkey").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_check_signature_file_needs_the_minisig_extension() {
+        let file = Some("{ url }.minisig".to_string());
+        assert!(check_signature_file(file, "Cargo.toml").is_ok());
+
+        for file in [None, Some("{ url }.sig".to_string())] {
+            assert!(matches!(
+                check_signature_file(file, "Cargo.toml"),
+                Err(Error::MissingSigningScaffold(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_check_signing_key_refuses_another_key() {
+        let key_pair = KeyPair::generate_unencrypted_keypair().unwrap();
+        let other = KeyPair::generate_unencrypted_keypair().unwrap();
+
+        assert!(check_signing_key(&key_pair.sk, &key_pair.pk.to_base64(), "Cargo.toml").is_ok());
+        assert!(matches!(
+            check_signing_key(&key_pair.sk, &other.pk.to_base64(), "Cargo.toml"),
+            Err(Error::SigningKeyMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_sign_file_writes_a_verifiable_signature() {
+        let key_pair = KeyPair::generate_unencrypted_keypair().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pcu.tar.gz");
+        fs::write(&path, "release archive").unwrap();
+
+        let (sig_path, sig_name) =
+            sign_file(&key_pair.sk, &key_pair.pk, &path, "pcu.tar.gz", dir.path()).unwrap();
+
+        assert_eq!(sig_name, "pcu.tar.gz.minisig");
+        let signature = SignatureBox::from_file(&sig_path).unwrap();
+        assert!(signature
+            .trusted_comment()
+            .unwrap()
+            .contains("file:pcu.tar.gz"));
+        minisign::verify(
+            &key_pair.pk,
+            &signature,
+            fs::File::open(&path).unwrap(),
+            true,
+            false,
+            false,
+        )
+        .unwrap();
+    }
+}
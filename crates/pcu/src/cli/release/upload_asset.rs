@@ -0,0 +1,639 @@
+//! `pcu release upload-asset`: attach files to an existing GitHub release.
+//!
+//! The asset paths may be files, directories or globs. Files already on the
+//! release with the same name and size are skipped, the rest are uploaded a
+//! few at a time, each with its own retries, and a `SHA256SUMS` covering every
+//! file is uploaded last. The checksums of assets uploaded by earlier runs are
+//! kept, so a release whose assets come from several jobs still ends with one
+//! `SHA256SUMS` listing them all.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use octocrate::{APIConfig, GitHubAPI, PersonalAccessToken};
+use pcu_release_assets::ReleaseRef;
+use sha2::Digest;
+use tokio::task::JoinSet;
+
+use super::{Mode, Release};
+use crate::{
+    client::{map_asset_upload_error, ReleaseAsset},
+    CIExit, Client, Error,
+};
+
+/// The name of the checksums asset, in the format `sha256sum --check` reads.
+pub(crate) const SHA256SUMS: &str = "SHA256SUMS";
+
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+impl Release {
+    pub(super) async fn upload_asset(self, client: Client) -> Result<CIExit, Error> {
+        let Mode::UploadAsset(ref cmd) = self.mode else {
+            return Err(Error::NoPackageSpecified);
+        };
+
+        let mut paths = resolve_asset_paths(&cmd.asset_path)?;
+        if !cmd.no_checksums {
+            paths.retain(|path| {
+                let generated = path.file_name().is_some_and(|name| name == SHA256SUMS);
+                if generated {
+                    log::info!("Not uploading {}: pcu generates it", path.display());
+                }
+                !generated
+            });
+        }
+        let local = local_assets(paths, cmd.asset_name.as_deref())?;
+
+        let release_ref = mutable_release(&client, &cmd.tag).await?;
+        let existing = client.release_assets(release_ref.id).await?;
+        let (uploads, skipped) = plan_uploads(&local, &existing);
+        for name in &skipped {
+            log::info!("Skipping {name}: already on the release with the same size");
+        }
+
+        let uploader = Uploader::new(&client, &cmd.tag, release_ref.id, cmd.max_attempts);
+        upload_all(&uploader, uploads, cmd.concurrency).await?;
+
+        if !cmd.no_checksums {
+            let mut sums = BTreeMap::new();
+            for asset in &local {
+                sums.insert(asset.name.clone(), sha256_hex(&asset.path)?);
+            }
+
+            let previous = existing.iter().find(|asset| asset.name == SHA256SUMS);
+            let previous_sums = match previous {
+                Some(_) => {
+                    let bytes = client
+                        .download_release_asset(&cmd.tag, SHA256SUMS, true)
+                        .await?;
+                    Some(String::from_utf8_lossy(&bytes).into_owned())
+                }
+                None => None,
+            };
+
+            let content = merge_sha256sums(previous_sums.as_deref(), &sums);
+            if previous_sums.as_deref() == Some(content.as_str()) {
+                log::info!("{SHA256SUMS} is already up to date");
+            } else {
+                let dir = tempfile::tempdir()?;
+                let path = dir.path().join(SHA256SUMS);
+                fs::write(&path, &content)?;
+                uploader
+                    .upload(&PlannedUpload {
+                        path,
+                        name: SHA256SUMS.to_string(),
+                        replace: previous.map(|asset| asset.id),
+                    })
+                    .await?;
+            }
+        }
+
+        Ok(CIExit::Released)
+    }
+}
+
+/// The release for `tag`, which must exist and still accept assets.
+pub(super) async fn mutable_release(client: &Client, tag: &str) -> Result<ReleaseRef, Error> {
+    log::info!("Looking up GitHub release for tag {tag}");
+
+    // Draft-aware: on a draft-first pipeline the release is still unpublished
+    // at this point, and a draft is invisible to get_release_by_tag.
+    let release_ref = client.find_release_for_tag(tag).await?.ok_or_else(|| {
+        Error::GitError(format!(
+            "no GitHub release found for tag '{tag}' to upload to"
+        ))
+    })?;
+
+    if release_ref.immutable {
+        return Err(Error::ImmutableRelease(
+            tag.to_string(),
+            "the release is published with immutable assets".to_string(),
+        ));
+    }
+
+    Ok(release_ref)
+}
+
+/// A file to attach to the release, under the asset name it will have there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct LocalAsset {
+    pub(super) path: PathBuf,
+    pub(super) name: String,
+    size: u64,
+}
+
+/// An upload to make, replacing the asset `replace` already on the release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct PlannedUpload {
+    pub(super) path: PathBuf,
+    pub(super) name: String,
+    pub(super) replace: Option<i64>,
+}
+
+/// The files named by the `--asset-path` values, in order and without
+/// duplicates. A directory stands for the files directly in it; a value that
+/// is not a path is read as a glob, which must match at least one file.
+pub(super) fn resolve_asset_paths(args: &[String]) -> Result<Vec<PathBuf>, Error> {
+    let mut paths = Vec::new();
+
+    for arg in args {
+        let path = Path::new(arg);
+        if path.is_dir() {
+            let mut files = fs::read_dir(path)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            files.retain(|file| file.is_file());
+            files.sort();
+            paths.extend(files);
+        } else if path.is_file() {
+            paths.push(path.to_path_buf());
+        } else if arg.contains(['*', '?', '[']) {
+            let matches = glob::glob(arg)
+                .map_err(|e| Error::AssetUpload(format!("invalid glob `{arg}`: {e}")))?
+                .filter_map(Result::ok)
+                .filter(|file| file.is_file())
+                .collect::<Vec<_>>();
+            if matches.is_empty() {
+                return Err(Error::AssetUpload(format!("no files match `{arg}`")));
+            }
+            paths.extend(matches);
+        } else {
+            return Err(Error::AssetUpload(format!("asset file not found: {arg}")));
+        }
+    }
+
+    let mut seen = BTreeSet::new();
+    paths.retain(|path| seen.insert(path.clone()));
+    Ok(paths)
+}
+
+/// Name and size each file. `asset_name` renames a single file; two files
+/// with the same name would overwrite each other on the release and are
+/// refused.
+pub(super) fn local_assets(
+    paths: Vec<PathBuf>,
+    asset_name: Option<&str>,
+) -> Result<Vec<LocalAsset>, Error> {
+    if asset_name.is_some() && paths.len() != 1 {
+        return Err(Error::AssetUpload(format!(
+            "--asset-name names a single file, but {} files were given",
+            paths.len()
+        )));
+    }
+
+    let mut names = BTreeSet::new();
+    let mut assets = Vec::new();
+    for path in paths {
+        let name = match asset_name {
+            Some(name) => name.to_string(),
+            None => path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .ok_or_else(|| Error::AssetUpload(format!("no file name in {}", path.display())))?,
+        };
+        if !names.insert(name.clone()) {
+            return Err(Error::AssetUpload(format!(
+                "more than one file would be uploaded as {name}"
+            )));
+        }
+        let size = fs::metadata(&path)?.len();
+        assets.push(LocalAsset { path, name, size });
+    }
+
+    Ok(assets)
+}
+
+/// Split `local` into the uploads to make and the names of the assets to
+/// skip because the release already has them at the same size. An asset of
+/// the same name but another size is replaced.
+fn plan_uploads(
+    local: &[LocalAsset],
+    existing: &[ReleaseAsset],
+) -> (Vec<PlannedUpload>, Vec<String>) {
+    let mut uploads = Vec::new();
+    let mut skipped = Vec::new();
+
+    for asset in local {
+        let found = existing.iter().find(|e| e.name == asset.name);
+        match found {
+            Some(e) if e.size == asset.size => skipped.push(asset.name.clone()),
+            _ => uploads.push(PlannedUpload {
+                path: asset.path.clone(),
+                name: asset.name.clone(),
+                replace: found.map(|e| e.id),
+            }),
+        }
+    }
+
+    (uploads, skipped)
+}
+
+pub(super) fn sha256_hex(path: &Path) -> Result<String, Error> {
+    Ok(hex_encode(&sha2::Sha256::digest(fs::read(path)?)))
+}
+
+/// Lower-case hex of `bytes`, the form digests take in SHA256SUMS, provenance
+/// and the trusted root.
+pub(super) fn hex_encode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut s, b| {
+            write!(s, "{b:02x}").unwrap();
+            s
+        })
+}
+
+/// The `SHA256SUMS` content for `sums` (asset name to hex digest), keeping
+/// the entries of `previous` for assets not in `sums`.
+fn merge_sha256sums(previous: Option<&str>, sums: &BTreeMap<String, String>) -> String {
+    let mut merged = previous
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(hash, name)| {
+            // `sha256sum` marks a file read in binary mode with `*`.
+            let name = name.trim_start_matches(' ').trim_start_matches('*');
+            (name.to_string(), hash.to_string())
+        })
+        .collect::<BTreeMap<_, _>>();
+    merged.extend(sums.clone());
+
+    merged
+        .iter()
+        .map(|(name, hash)| format!("{hash}  {name}\n"))
+        .collect()
+}
+
+/// What an upload task needs from the [`Client`], which cannot be shared
+/// with spawned tasks because it holds a `git2::Repository`.
+#[derive(Clone)]
+pub(super) struct Uploader {
+    rest: Arc<GitHubAPI>,
+    /// Binary uploads must go to uploads.github.com, not api.github.com.
+    uploads: Arc<GitHubAPI>,
+    owner: String,
+    repo: String,
+    tag: String,
+    release_id: i64,
+    max_attempts: u32,
+}
+
+impl Uploader {
+    pub(super) fn new(client: &Client, tag: &str, release_id: i64, max_attempts: u32) -> Self {
+        let upload_token = PersonalAccessToken::new(client.github_token.clone());
+        let upload_config = APIConfig::new("https://uploads.github.com", upload_token);
+
+        Self {
+            rest: client.github_rest.clone(),
+            uploads: Arc::new(GitHubAPI::new(&upload_config)),
+            owner: client.owner().to_string(),
+            repo: client.repo().to_string(),
+            tag: tag.to_string(),
+            release_id,
+            max_attempts,
+        }
+    }
+
+    async fn upload(&self, upload: &PlannedUpload) -> Result<(), Error> {
+        with_retry(&upload.name, self.max_attempts, RETRY_DELAY, |attempt| {
+            self.try_upload(upload, attempt)
+        })
+        .await?;
+        log::info!("Successfully uploaded {}", upload.name);
+        Ok(())
+    }
+
+    async fn try_upload(&self, upload: &PlannedUpload, attempt: u32) -> Result<(), Error> {
+        // A failed attempt can leave a partial asset behind, which would make
+        // the retry fail with GitHub's 422 "Validation Failed".
+        let replace = match attempt {
+            1 => upload.replace,
+            _ => self.asset_id(&upload.name).await?,
+        };
+        if let Some(asset_id) = replace {
+            log::info!("Replacing existing asset '{}' (id={asset_id})", upload.name);
+            self.rest
+                .repos
+                .delete_release_asset(&self.owner, &self.repo, asset_id)
+                .send()
+                .await
+                .map_err(|e| map_asset_upload_error(&self.tag, &e.to_string()))?;
+        }
+
+        let file = tokio::fs::File::open(&upload.path).await?;
+        let content_length = file.metadata().await?.len();
+
+        // Signatures and checksums are text; binaries use octet-stream
+        let content_type = if upload.name.ends_with(".sig")
+            || upload.name.ends_with(".minisig")
+            || upload.name == SHA256SUMS
+        {
+            "text/plain"
+        } else {
+            "application/octet-stream"
+        };
+
+        let query = octocrate::repos::upload_release_asset::Query::builder()
+            .name(upload.name.clone())
+            .build();
+
+        self.uploads
+            .repos
+            .upload_release_asset(&self.owner, &self.repo, self.release_id)
+            .query(&query)
+            .header("Content-Type", content_type)
+            .header("Content-Length", content_length.to_string())
+            .file(file)
+            .send()
+            .await
+            .map_err(|e| map_asset_upload_error(&self.tag, &e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn asset_id(&self, name: &str) -> Result<Option<i64>, Error> {
+        let release = self
+            .rest
+            .repos
+            .get_release(&self.owner, &self.repo, self.release_id)
+            .send()
+            .await?;
+
+        Ok(release
+            .assets
+            .into_iter()
+            .find(|asset| asset.name == name)
+            .map(|asset| asset.id))
+    }
+}
+
+/// Upload every planned asset, at most `concurrency` at a time, and report
+/// all the uploads that failed rather than stopping at the first.
+pub(super) async fn upload_all(
+    uploader: &Uploader,
+    uploads: Vec<PlannedUpload>,
+    concurrency: usize,
+) -> Result<(), Error> {
+    let total = uploads.len();
+    let mut tasks = JoinSet::new();
+    let mut failures = Vec::new();
+
+    for upload in uploads {
+        if tasks.len() >= concurrency.max(1) {
+            if let Some(joined) = tasks.join_next().await {
+                collect_failure(joined, &mut failures);
+            }
+        }
+        let uploader = uploader.clone();
+        tasks.spawn(async move {
+            let result = uploader.upload(&upload).await;
+            (upload.name, result)
+        });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        collect_failure(joined, &mut failures);
+    }
+
+    // The immutable-release error carries the remedy; return it as it is.
+    if let Some(index) = failures
+        .iter()
+        .position(|(_, e)| matches!(e, Error::ImmutableRelease(_, _)))
+    {
+        return Err(failures.swap_remove(index).1);
+    }
+
+    if failures.is_empty() {
+        return Ok(());
+    }
+    failures.sort_by(|a, b| a.0.cmp(&b.0));
+    let details = failures
+        .iter()
+        .map(|(name, e)| format!("\n  {name}: {e}"))
+        .collect::<String>();
+    Err(Error::AssetUpload(format!(
+        "{} of {total} uploads failed:{details}",
+        failures.len()
+    )))
+}
+
+fn collect_failure(
+    joined: Result<(String, Result<(), Error>), tokio::task::JoinError>,
+    failures: &mut Vec<(String, Error)>,
+) {
+    match joined {
+        Ok((_, Ok(()))) => {}
+        Ok((name, Err(e))) => failures.push((name, e)),
+        Err(e) => failures.push(("upload task".to_string(), Error::AssetUpload(e.to_string()))),
+    }
+}
+
+/// Run `attempt_fn` up to `max_attempts` times, waiting `retry_delay` after
+/// each failure, and return the last error if every attempt fails. An
+/// immutable release is not retried: no attempt can succeed.
+async fn with_retry<F, Fut>(
+    name: &str,
+    max_attempts: u32,
+    retry_delay: Duration,
+    mut attempt_fn: F,
+) -> Result<(), Error>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<(), Error>>,
+{
+    let max_attempts = max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        match attempt_fn(attempt).await {
+            Ok(()) => return Ok(()),
+            Err(e @ Error::ImmutableRelease(_, _)) => return Err(e),
+            Err(e) if attempt >= max_attempts => return Err(e),
+            Err(e) => {
+                log::warn!("Upload attempt {attempt} of {name} failed: {e}");
+                log::info!("Retrying in {}s...", retry_delay.as_secs());
+                tokio::time::sleep(retry_delay).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    fn write_files(dir: &Path, names: &[&str]) {
+        for name in names {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, name.as_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_resolve_asset_paths_expands_directories_and_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        write_files(
+            dir.path(),
+            &[
+                "dist/b.tar.gz",
+                "dist/a.tar.gz",
+                "dist/nested/c.tar.gz",
+                "pcu.sig",
+            ],
+        );
+        let root = dir.path().display();
+
+        let paths = resolve_asset_paths(&[
+            format!("{root}/dist"),
+            format!("{root}/dist/*.tar.gz"),
+            format!("{root}/pcu.sig"),
+        ])
+        .unwrap();
+
+        let names = paths
+            .iter()
+            .map(|p| p.strip_prefix(dir.path()).unwrap().display().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["dist/a.tar.gz", "dist/b.tar.gz", "pcu.sig"]);
+    }
+
+    #[test]
+    fn test_resolve_asset_paths_refuses_missing_files_and_empty_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().display();
+
+        for arg in [format!("{root}/missing.tar.gz"), format!("{root}/*.zip")] {
+            assert!(matches!(
+                resolve_asset_paths(&[arg]),
+                Err(Error::AssetUpload(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_local_assets_refuses_clashing_names() {
+        let dir = tempfile::tempdir().unwrap();
+        write_files(dir.path(), &["x/pcu.tar.gz", "y/pcu.tar.gz"]);
+        let paths = vec![
+            dir.path().join("x/pcu.tar.gz"),
+            dir.path().join("y/pcu.tar.gz"),
+        ];
+
+        assert!(matches!(
+            local_assets(paths.clone(), None),
+            Err(Error::AssetUpload(_))
+        ));
+        assert!(matches!(
+            local_assets(paths, Some("pcu.tar.gz")),
+            Err(Error::AssetUpload(_))
+        ));
+    }
+
+    #[test]
+    fn test_plan_uploads_skips_assets_of_the_same_name_and_size() {
+        let asset = |name: &str, size| LocalAsset {
+            path: PathBuf::from(name),
+            name: name.to_string(),
+            size,
+        };
+        let local = vec![asset("same", 10), asset("resized", 20), asset("new", 30)];
+        let existing = vec![
+            ReleaseAsset {
+                id: 1,
+                name: "same".to_string(),
+                size: 10,
+            },
+            ReleaseAsset {
+                id: 2,
+                name: "resized".to_string(),
+                size: 15,
+            },
+        ];
+
+        let (uploads, skipped) = plan_uploads(&local, &existing);
+
+        assert_eq!(skipped, vec!["same"]);
+        assert_eq!(
+            uploads
+                .iter()
+                .map(|u| (u.name.as_str(), u.replace))
+                .collect::<Vec<_>>(),
+            vec![("resized", Some(2)), ("new", None)]
+        );
+    }
+
+    #[test]
+    fn test_merge_sha256sums_keeps_earlier_entries() {
+        let previous = "aaaa  pcu-linux.tar.gz\nbbbb *pcu.sig\n";
+        let sums = BTreeMap::from([
+            ("pcu.sig".to_string(), "cccc".to_string()),
+            ("pcu-macos.tar.gz".to_string(), "dddd".to_string()),
+        ]);
+
+        assert_eq!(
+            merge_sha256sums(Some(previous), &sums),
+            "aaaa  pcu-linux.tar.gz\ndddd  pcu-macos.tar.gz\ncccc  pcu.sig\n"
+        );
+        assert_eq!(
+            merge_sha256sums(None, &sums),
+            "dddd  pcu-macos.tar.gz\ncccc  pcu.sig\n"
+        );
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc");
+        fs::write(&path, "abc").unwrap();
+
+        assert_eq!(
+            sha256_hex(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_retries_until_an_attempt_succeeds() {
+        let attempts = AtomicU32::new(0);
+        let result = with_retry("pcu.tar.gz", 3, Duration::ZERO, |attempt| {
+            attempts.store(attempt, Ordering::SeqCst);
+            async move {
+                match attempt {
+                    1 => Err(Error::GitError("connection reset".to_string())),
+                    _ => Ok(()),
+                }
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_gives_up_on_an_immutable_release() {
+        let attempts = AtomicU32::new(0);
+        let result = with_retry("pcu.tar.gz", 3, Duration::ZERO, |_| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            async {
+                Err(Error::ImmutableRelease(
+                    "v1.2.0".to_string(),
+                    "immutable".to_string(),
+                ))
+            }
+        })
+        .await;
+
+        assert!(matches!(result, Err(Error::ImmutableRelease(_, _))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,557 @@
+//! `pcu release verify-attestation`: check a Sigstore bundle written by
+//! `pcu release attest` against a supplied trusted root, with no network.
+//!
+//! sigstore checks the Fulcio certificate chain and its SCT, the identity in
+//! the certificate, the signature and that the Rekor entry matches the bundle.
+//! It does not yet check the Rekor inclusion proof (sigstore-rs#285), so that
+//! is done here: the entry is hashed up to the proof's root, and the root is
+//! checked against the checkpoint the Rekor key signed.
+
+use std::{fs, path::Path};
+
+use base64::Engine as _;
+use regex::Regex;
+use serde::Deserialize;
+use sha2::{Digest as _, Sha256};
+use sigstore::{
+    bundle::{
+        verify::{
+            policy::{OIDCIssuer, PolicyError, PolicyResult, SingleX509ExtPolicy},
+            VerificationPolicy, Verifier,
+        },
+        Bundle,
+    },
+    crypto::{CosignVerificationKey, Signature},
+    rekor::apis::configuration::Configuration as RekorConfiguration,
+    trust::{sigstore::SigstoreTrustRoot, TrustRoot},
+};
+use sigstore_protobuf_specs::dev::sigstore::rekor::v1::{InclusionProof, TransparencyLogEntry};
+use x509_cert::ext::pkix::{name::GeneralName, SubjectAltName};
+
+use super::{mode::VerifyAttestation, upload_asset::hex_encode, Release};
+use crate::{CIExit, Error};
+
+impl Release {
+    pub(super) async fn verify_attestation(
+        &self,
+        cmd: &VerifyAttestation,
+    ) -> Result<CIExit, Error> {
+        let artifact = fs::read(&cmd.artifact)?;
+        let artifact_name = file_name(&cmd.artifact);
+        let bundle_path = cmd.bundle.clone().unwrap_or_else(|| {
+            cmd.artifact
+                .with_file_name(format!("{artifact_name}.sigstore.json"))
+        });
+
+        let bundle: Bundle = serde_json::from_slice(&fs::read(&bundle_path)?).map_err(|e| {
+            Error::AttestationNotVerified(format!("cannot read {}: {e}", bundle_path.display()))
+        })?;
+        let trust_root =
+            SigstoreTrustRoot::from_trusted_root_json_unchecked(&fs::read(&cmd.trusted_root)?)
+                .map_err(|e| {
+                    Error::AttestationNotVerified(format!(
+                        "cannot read the trusted root {}: {e}",
+                        cmd.trusted_root.display()
+                    ))
+                })?;
+        let identity = IdentityPattern::new(
+            &cmd.certificate_oidc_issuer,
+            &cmd.certificate_identity_regexp,
+        )?;
+
+        let tlog_entry = bundle
+            .verification_material
+            .as_ref()
+            .and_then(|material| material.tlog_entries.first())
+            .cloned()
+            .ok_or_else(|| {
+                Error::AttestationNotVerified("the bundle has no Rekor entry".to_string())
+            })?;
+        verify_tlog_inclusion(&tlog_entry, &trust_root)?;
+        log::info!("Rekor inclusion proof verified");
+
+        let verifier = Verifier::new(RekorConfiguration::default(), trust_root)
+            .map_err(|e| Error::AttestationNotVerified(e.to_string()))?;
+        verifier
+            .verify(artifact.as_slice(), bundle, &identity, true)
+            .await
+            .map_err(|e| Error::AttestationNotVerified(e.to_string()))?;
+        log::info!("Certificate, identity and signature verified for {artifact_name}");
+
+        if let Some(provenance) = &cmd.provenance {
+            check_provenance_subject(&fs::read(provenance)?, &artifact_name, &artifact)?;
+            log::info!("Provenance subject matches {artifact_name}");
+        }
+        for subject in &cmd.subject {
+            let name = file_name(subject);
+            check_provenance_subject(&artifact, &name, &fs::read(subject)?)?;
+            log::info!("{artifact_name} lists {name} as a subject");
+        }
+
+        Ok(CIExit::AttestationVerified(artifact_name))
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The expected signer: an exact OIDC issuer, and a pattern one of the
+/// certificate's subject alternative names must match in full.
+struct IdentityPattern {
+    issuer: OIDCIssuer,
+    san: Regex,
+}
+
+impl IdentityPattern {
+    fn new(issuer: &str, san: &str) -> Result<Self, Error> {
+        let san = Regex::new(&format!("^(?:{san})$"))
+            .map_err(|e| Error::AttestationNotVerified(format!("invalid identity pattern: {e}")))?;
+        Ok(Self {
+            issuer: OIDCIssuer::new(issuer),
+            san,
+        })
+    }
+}
+
+impl VerificationPolicy for IdentityPattern {
+    fn verify(&self, cert: &x509_cert::Certificate) -> PolicyResult {
+        self.issuer.verify(cert)?;
+
+        let Ok(Some((_, san))) = cert.tbs_certificate.get::<SubjectAltName>() else {
+            return Err(PolicyError::ExtensionNotFound);
+        };
+        let names: Vec<String> = san
+            .0
+            .iter()
+            .filter_map(|name| match name {
+                GeneralName::Rfc822Name(name) => Some(name.to_string()),
+                GeneralName::UniformResourceIdentifier(name) => Some(name.to_string()),
+                GeneralName::OtherName(name) => std::str::from_utf8(name.value.value())
+                    .ok()
+                    .map(String::from),
+                _ => None,
+            })
+            .collect();
+
+        if names.iter().any(|name| self.san.is_match(name)) {
+            Ok(())
+        } else {
+            Err(PolicyError::ExtensionCheckFailed {
+                extension: "SubjectAltName".to_string(),
+                expected: self.san.to_string(),
+                actual: names.join(", "),
+            })
+        }
+    }
+}
+
+/// Check the entry's inclusion proof, and the checkpoint that signs its root
+/// with the key the trusted root holds for the entry's log.
+fn verify_tlog_inclusion(
+    entry: &TransparencyLogEntry,
+    trust_root: &impl TrustRoot,
+) -> Result<(), Error> {
+    let not_verified = |reason: &str| Error::AttestationNotVerified(reason.to_string());
+
+    let proof = entry
+        .inclusion_proof
+        .as_ref()
+        .ok_or_else(|| not_verified("the Rekor entry has no inclusion proof"))?;
+    let log_id = entry
+        .log_id
+        .as_ref()
+        .ok_or_else(|| not_verified("the Rekor entry has no log id"))?;
+
+    let keys = trust_root
+        .rekor_keys()
+        .map_err(|e| Error::AttestationNotVerified(e.to_string()))?;
+    let key = keys
+        .get(&hex_encode(&log_id.key_id))
+        .ok_or_else(|| not_verified("the trusted root has no key for the Rekor log"))?;
+    let key = CosignVerificationKey::try_from_der(key)
+        .map_err(|e| Error::AttestationNotVerified(format!("invalid Rekor key: {e}")))?;
+
+    let leaf = leaf_hash(&entry.canonicalized_body);
+    if !verify_inclusion(proof, &leaf) {
+        return Err(not_verified(
+            "the Rekor inclusion proof does not lead to its root hash",
+        ));
+    }
+
+    let envelope = proof
+        .checkpoint
+        .as_ref()
+        .map(|checkpoint| checkpoint.envelope.as_str())
+        .ok_or_else(|| not_verified("the Rekor inclusion proof has no checkpoint"))?;
+    let checkpoint = Checkpoint::parse(envelope)?;
+    if checkpoint.size != proof.tree_size || checkpoint.root_hash != proof.root_hash {
+        return Err(not_verified(
+            "the Rekor checkpoint is for another tree than the inclusion proof",
+        ));
+    }
+    if !checkpoint.signatures.iter().any(|sig| {
+        key.verify_signature(Signature::Raw(sig), checkpoint.body.as_bytes())
+            .is_ok()
+    }) {
+        return Err(not_verified(
+            "the Rekor checkpoint is not signed by the trusted log key",
+        ));
+    }
+
+    Ok(())
+}
+
+/// RFC 6962 hash of a log leaf.
+fn leaf_hash(body: &[u8]) -> [u8; 32] {
+    Sha256::new_with_prefix([0x00])
+        .chain_update(body)
+        .finalize()
+        .into()
+}
+
+/// RFC 6962 hash of an interior node.
+fn node_hash(left: &[u8], right: &[u8]) -> [u8; 32] {
+    Sha256::new_with_prefix([0x01])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Verify an inclusion proof as RFC 9162 section 2.1.3.2 describes.
+fn verify_inclusion(proof: &InclusionProof, leaf: &[u8; 32]) -> bool {
+    let (Ok(index), Ok(size)) = (
+        u64::try_from(proof.log_index),
+        u64::try_from(proof.tree_size),
+    ) else {
+        return false;
+    };
+    if index >= size {
+        return false;
+    }
+
+    let (mut f_n, mut s_n) = (index, size - 1);
+    let mut hash = *leaf;
+    for sibling in &proof.hashes {
+        if s_n == 0 {
+            return false;
+        }
+        if f_n & 1 == 1 || f_n == s_n {
+            hash = node_hash(sibling, &hash);
+            while f_n & 1 == 0 && f_n != 0 {
+                f_n >>= 1;
+                s_n >>= 1;
+            }
+        } else {
+            hash = node_hash(&hash, sibling);
+        }
+        f_n >>= 1;
+        s_n >>= 1;
+    }
+
+    s_n == 0 && hash.as_slice() == proof.root_hash.as_slice()
+}
+
+/// A Rekor checkpoint: a signed note carrying the tree size and root hash.
+#[derive(Debug)]
+struct Checkpoint {
+    /// The signed text, up to the blank line before the signatures
+    body: String,
+    size: i64,
+    root_hash: Vec<u8>,
+    /// Each signature, without its four-byte key hint
+    signatures: Vec<Vec<u8>>,
+}
+
+impl Checkpoint {
+    fn parse(envelope: &str) -> Result<Self, Error> {
+        let malformed = || Error::AttestationNotVerified("malformed Rekor checkpoint".to_string());
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        let (body, signatures) = envelope.split_once("\n\n").ok_or_else(malformed)?;
+        let mut lines = body.lines().skip(1);
+        let size = lines
+            .next()
+            .and_then(|size| size.parse().ok())
+            .ok_or_else(malformed)?;
+        let root_hash = lines
+            .next()
+            .and_then(|root| engine.decode(root).ok())
+            .ok_or_else(malformed)?;
+
+        let signatures = signatures
+            .lines()
+            .filter_map(|line| line.strip_prefix("\u{2014} "))
+            .filter_map(|line| line.split_once(' '))
+            .filter_map(|(_, sig)| engine.decode(sig).ok())
+            .filter(|sig| sig.len() > 4)
+            .map(|sig| sig[4..].to_vec())
+            .collect::<Vec<_>>();
+        if signatures.is_empty() {
+            return Err(malformed());
+        }
+
+        Ok(Self {
+            body: format!("{body}\n"),
+            size,
+            root_hash,
+            signatures,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ProvenanceSubjects {
+    subject: Vec<ProvenanceSubject>,
+}
+
+#[derive(Deserialize)]
+struct ProvenanceSubject {
+    name: String,
+    digest: std::collections::BTreeMap<String, String>,
+}
+
+/// Check that the provenance, SLSA v1 or v0.2, names the artifact with its
+/// SHA-256 among its subjects.
+fn check_provenance_subject(provenance: &[u8], name: &str, artifact: &[u8]) -> Result<(), Error> {
+    let provenance: ProvenanceSubjects = serde_json::from_slice(provenance).map_err(|e| {
+        Error::AttestationNotVerified(format!("cannot read the provenance subjects: {e}"))
+    })?;
+    let digest = hex_encode(&Sha256::digest(artifact));
+
+    if provenance
+        .subject
+        .iter()
+        .any(|subject| subject.digest.get("sha256") == Some(&digest))
+    {
+        return Ok(());
+    }
+
+    let named = provenance
+        .subject
+        .iter()
+        .find(|subject| subject.name == name)
+        .and_then(|subject| subject.digest.get("sha256"));
+    Err(Error::AttestationNotVerified(match named {
+        Some(recorded) => {
+            format!("the provenance records sha256 {recorded} for {name}, not {digest}")
+        }
+        None => format!("no provenance subject has the sha256 {digest} of {name}"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use sigstore::{crypto::SigningScheme, trust::ManualTrustRoot};
+    use sigstore_protobuf_specs::dev::sigstore::{
+        common::v1::LogId, rekor::v1::Checkpoint as CheckpointEnvelope,
+    };
+
+    use super::*;
+
+    /// RFC 6962 Merkle tree hash of `leaves`.
+    fn tree_hash(leaves: &[[u8; 32]]) -> [u8; 32] {
+        match leaves.len() {
+            1 => leaves[0],
+            n => {
+                let split = n.next_power_of_two() / 2;
+                node_hash(&tree_hash(&leaves[..split]), &tree_hash(&leaves[split..]))
+            }
+        }
+    }
+
+    /// RFC 6962 audit path for leaf `index`.
+    fn audit_path(leaves: &[[u8; 32]], index: usize) -> Vec<Vec<u8>> {
+        if leaves.len() == 1 {
+            return Vec::new();
+        }
+        let split = leaves.len().next_power_of_two() / 2;
+        if index < split {
+            let mut path = audit_path(&leaves[..split], index);
+            path.push(tree_hash(&leaves[split..]).to_vec());
+            path
+        } else {
+            let mut path = audit_path(&leaves[split..], index - split);
+            path.push(tree_hash(&leaves[..split]).to_vec());
+            path
+        }
+    }
+
+    fn leaves(count: usize) -> Vec<[u8; 32]> {
+        (0..count)
+            .map(|i| leaf_hash(format!("entry {i}").as_bytes()))
+            .collect()
+    }
+
+    fn proof(leaves: &[[u8; 32]], index: usize) -> InclusionProof {
+        InclusionProof {
+            log_index: index as i64,
+            root_hash: tree_hash(leaves).to_vec(),
+            tree_size: leaves.len() as i64,
+            hashes: audit_path(leaves, index),
+            checkpoint: None,
+        }
+    }
+
+    #[test]
+    fn test_verify_inclusion_for_every_leaf_of_uneven_trees() {
+        for size in [1, 2, 3, 5, 7, 8, 13] {
+            let leaves = leaves(size);
+            for index in 0..size {
+                assert!(
+                    verify_inclusion(&proof(&leaves, index), &leaves[index]),
+                    "leaf {index} of {size}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_inclusion_rejects_a_wrong_leaf_or_path() {
+        let leaves = leaves(7);
+        let proof = proof(&leaves, 4);
+
+        assert!(!verify_inclusion(&proof, &leaves[3]));
+
+        let mut tampered = proof.clone();
+        tampered.hashes[0][0] ^= 1;
+        assert!(!verify_inclusion(&tampered, &leaves[4]));
+
+        let mut short = proof.clone();
+        short.hashes.pop();
+        assert!(!verify_inclusion(&short, &leaves[4]));
+
+        let mut out_of_range = proof;
+        out_of_range.log_index = 7;
+        assert!(!verify_inclusion(&out_of_range, &leaves[4]));
+    }
+
+    const LOG_ID: [u8; 4] = [0xde, 0xad, 0xbe, 0xef];
+
+    /// A Rekor entry for leaf `index` of a log of `size` entries, with a
+    /// checkpoint signed as Rekor signs one, and a trust root holding the key.
+    fn logged_entry(size: usize, index: usize) -> (TransparencyLogEntry, ManualTrustRoot<'static>) {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let signer = SigningScheme::ECDSA_P256_SHA256_ASN1
+            .create_signer()
+            .unwrap();
+
+        let bodies: Vec<_> = (0..size).map(|i| format!("entry {i}")).collect();
+        let leaves: Vec<_> = bodies
+            .iter()
+            .map(|body| leaf_hash(body.as_bytes()))
+            .collect();
+        let mut proof = proof(&leaves, index);
+
+        let note = format!(
+            "rekor.sigstore.dev - 1193050959916656506\n{}\n{}\n",
+            proof.tree_size,
+            engine.encode(&proof.root_hash)
+        );
+        let mut sig = LOG_ID.to_vec();
+        sig.extend(signer.sign(note.as_bytes()).unwrap());
+        proof.checkpoint = Some(CheckpointEnvelope {
+            envelope: format!(
+                "{note}\n\u{2014} rekor.sigstore.dev {}\n",
+                engine.encode(sig)
+            ),
+        });
+
+        let entry = TransparencyLogEntry {
+            canonicalized_body: bodies[index].clone().into_bytes(),
+            inclusion_proof: Some(proof),
+            log_id: Some(LogId {
+                key_id: LOG_ID.to_vec(),
+            }),
+            ..Default::default()
+        };
+        let key = signer
+            .to_sigstore_keypair()
+            .unwrap()
+            .public_key_to_der()
+            .unwrap();
+        let trust_root = ManualTrustRoot {
+            rekor_keys: BTreeMap::from([(hex_encode(&LOG_ID), key)]),
+            ..Default::default()
+        };
+
+        (entry, trust_root)
+    }
+
+    #[test]
+    fn test_verify_tlog_inclusion() {
+        let (entry, trust_root) = logged_entry(5, 2);
+        verify_tlog_inclusion(&entry, &trust_root).unwrap();
+    }
+
+    #[test]
+    fn test_verify_tlog_inclusion_rejects_a_changed_entry() {
+        let (mut entry, trust_root) = logged_entry(5, 2);
+        entry.canonicalized_body = b"entry 3".to_vec();
+
+        assert!(matches!(
+            verify_tlog_inclusion(&entry, &trust_root),
+            Err(Error::AttestationNotVerified(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_tlog_inclusion_rejects_another_log_key() {
+        let (entry, _) = logged_entry(5, 2);
+        let (_, other_root) = logged_entry(5, 2);
+
+        assert!(matches!(
+            verify_tlog_inclusion(&entry, &other_root),
+            Err(Error::AttestationNotVerified(_))
+        ));
+    }
+
+    #[test]
+    fn test_checkpoint_rejects_an_unsigned_note() {
+        assert!(matches!(
+            Checkpoint::parse("rekor.sigstore.dev - 1\n5\nAAAA\n"),
+            Err(Error::AttestationNotVerified(_))
+        ));
+    }
+
+    #[test]
+    fn test_identity_pattern_is_anchored() {
+        let identity = IdentityPattern::new(
+            "https://oidc.circleci.com/org/abc",
+            "https://circleci.com/gh/jerus-org/.*",
+        )
+        .unwrap();
+
+        assert!(identity
+            .san
+            .is_match("https://circleci.com/gh/jerus-org/pcu/1"));
+        assert!(!identity
+            .san
+            .is_match("https://evil.example/?https://circleci.com/gh/jerus-org/pcu"));
+        assert!(IdentityPattern::new("https://issuer", "(").is_err());
+    }
+
+    #[test]
+    fn test_check_provenance_subject() {
+        let artifact = b"crate bytes";
+        let digest = hex_encode(&Sha256::digest(artifact));
+        let provenance = format!(
+            r#"{{"_type": "https://in-toto.io/Statement/v1",
+                "subject": [{{"name": "pcu-0.6.34.crate", "digest": {{"sha256": "{digest}"}}}}]}}"#
+        );
+
+        assert!(
+            check_provenance_subject(provenance.as_bytes(), "pcu-0.6.34.crate", artifact).is_ok()
+        );
+
+        let err =
+            check_provenance_subject(provenance.as_bytes(), "pcu-0.6.34.crate", b"other bytes")
+                .unwrap_err();
+        assert!(err.to_string().contains(&digest));
+    }
+}
@@ -10,7 +10,7 @@ use octocrate::{APIConfig, AppAuthorization, GitHubAPI, PersonalAccessToken};
 use owo_colors::{OwoColorize, Style};
 
 use self::pull_request::PullRequest;
-use crate::{Error, PrTitle};
+use crate::{Error, PrTitle, SectionMap, TagTemplates};
 
 const END_POINT: &str = "https://api.github.com/graphql";
 
@@ -34,6 +34,10 @@ pub struct Client {
     pub(crate) line_limit: usize,
     pub(crate) prlog_parse_options: ChangelogParseOptions,
     pub(crate) prlog_update: Option<PrTitle>,
+    pub(crate) prlog_sections: SectionMap,
+    pub(crate) credit_authors: bool,
+    pub(crate) release_notes_template: Option<String>,
+    pub(crate) tag_templates: TagTemplates,
     pub(crate) commit_message: String,
 }
 
@@ -50,6 +54,10 @@ impl Debug for Client {
             .field("line_limit", &self.line_limit)
             .field("prlog_parse_options", &self.prlog_parse_options)
             .field("prlog_update", &self.prlog_update)
+            .field("prlog_sections", &self.prlog_sections)
+            .field("credit_authors", &self.credit_authors)
+            .field("release_notes_template", &self.release_notes_template)
+            .field("tag_templates", &self.tag_templates)
             .field("commit_message", &self.commit_message)
             .finish()
     }
@@ -130,24 +138,13 @@ impl Client {
         };
         log::trace!("branch: {branch:?} and pull_request: {pull_request:?}");
 
-        log::trace!("log: {:?}", settings.get::<String>("prlog"));
-        let prlog: String = settings
-            .get("prlog")
-            .map_err(|_| Error::DefaultChangeLogNotSet)?;
-        let prlog = OsString::from(prlog);
-
-        let svs_root = settings
-            .get("dev_platform")
-            .unwrap_or_else(|_| "https://github.com/".to_string());
-        let prefix = settings
-            .get("version_prefix")
-            .unwrap_or_else(|_| "v".to_string());
-        let repo_url = Some(format!("{svs_root}{owner}/{repo}"));
-        let prlog_parse_options = ChangelogParseOptions {
-            url: repo_url,
-            head: Some("HEAD".to_string()),
-            tag_prefix: Some(prefix),
-        };
+        let prlog = OsString::from(prlog_file(settings)?);
+        let prlog_sections = SectionMap::from_settings(settings)?;
+        let credit_authors = settings.get::<bool>("credit_authors").unwrap_or(false);
+        let release_notes_template = settings.get::<String>("release_notes_template").ok();
+        let tag_templates = TagTemplates::from_settings(settings)?;
+
+        let prlog_parse_options = prlog_parse_options(settings, &owner, &repo);
 
         let github_rest = Arc::new(github_rest);
         let github_graphql = Arc::new(github_graphql);
@@ -174,6 +171,10 @@ impl Client {
             line_limit,
             prlog_parse_options,
             prlog_update: None,
+            prlog_sections,
+            credit_authors,
+            release_notes_template,
+            tag_templates,
             commit_message,
         })
     }
@@ -280,6 +281,11 @@ impl Client {
         }
     }
 
+    /// Login of the pull request's author, if GitHub still knows the account
+    pub fn author(&self) -> Option<&str> {
+        self.pull_request.as_ref()?.author.as_deref()
+    }
+
     pub fn pr_number(&self) -> i64 {
         if let Some(pr) = self.pull_request.as_ref() {
             pr.pr_number
@@ -426,6 +432,10 @@ impl Client {
             line_limit: 10,
             prlog_parse_options,
             prlog_update: None,
+            prlog_sections: SectionMap::default(),
+            credit_authors: false,
+            release_notes_template: None,
+            tag_templates: TagTemplates::default(),
             commit_message: String::new(),
         })
     }
@@ -590,10 +600,11 @@ impl Client {
     /// it, returning its id if present.
     ///
     /// Used by `upload_release_asset` to decide whether to
-    /// delete-then-replace. `download_release_asset` delegates straight to
+    /// delete-then-replace, and by `pcu release check-binstall`.
+    /// `download_release_asset` delegates straight to
     /// `pcu_release_assets::ReleaseAssetClient`, which has its own copy of
     /// this lookup — it no longer calls this method.
-    async fn find_asset_in_release(
+    pub(crate) async fn find_asset_in_release(
         &self,
         release_id: i64,
         asset_name: &str,
@@ -609,13 +620,24 @@ impl Client {
     /// Unconditional by design — the release pipeline runs this as its last
     /// step with no condition attached — so it must be a
     /// no-op wherever there is nothing to publish. `make_latest` is set here
-    /// rather than at creation because GitHub does not accept it on a draft.
-    pub(crate) async fn publish_release(&self, release_id: i64) -> Result<(), Error> {
+    /// rather than at creation because GitHub does not accept it on a draft,
+    /// and a prerelease never becomes latest.
+    pub(crate) async fn publish_release(
+        &self,
+        release_id: i64,
+        prerelease: bool,
+    ) -> Result<(), Error> {
+        let make_latest = if prerelease {
+            octocrate::repos::update_release::RequestMakeLatest::False
+        } else {
+            octocrate::repos::update_release::RequestMakeLatest::True
+        };
+
         let request = octocrate::repos::update_release::Request {
             body: None,
             discussion_category_name: None,
             draft: Some(false),
-            make_latest: Some(octocrate::repos::update_release::RequestMakeLatest::True),
+            make_latest: Some(make_latest),
             name: None,
             prerelease: None,
             tag_name: None,
@@ -631,6 +653,57 @@ impl Client {
 
         Ok(())
     }
+
+    /// The names of the assets attached to the release `release_id`.
+    pub(crate) async fn release_asset_names(&self, release_id: i64) -> Result<Vec<String>, Error> {
+        Ok(self
+            .release_assets(release_id)
+            .await?
+            .into_iter()
+            .map(|asset| asset.name)
+            .collect())
+    }
+
+    /// The assets attached to the release `release_id`.
+    pub(crate) async fn release_assets(&self, release_id: i64) -> Result<Vec<ReleaseAsset>, Error> {
+        let release = self
+            .github_rest
+            .repos
+            .get_release(&self.owner, &self.repo, release_id)
+            .send()
+            .await?;
+
+        Ok(release
+            .assets
+            .into_iter()
+            .map(|asset| ReleaseAsset {
+                id: asset.id,
+                name: asset.name,
+                size: asset.size.try_into().unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// Delete the GitHub release `release_id`. Used only by `pcu release
+    /// abort`, after its guards have established the release is an
+    /// unpublished draft.
+    pub(crate) async fn delete_release(&self, release_id: i64) -> Result<(), Error> {
+        self.github_rest
+            .repos
+            .delete_release(&self.owner, &self.repo, release_id)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// An asset attached to a GitHub release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ReleaseAsset {
+    pub(crate) id: i64,
+    pub(crate) name: String,
+    pub(crate) size: u64,
 }
 
 /// Parse `owner` and `repo` from the `origin` remote URL of `repo`.
@@ -638,7 +711,7 @@ impl Client {
 /// Handles both SCP-style (`git@github.com:org/repo.git`) and HTTPS
 /// (`https://github.com/org/repo.git`) URLs.  Returns `None` if the remote
 /// is absent or the URL cannot be parsed.
-fn extract_owner_repo_from_git(repo: &Repository) -> Option<(String, String)> {
+pub(crate) fn extract_owner_repo_from_git(repo: &Repository) -> Option<(String, String)> {
     let remote = repo.find_remote("origin").ok()?;
     let url = remote.url().ok()?;
     parse_owner_repo_from_url(url)
@@ -666,6 +739,51 @@ fn parse_owner_repo_from_url(url: &str) -> Option<(String, String)> {
     Some((owner, repo))
 }
 
+/// The PRLOG file name from `settings`.
+///
+/// Normally the string `prlog` key. Once pcu.toml has a `[prlog.sections]`
+/// table, `prlog` is a table too and the file name moves to `prlog.file`,
+/// defaulting to PRLOG.md.
+pub(crate) fn prlog_file(settings: &Config) -> Result<String, Error> {
+    log::trace!("log: {:?}", settings.get::<String>("prlog"));
+    if let Ok(prlog) = settings.get::<String>("prlog") {
+        return Ok(prlog);
+    }
+
+    match settings.get_table("prlog") {
+        Ok(_) => Ok(settings
+            .get::<String>("prlog.file")
+            .unwrap_or_else(|_| "PRLOG.md".to_string())),
+        Err(_) => Err(Error::DefaultChangeLogNotSet),
+    }
+}
+
+/// Options for parsing the PRLOG of `owner/repo`: compare links point at the
+/// repository on `dev_platform` and tags carry the `version_prefix`.
+pub(crate) fn prlog_parse_options(
+    settings: &Config,
+    owner: &str,
+    repo: &str,
+) -> ChangelogParseOptions {
+    let svs_root = settings
+        .get("dev_platform")
+        .unwrap_or_else(|_| "https://github.com/".to_string());
+    let version_prefix = settings
+        .get("version_prefix")
+        .unwrap_or_else(|_| "v".to_string());
+    // A workspace template naming the crate cannot give the tags of a PRLOG
+    // shared by several crates; the version prefix stands in for it then.
+    let prefix = TagTemplates::from_settings(settings)
+        .and_then(|templates| templates.prefix(None, &version_prefix))
+        .unwrap_or(version_prefix);
+    let repo_url = Some(format!("{svs_root}{owner}/{repo}"));
+    ChangelogParseOptions {
+        url: repo_url,
+        head: Some("HEAD".to_string()),
+        tag_prefix: Some(prefix),
+    }
+}
+
 /// Build the error for "no release exists for this tag".
 /// Pure so the message is unit-testable without a network round trip.
 fn release_not_found_error(tag: &str) -> Error {
@@ -688,6 +806,43 @@ pub(crate) fn map_asset_upload_error(tag: &str, api_message: &str) -> Error {
 mod tests {
     use super::*;
 
+    fn settings_from(toml: &str) -> Config {
+        Config::builder()
+            .set_default("prlog", "PRLOG.md")
+            .unwrap()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn prlog_file_from_string_key() {
+        let settings = settings_from(r#"prlog = "CHANGES.md""#);
+        assert_eq!(prlog_file(&settings).unwrap(), "CHANGES.md");
+    }
+
+    #[test]
+    fn prlog_file_alongside_sections_table() {
+        let settings = settings_from(
+            r#"
+[prlog.sections.types.perf]
+section = "Changed"
+"#,
+        );
+        assert_eq!(prlog_file(&settings).unwrap(), "PRLOG.md");
+
+        let settings = settings_from(
+            r#"
+[prlog]
+file = "CHANGES.md"
+
+[prlog.sections.types.perf]
+section = "Changed"
+"#,
+        );
+        assert_eq!(prlog_file(&settings).unwrap(), "CHANGES.md");
+    }
+
     #[test]
     fn parse_https_url() {
         let (owner, repo) =
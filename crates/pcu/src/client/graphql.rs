@@ -11,7 +11,7 @@ mod label_pr;
 pub(crate) use create_label::GraphQLCreateLabel;
 pub(crate) use get_label_id::GraphQLGetLabel;
 pub(crate) use get_open_prs::GraphQLGetOpenPRs;
-pub(crate) use get_pr_by_commit::get_pull_request_by_commit;
+pub(crate) use get_pr_by_commit::{get_pull_request_by_commit, get_pull_request_by_past_commit};
 pub(crate) use get_pr_id::GraphQLGetPRId;
 pub(crate) use get_pr_title::get_pull_request_title;
 pub(crate) use get_repo_id::GraphQLGetRepoID;
@@ -58,6 +58,12 @@ struct PullRequest {
     url: String,
     #[serde(rename = "mergedAt")]
     merged_at: Option<String>,
+    author: Option<Author>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct Author {
+    login: String,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -71,7 +77,7 @@ struct Vars {
 #[derive(Debug)]
 enum QueryOutcome {
     /// A PR was found.
-    Found(i64, String, String, String),
+    Found(i64, String, String, String, Option<String>),
     /// The query succeeded but `associatedPullRequests` returned no nodes, or
     /// the commit object was not yet indexed.  Retrying may help.
     TransientEmpty,
@@ -80,13 +86,19 @@ enum QueryOutcome {
 }
 
 /// Select the best PR from a non-empty list and return the tuple fields.
-fn select_pr(prs: &[PullRequest]) -> Option<(i64, String, String, String)> {
+fn select_pr(prs: &[PullRequest]) -> Option<(i64, String, String, String, Option<String>)> {
     let pr = prs
         .iter()
         .filter(|pr| pr.merged_at.is_some())
         .max_by_key(|pr| pr.merged_at.as_ref())
         .or_else(|| prs.first())?;
-    Some((pr.number, pr.title.clone(), pr.url.clone(), pr.body.clone()))
+    Some((
+        pr.number,
+        pr.title.clone(),
+        pr.url.clone(),
+        pr.body.clone(),
+        pr.author.as_ref().map(|author| author.login.clone()),
+    ))
 }
 
 /// Classify a single query result as found, transient-empty, or a hard error.
@@ -108,7 +120,7 @@ fn classify_data(data: Data, attempt: u32) -> QueryOutcome {
         return QueryOutcome::TransientEmpty;
     }
     match select_pr(&prs) {
-        Some(result) => QueryOutcome::Found(result.0, result.1, result.2, result.3),
+        Some(result) => QueryOutcome::Found(result.0, result.1, result.2, result.3, result.4),
         None => QueryOutcome::HardError(Error::InvalidMergeCommitMessage),
     }
 }
@@ -124,7 +136,7 @@ fn classify_data(data: Data, attempt: u32) -> QueryOutcome {
 async fn get_pull_request_by_commit_with_retry<F>(
     query_fn: F,
     config: RetryConfig,
-) -> Result<(i64, String, String, String), Error>
+) -> Result<(i64, String, String, String, Option<String>), Error>
 where
     F: Fn() -> Pin<Box<dyn Future<Output = Result<Data, Error>> + Send>>,
 {
@@ -135,8 +147,8 @@ where
         let outcome = classify_query_result(query_fn().await, attempt);
 
         match outcome {
-            QueryOutcome::Found(number, title, url, body) => {
-                return Ok((number, title, url, body));
+            QueryOutcome::Found(number, title, url, body, author) => {
+                return Ok((number, title, url, body, author));
             }
             QueryOutcome::HardError(e) => {
                 return Err(e);
@@ -180,7 +192,40 @@ pub(crate) async fn get_pull_request_by_commit(
     owner: &str,
     name: &str,
     commit_sha: &str,
-) -> Result<(i64, String, String, String), Error> {
+) -> Result<(i64, String, String, String, Option<String>), Error> {
+    query_pull_request_by_commit(
+        github_graphql,
+        owner,
+        name,
+        commit_sha,
+        RetryConfig::default(),
+    )
+    .await
+}
+
+/// Like [`get_pull_request_by_commit`] but without retries, for commits that
+/// merged long ago: an empty `associatedPullRequests` then means there is no
+/// pull request rather than an index that has yet to catch up.
+pub(crate) async fn get_pull_request_by_past_commit(
+    github_graphql: &gql_client::Client,
+    owner: &str,
+    name: &str,
+    commit_sha: &str,
+) -> Result<(i64, String, String, String, Option<String>), Error> {
+    let config = RetryConfig {
+        max_retries: 0,
+        ..RetryConfig::default()
+    };
+    query_pull_request_by_commit(github_graphql, owner, name, commit_sha, config).await
+}
+
+async fn query_pull_request_by_commit(
+    github_graphql: &gql_client::Client,
+    owner: &str,
+    name: &str,
+    commit_sha: &str,
+    config: RetryConfig,
+) -> Result<(i64, String, String, String, Option<String>), Error> {
     let query = r#"
             query($owner: String!, $name: String!, $oid: GitObjectID!) {
                 repository(owner: $owner, name: $name) {
@@ -193,6 +238,9 @@ pub(crate) async fn get_pull_request_by_commit(
                                     body
                                     url
                                     mergedAt
+                                    author {
+                                        login
+                                    }
                                 }
                             }
                         }
@@ -227,7 +275,7 @@ pub(crate) async fn get_pull_request_by_commit(
         })
     };
 
-    get_pull_request_by_commit_with_retry(query_fn, RetryConfig::default()).await
+    get_pull_request_by_commit_with_retry(query_fn, config).await
 }
 
 #[cfg(test)]
@@ -269,6 +317,9 @@ mod tests {
             body: format!("Body of PR #{number}"),
             url: format!("https://github.com/owner/repo/pull/{number}"),
             merged_at: Some("2024-01-01T00:00:00Z".to_string()),
+            author: Some(Author {
+                login: format!("author{number}"),
+            }),
         }
     }
 
@@ -309,11 +360,12 @@ mod tests {
         let result = get_pull_request_by_commit_with_retry(query_fn, config).await;
 
         assert!(result.is_ok(), "Expected Ok after retry, got: {result:?}");
-        let (number, title, url, body) = result.unwrap();
+        let (number, title, url, body, author) = result.unwrap();
         assert_eq!(number, 42);
         assert_eq!(title, "PR #42");
         assert_eq!(url, "https://github.com/owner/repo/pull/42");
         assert_eq!(body, "Body of PR #42");
+        assert_eq!(author.as_deref(), Some("author42"));
 
         let final_count = *call_count.lock().unwrap();
         assert_eq!(
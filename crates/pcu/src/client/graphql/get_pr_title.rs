@@ -23,6 +23,12 @@ struct PullRequest {
     number: i64,
     title: String,
     body: String,
+    author: Option<Author>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct Author {
+    login: String,
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -38,7 +44,7 @@ pub(crate) async fn get_pull_request_title(
     owner: &str,
     name: &str,
     number: i64,
-) -> Result<(String, String), Error> {
+) -> Result<(String, String, Option<String>), Error> {
     let query = r#"
             query($owner:String!, $name:String!, $number:Int!){
                 repository(owner: $owner, name: $name) {
@@ -46,6 +52,9 @@ pub(crate) async fn get_pull_request_title(
                         number
                         title
                         body
+                        author {
+                            login
+                        }
                     }
                 }
             }
@@ -69,6 +78,11 @@ pub(crate) async fn get_pull_request_title(
 
     let title = data.repository.pull_request.title;
     let body = data.repository.pull_request.body;
+    let author = data
+        .repository
+        .pull_request
+        .author
+        .map(|author| author.login);
 
-    Ok((title, body))
+    Ok((title, body, author))
 }
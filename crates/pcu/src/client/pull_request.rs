@@ -10,6 +10,7 @@ pub(crate) struct PullRequest {
     pub(crate) pull_request: String,
     pub(crate) title: String,
     pub(crate) body: String,
+    pub(crate) author: Option<String>,
     #[allow(dead_code)]
     pub(crate) owner: String,
     #[allow(dead_code)]
@@ -49,13 +50,14 @@ impl PullRequest {
         let pr_number = pr_number.parse::<i64>()?;
 
         log::debug!("********* Using GraphQL");
-        let (title, body) =
+        let (title, body, author) =
             super::graphql::get_pull_request_title(graphql, &owner, &repo, pr_number).await?;
 
         Ok(Some(Self {
             pull_request,
             title,
             body,
+            author,
             owner,
             repo,
             repo_url,
@@ -80,7 +82,7 @@ impl PullRequest {
         log::debug!("Looking up PR for commit: {commit_sha}");
 
         // Query GitHub API to find associated PR
-        let (pr_number, title, pull_request, body) =
+        let (pr_number, title, pull_request, body, author) =
             super::graphql::get_pull_request_by_commit(graphql, owner, repo, &commit_sha).await?;
 
         log::debug!("Found PR #{pr_number}: {title}");
@@ -91,6 +93,7 @@ impl PullRequest {
             pull_request,
             title,
             body,
+            author,
             owner: owner.to_string(),
             repo: repo.to_string(),
             repo_url,
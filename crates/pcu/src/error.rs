@@ -42,6 +42,32 @@ pub enum Error {
     InvalidPath(OsString),
     #[error("Keep a prlog says: {0}")]
     KeepAChangelog(String),
+    #[error("Invalid prlog.sections configuration: {0}")]
+    InvalidSectionMap(String),
+    #[error("Invalid tag template: {0}")]
+    InvalidTagTemplate(String),
+    #[error("Release notes template: {0}")]
+    ReleaseNotesTemplate(String),
+    #[error("Invalid required_assets configuration: {0}")]
+    InvalidRequiredAssets(String),
+    #[error("Release {0} is missing required assets (pass --force to publish anyway):{1}")]
+    MissingReleaseAssets(String, String),
+    #[error("Invalid cargo-binstall metadata: {0}")]
+    InvalidBinstallMetadata(String),
+    #[error("cargo-binstall metadata does not match the assets of release {0}:{1}")]
+    BinstallAssetsMissing(String, String),
+    #[error("Asset upload: {0}")]
+    AssetUpload(String),
+    #[error("Signing error: {0}")]
+    Signing(String),
+    #[error("The minisign secret key (public key {1}) does not match the pubkey committed in {0}")]
+    SigningKeyMismatch(String, String),
+    #[error("SBOM: {0}")]
+    Sbom(String),
+    #[error("Release journal: {0}")]
+    ReleaseJournal(String),
+    #[error("Refusing to abort release {0}: {1}")]
+    ReleaseAbortRefused(String, String),
     #[error("No GitHub API private key found")]
     NoGitHubAPIPrivateKey,
     #[error("No GitHub API Authorisation found")]
@@ -107,9 +133,15 @@ pub enum Error {
     /// Signature verification failed
     #[error("Signature verification failed: {0} failure(s) detected")]
     SignatureVerificationFailed(usize),
+    /// PRLOG lint found problems
+    #[error("PRLOG lint failed: {0} problem(s) found")]
+    PrlogLintFailed(usize),
     /// SLSA attestation error
     #[error("Attestation error: {0}")]
     Attestation(String),
+    /// A Sigstore bundle or provenance failed verification
+    #[error("Attestation not verified: {0}")]
+    AttestationNotVerified(String),
     /// Assets cannot be attached to a release that is already published on a
     /// repository with immutable releases enabled.
     ///
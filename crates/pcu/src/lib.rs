@@ -3,6 +3,8 @@ mod client;
 mod error;
 mod ops;
 mod pr_title;
+mod section_map;
+mod tag_template;
 mod utilities;
 mod workspace;
 
@@ -14,6 +16,8 @@ pub use ops::{
     SignConfig, UpdateFromPr,
 };
 pub use pr_title::PrTitle;
+pub use section_map::{SectionMap, SectionRule};
+pub use tag_template::TagTemplates;
 pub use workspace::{Package, Workspace};
 
 #[cfg(test)]
@@ -237,27 +237,28 @@ pub trait GitOps {
     /// all paths are processed.
     fn stage_paths(&self, paths: &[&Path]) -> Result<(), Error>;
     #[allow(async_fn_in_trait)]
+    /// Commit the changed files and, given `tag`, tag the commit with it.
+    /// The tag is used as given: resolve it from the tag template first.
     async fn commit_changed_files(
         &self,
         sign_config: SignConfig,
         commit_message: &str,
-        prefix: &str,
-        tag_opt: Option<&str>,
+        tag: Option<&str>,
     ) -> Result<(), Error>;
     fn commit_staged(
         &self,
         sign_config: SignConfig,
         commit_message: &str,
-        prefix: &str,
         tag: Option<&str>,
     ) -> Result<(), Error>;
+    /// Push the branch and, given `tag`, the tag made by `commit_staged`.
     fn push_commit(
         &self,
-        prefix: &str,
-        version: Option<&str>,
+        tag: Option<&str>,
         no_push: bool,
         bot_user_name: &str,
     ) -> Result<(), Error>;
+    fn delete_remote_tag(&self, tag: &str) -> Result<(), Error>;
     #[allow(async_fn_in_trait)]
     async fn label_next_pr(
         &self,
@@ -534,8 +535,7 @@ impl GitOps for Client {
         &self,
         sign_config: SignConfig,
         commit_message: &str,
-        prefix: &str,
-        tag_opt: Option<&str>,
+        tag: Option<&str>,
     ) -> Result<(), Error> {
         let hdr_style = Style::new().bold().underline();
         log::debug!("{}", "Check WorkDir".style(hdr_style));
@@ -562,7 +562,7 @@ impl GitOps for Client {
 
         if !files_staged_for_commit.is_empty() {
             log::info!("Commit the staged changes with commit message: {commit_message}");
-            self.commit_staged(sign_config, commit_message, prefix, tag_opt)?;
+            self.commit_staged(sign_config, commit_message, tag)?;
             log::debug!("{}", "Check Committed".style(hdr_style));
             log::debug!("WorkDir files:\n\t{:?}", self.repo_files_not_staged()?);
             let files_staged_for_commit = self.repo_files_staged()?;
@@ -579,7 +579,6 @@ impl GitOps for Client {
         &self,
         sign_config: SignConfig,
         commit_message: &str,
-        prefix: &str,
         tag: Option<&str>,
     ) -> Result<(), Error> {
         log::trace!("Commit staged with sign {:?}", sign_config.sign);
@@ -703,11 +702,10 @@ impl GitOps for Client {
         };
 
         if let Some(version_tag) = tag {
-            let version_tag = format!("{prefix}{version_tag}");
             if requires_signed_tag(&sign_config.sign) {
-                self.create_signed_tag(&version_tag)?;
+                self.create_signed_tag(version_tag)?;
             } else {
-                self.create_tag(&version_tag, commit_id, &sig)?;
+                self.create_tag(version_tag, commit_id, &sig)?;
             }
         }
 
@@ -716,12 +714,11 @@ impl GitOps for Client {
 
     fn push_commit(
         &self,
-        prefix: &str,
-        version: Option<&str>,
+        tag: Option<&str>,
         no_push: bool,
         _bot_user_name: &str,
     ) -> Result<(), Error> {
-        log::trace!("version: {version:?} and no_push: {no_push}");
+        log::trace!("tag: {tag:?} and no_push: {no_push}");
 
         // Guarantee the push is a fast-forward before touching the remote: refresh
         // the target branch and refuse clearly if it has advanced beyond our local
@@ -785,9 +782,9 @@ impl GitOps for Client {
         #[allow(unused_assignments)]
         let mut tag_ref = String::from("");
 
-        if let Some(version_tag) = version {
-            log::trace!("Found version tag: {prefix}{version_tag}");
-            tag_ref = format!("refs/tags/{prefix}{version_tag}:refs/tags/{prefix}{version_tag}");
+        if let Some(version_tag) = tag {
+            log::trace!("Found version tag: {version_tag}");
+            tag_ref = format!("refs/tags/{version_tag}:refs/tags/{version_tag}");
             log::trace!("Tag ref: {tag_ref}");
             push_refs.push(&tag_ref);
         };
@@ -813,6 +810,34 @@ impl GitOps for Client {
         Ok(())
     }
 
+    /// Delete `tag` from the `origin` remote, authenticating as `push_commit`
+    /// does.
+    fn delete_remote_tag(&self, tag: &str) -> Result<(), Error> {
+        let mut remote = self.git_repo.find_remote("origin")?;
+        let remote_url = remote.url().unwrap_or("<unknown>").to_string();
+
+        let token = self.github_token.clone();
+        let https_url = ssh_to_https_url(&remote_url);
+        if !token.is_empty() && https_url != remote_url {
+            log::info!("Re-writing SSH remote to HTTPS for App token auth: {https_url}");
+            self.git_repo.remote_set_url("origin", &https_url)?;
+            remote = self.git_repo.find_remote("origin")?;
+        }
+
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(move |url, username, allowed| {
+            make_credential(&token, url, username, allowed)
+        });
+        let mut push_opts = PushOptions::new();
+        push_opts.remote_callbacks(callbacks);
+
+        let delete_ref = format!(":refs/tags/{tag}");
+        log::trace!("Push refs: {delete_ref}");
+        remote.push(&[delete_ref.as_str()], Some(&mut push_opts))?;
+
+        Ok(())
+    }
+
     /// Rebase the next pr of dependency updates if any
     #[instrument(skip(self))]
     async fn label_next_pr(
@@ -1488,7 +1513,7 @@ mod tests {
         // temp repo's (absent) user.name/user.email git config.
         let sign = SignConfig::new(Sign::None).with_identity("Bot", "bot@example.com");
         client
-            .commit_staged(sign, "chore: regenerate orb", "", None)
+            .commit_staged(sign, "chore: regenerate orb", None)
             .expect("ambient client must commit staged changes without GitHub auth");
 
         let repo = git2::Repository::open(dir.path()).unwrap();
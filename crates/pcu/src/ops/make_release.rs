@@ -1,19 +1,67 @@
-use keep_a_changelog::{Changelog, ChangelogParseOptions};
-use octocrate::repos::create_release::RequestMakeLatest;
+use keep_a_changelog::{Changelog, Version};
+use octocrate::repos::create_release::{Request, RequestMakeLatest};
 
 use crate::{
     utilities::{ReleaseNotesProvider, ReleaseUnreleased},
     Client, Error, GitOps,
 };
 
+/// True if `version` carries a prerelease part, as in `1.2.0-rc.1`.
+pub(crate) fn is_prerelease(version: &str) -> Result<bool, Error> {
+    let version = Version::parse(version).map_err(|e| Error::InvalidVersion(e.to_string()))?;
+    Ok(!version.pre.is_empty())
+}
+
+/// The request creating the GitHub release for `tag`.
+///
+/// `make_latest` is only set when publishing a full release directly: GitHub
+/// documents that "Drafts and prereleases cannot be set as latest", so for a
+/// draft it moves to the publish call that flips `draft` to false, and a
+/// prerelease is explicitly kept from becoming latest.
+fn release_request(
+    body: String,
+    name: String,
+    tag: String,
+    commit: String,
+    draft: bool,
+    prerelease: bool,
+) -> Request {
+    let mut request = Request::builder()
+        .body(body)
+        .name(name)
+        .tag_name(tag)
+        .target_commitish(commit)
+        .build();
+
+    if draft {
+        request.draft = Some(true);
+    } else {
+        request.make_latest = Some(RequestMakeLatest::True);
+    }
+
+    if prerelease {
+        request.prerelease = Some(true);
+        if !draft {
+            request.make_latest = Some(RequestMakeLatest::False);
+        }
+    }
+
+    request
+}
+
 pub trait MakeRelease {
     #[allow(async_fn_in_trait)]
     async fn make_release(&self, prefix: &str, version: &str, draft: bool) -> Result<(), Error>;
-    fn release_unreleased(&mut self, version: &str) -> Result<(), Error>;
+    fn release_unreleased(&mut self, version: &str, collect_prereleases: bool)
+        -> Result<(), Error>;
 }
 
 impl MakeRelease for Client {
-    fn release_unreleased(&mut self, version: &str) -> Result<(), Error> {
+    fn release_unreleased(
+        &mut self,
+        version: &str,
+        collect_prereleases: bool,
+    ) -> Result<(), Error> {
         let opts = self.prlog_parse_options.clone();
 
         let mut change_log = Changelog::parse_from_file(self.prlog_as_str(), Some(opts))
@@ -22,6 +70,9 @@ impl MakeRelease for Client {
         let total_releases = change_log.releases().len();
         log::debug!("total_releases: {total_releases:?}");
 
+        if collect_prereleases {
+            change_log.collect_prereleases(version)?;
+        }
         change_log.release_unreleased(version).unwrap();
 
         change_log
@@ -33,7 +84,7 @@ impl MakeRelease for Client {
     async fn make_release(&self, prefix: &str, version: &str, draft: bool) -> Result<(), Error> {
         log::debug!("Making release {version} (draft={draft})");
 
-        let opts = ChangelogParseOptions::default();
+        let opts = self.prlog_parse_options.clone();
         let prlog = match Changelog::parse_from_file(self.prlog_as_str(), Some(opts)) {
             Ok(pl) => pl,
             Err(e) => {
@@ -42,27 +93,22 @@ impl MakeRelease for Client {
             }
         };
 
-        let release_notes = prlog.release_notes(prefix, version)?;
+        let release_notes =
+            prlog.release_notes_with(prefix, version, self.release_notes_template.as_deref())?;
         log::trace!("Release notes: {release_notes:#?}");
 
         let tag = format!("{prefix}{version}");
         let commit = Self::get_commitish_for_tag(self, &tag).await?;
         log::trace!("Commit: {commit:#?}");
 
-        // `make_latest` is only set when publishing directly: GitHub documents
-        // that "Drafts and prereleases cannot be set as latest", so for a draft
-        // it moves to the publish call that flips `draft` to false.
-        let builder = octocrate::repos::create_release::Request::builder()
-            .body(release_notes.body.to_string())
-            .name(release_notes.name.to_string())
-            .tag_name(tag)
-            .target_commitish(commit);
-
-        let release_request = if draft {
-            builder.draft(true).build()
-        } else {
-            builder.make_latest(RequestMakeLatest::True).build()
-        };
+        let release_request = release_request(
+            release_notes.body.to_string(),
+            release_notes.name.to_string(),
+            tag,
+            commit,
+            draft,
+            is_prerelease(version)?,
+        );
 
         let release = match self
             .github_rest
@@ -84,3 +130,51 @@ impl MakeRelease for Client {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::stable("1.2.0", false)]
+    #[case::release_candidate("1.2.0-rc.1", true)]
+    #[case::alpha("0.7.0-alpha", true)]
+    #[case::build_metadata("1.2.0+build.5", false)]
+    fn test_is_prerelease(#[case] version: &str, #[case] expected: bool) {
+        assert_eq!(is_prerelease(version).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::published(false, false, None, Some("true"), None)]
+    #[case::draft(true, false, Some(true), None, None)]
+    #[case::prerelease(false, true, None, Some("false"), Some(true))]
+    #[case::draft_prerelease(true, true, Some(true), None, Some(true))]
+    fn test_release_request(
+        #[case] draft: bool,
+        #[case] prerelease: bool,
+        #[case] expected_draft: Option<bool>,
+        #[case] expected_latest: Option<&str>,
+        #[case] expected_prerelease: Option<bool>,
+    ) {
+        let request = release_request(
+            "notes".to_string(),
+            "v1.2.0".to_string(),
+            "v1.2.0".to_string(),
+            "abc123".to_string(),
+            draft,
+            prerelease,
+        );
+
+        assert_eq!(request.draft, expected_draft);
+        assert_eq!(
+            request
+                .make_latest
+                .map(|latest| latest.to_string())
+                .as_deref(),
+            expected_latest
+        );
+        assert_eq!(request.prerelease, expected_prerelease);
+    }
+}
@@ -10,5 +10,6 @@ mod update_from_pr;
 pub use ci_env::{export_ci_branch, write_ci_branch_export};
 pub use git_ops::{GitOps, Sign, SignConfig};
 pub use gpg_ops::import_gpg_key;
+pub(crate) use make_release::is_prerelease;
 pub use make_release::MakeRelease;
 pub use update_from_pr::UpdateFromPr;
@@ -17,6 +17,10 @@ impl UpdateFromPr for Client {
         pr_title.pr_id = Some(self.pr_number());
         pr_title.pr_url = Some(Url::from_str(self.pull_request())?);
         pr_title.set_pr_body(Some(self.body().to_string()));
+        pr_title.set_sections(self.prlog_sections.clone());
+        if self.credit_authors {
+            pr_title.set_author(self.author().map(str::to_string));
+        }
         pr_title.calculate_section_and_entry();
         log::trace!("pr_title: {pr_title:#?}");
 
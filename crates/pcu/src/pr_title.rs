@@ -5,7 +5,10 @@ use keep_a_changelog::{
 };
 use url::Url;
 
-use crate::Error;
+use crate::{
+    utilities::{ChangelogLinks, ReleaseEntries},
+    Error, SectionMap,
+};
 
 #[derive(Debug)]
 pub struct PrTitle {
@@ -19,10 +22,31 @@ pub struct PrTitle {
     pub commit_breaking: bool,
     pub section: Option<ChangeKind>,
     pub entry: String,
+    pub sections: SectionMap,
+    /// The title of the PR this one reverts, for a `Revert "..."` title.
+    pub reverts: Option<String>,
+    /// Login of the PR author, credited as `by @login` when set.
+    pub author: Option<String>,
 }
 
 impl PrTitle {
     pub fn parse(title: &str) -> Result<Self, Error> {
+        let revert_re = regex::Regex::new(r#"^Revert "(?P<reverted>.+)"$"#)?;
+        if let Some(captures) = revert_re.captures(title) {
+            let reverted = captures["reverted"].to_string();
+            let reverted_title = Self::parse(&reverted)?;
+            log::debug!("Revert of: `{reverted}`");
+
+            return Ok(Self {
+                commit_emoji: None,
+                commit_type: Some("revert".to_string()),
+                commit_scope: None,
+                commit_breaking: false,
+                reverts: Some(reverted),
+                ..reverted_title
+            });
+        }
+
         let re = regex::Regex::new(
             r"^(?P<emoji>.+\s)?(?P<type>[a-z]+)(?:\((?P<scope>.+)\))?(?P<breaking>!)?: (?P<description>.*)$$",
         )?;
@@ -51,6 +75,9 @@ impl PrTitle {
                 commit_breaking,
                 section: None,
                 entry: String::new(),
+                sections: SectionMap::default(),
+                reverts: None,
+                author: None,
             }
         } else {
             Self {
@@ -64,6 +91,9 @@ impl PrTitle {
                 commit_breaking: false,
                 section: None,
                 entry: String::new(),
+                sections: SectionMap::default(),
+                reverts: None,
+                author: None,
             }
         };
 
@@ -84,6 +114,10 @@ impl PrTitle {
         self.pr_body = body;
     }
 
+    pub fn set_author(&mut self, author: Option<String>) {
+        self.author = author;
+    }
+
     /// Returns true if a non-empty PR body is available, meaning the PR
     /// URL link should be included in the changelog entry.
     fn has_pr_body(&self) -> bool {
@@ -93,78 +127,193 @@ impl PrTitle {
         }
     }
 
-    pub fn calculate_section_and_entry(&mut self) {
-        log::trace!("Calculating section and entry for `{self:#?}`");
-        let mut section = ChangeKind::Changed;
-        let mut entry = self.title.clone();
-
-        log::debug!("Initial description `{entry}`");
-
-        if let Some(commit_type) = &self.commit_type {
-            match commit_type.as_str() {
-                "feat" => section = ChangeKind::Added,
-                "fix" => {
-                    section = ChangeKind::Fixed;
-                    if let Some(commit_scope) = &self.commit_scope {
-                        log::trace!("Found scope `{commit_scope}`");
-                        entry = format!("{}: {}", commit_scope, self.title);
-                    }
-                }
-                _ => {
-                    section = ChangeKind::Changed;
-                    entry = format!("{}-{}", self.commit_type.as_ref().unwrap(), entry);
-
-                    log::debug!("After checking for `feat` or `fix` type: `{entry}`");
-
-                    if let Some(commit_scope) = &self.commit_scope {
-                        log::trace!("Checking scope `{commit_scope}`");
-                        match commit_scope.as_str() {
-                            "security" => {
-                                section = ChangeKind::Security;
-                                entry = format!("Security: {}", self.title);
-                            }
-                            "deps" => {
-                                section = ChangeKind::Security;
-                                entry = format!("Dependencies: {}", self.title);
-                            }
-                            "remove" => {
-                                section = ChangeKind::Removed;
-                                entry = format!("Removed: {}", self.title);
-                            }
-                            "deprecate" => {
-                                section = ChangeKind::Deprecated;
-                                entry = format!("Deprecated: {}", self.title);
-                            }
-                            _ => {
-                                section = ChangeKind::Changed;
-                                let split_description = entry.splitn(2, '-').collect::<Vec<&str>>();
-                                log::trace!("Split description: {split_description:#?}");
-                                entry = format!(
-                                    "{}({})-{}",
-                                    split_description[0], commit_scope, split_description[1]
-                                );
-                            }
-                        }
-                    }
-                }
+    /// The number of the PR being reverted, from the `Reverts owner/repo#N`
+    /// line GitHub writes into the body of a revert PR.
+    pub fn reverted_pr(&self) -> Option<i64> {
+        self.reverts.as_ref()?;
+        let re = regex::Regex::new(r"(?m)^\s*Reverts\s+(?:[\w.-]+/[\w.-]+)?#(?P<id>\d+)").ok()?;
+        let captures = re.captures(self.pr_body.as_deref()?)?;
+        captures["id"].parse().ok()
+    }
+
+    /// True if `entry` is the PRLOG entry of the PR this one reverts: found by
+    /// PR number when the body names it, otherwise by the reverted title.
+    fn is_reverted_entry(&self, entry: &str) -> bool {
+        if let Some(id) = self.reverted_pr() {
+            return references_pr(entry, id);
+        }
+
+        let Some(reverted) = &self.reverts else {
+            return false;
+        };
+        let Ok(mut reverted_title) = PrTitle::parse(reverted) else {
+            return false;
+        };
+        reverted_title.set_sections(self.sections.clone());
+        reverted_title.calculate_section_and_entry();
+        let text = entry.rfind("(pr ").map_or(entry, |at| &entry[..at]);
+        text.trim_end() == reverted_title.entry
+    }
+
+    /// The curated release note from the PR body, if the author wrote one.
+    ///
+    /// Taken from a fenced ```` ```changelog ```` block or, failing that, the
+    /// text under a `## Changelog` heading. Multi-line notes are kept as
+    /// continuation lines of a single PRLOG entry.
+    pub fn release_note(&self) -> Option<String> {
+        let body = self.pr_body.as_deref()?;
+        let note = fenced_changelog_block(body).or_else(|| changelog_section(body))?;
+
+        let mut lines = note
+            .iter()
+            .map(|line| line.trim_end())
+            .filter(|line| !line.trim().is_empty());
+        let first = lines.next()?;
+        let first = first
+            .trim_start()
+            .strip_prefix("- ")
+            .or_else(|| first.trim_start().strip_prefix("* "))
+            .unwrap_or(first.trim_start());
+
+        let note = std::iter::once(first)
+            .chain(lines)
+            .collect::<Vec<&str>>()
+            .join("\n");
+        Some(note)
+    }
+
+    /// Issues the PR closes, from GitHub closing keywords in its body such as
+    /// `Closes #123`, `Fixes owner/repo#45` or `Resolves <issue url>`. An
+    /// issue in the PR's own repository is given without its repository.
+    pub(crate) fn closed_issues(&self) -> Vec<ClosedIssue> {
+        let Some(body) = self.pr_body.as_deref() else {
+            return Vec::new();
+        };
+        let Ok(re) = regex::Regex::new(
+            r"(?i)\b(?:close[sd]?|fix(?:e[sd])?|resolve[sd]?):?\s+(?:https://github\.com/(?P<url_repo>[\w.-]+/[\w.-]+)/issues/(?P<url_number>\d+)|(?P<repo>[\w.-]+/[\w.-]+)?#(?P<number>\d+))",
+        ) else {
+            return Vec::new();
+        };
+        let own_repo = self
+            .repo_url()
+            .map(|url| url.trim_start_matches("https://github.com/").to_lowercase());
+
+        let mut issues: Vec<ClosedIssue> = Vec::new();
+        for captures in re.captures_iter(body) {
+            let repo = captures.name("url_repo").or(captures.name("repo"));
+            let number = captures.name("url_number").or(captures.name("number"));
+            let Some(number) = number.and_then(|n| n.as_str().parse().ok()) else {
+                continue;
+            };
+            let repo = repo
+                .map(|r| r.as_str().to_string())
+                .filter(|r| Some(r.to_lowercase()) != own_repo);
+
+            let issue = ClosedIssue { repo, number };
+            if !issues.contains(&issue) {
+                issues.push(issue);
             }
         }
+
+        issues
+    }
+
+    /// `https://github.com/owner/repo` for the repository of the PR
+    fn repo_url(&self) -> Option<String> {
+        let pr_url = self.pr_url.as_ref()?.to_string();
+        let components = pr_url.split('/').collect::<Vec<&str>>();
+        Some(format!(
+            "https://github.com/{}/{}",
+            components.get(3)?,
+            components.get(4)?
+        ))
+    }
+
+    /// Add the `[#N]:` link definitions for the issues the PR closes, unless
+    /// the prlog already has them.
+    pub(crate) fn add_issue_links(&self, change_log: &mut Changelog) {
+        if self.pr_url.is_none() || !self.has_pr_body() {
+            return;
+        }
+        let Some(repo_url) = self.repo_url() else {
+            return;
+        };
+
+        for issue in self.closed_issues() {
+            let anchor = issue.anchor();
+            if !change_log.has_link(&anchor) {
+                change_log.add_link(format!("[{anchor}]:"), issue.url(&repo_url));
+            }
+        }
+    }
+
+    /// Replace the built-in type/scope to section rules, e.g. with those
+    /// configured under `[prlog.sections]` in pcu.toml.
+    pub fn set_sections(&mut self, sections: SectionMap) {
+        self.sections = sections;
+    }
+
+    pub fn calculate_section_and_entry(&mut self) {
+        log::trace!("Calculating section and entry for `{self:#?}`");
+        log::debug!("Initial description `{}`", self.title);
+
+        let (section, mut entry) = match self.reverts {
+            Some(_) => SectionMap::resolve_reverted(&self.title),
+            None => self.sections.resolve(
+                self.commit_type.as_deref(),
+                self.commit_scope.as_deref(),
+                &self.title,
+            ),
+        };
+
         log::debug!("After checking scope `{entry}`");
 
+        if let Some(note) = self.release_note() {
+            log::debug!("Using release note from PR body `{note}`");
+            entry = note;
+        }
+
         if self.commit_breaking {
             entry = format!("BREAKING: {entry}");
         }
 
         if let Some(id) = self.pr_id {
-            if self.pr_url.is_some() && self.has_pr_body() {
-                entry = format!("{entry}(pr [#{id}])");
+            let linked = self.pr_url.is_some() && self.has_pr_body();
+            let reverts = match self.reverted_pr() {
+                Some(reverted) if linked => format!(", reverts [#{reverted}]"),
+                Some(reverted) => format!(", reverts #{reverted}"),
+                None => String::new(),
+            };
+            let issues = self.closed_issues();
+            let closes = if issues.is_empty() {
+                String::new()
             } else {
-                entry = format!("{entry}(pr #{id})");
+                let issues = issues
+                    .iter()
+                    .map(|issue| {
+                        if linked {
+                            format!("[{}]", issue.anchor())
+                        } else {
+                            issue.anchor()
+                        }
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!(", closes {issues}")
+            };
+            if linked {
+                entry = format!("{entry}(pr [#{id}]{reverts}{closes})");
+            } else {
+                entry = format!("{entry}(pr #{id}{reverts}{closes})");
             }
 
             log::debug!("After checking pr id `{entry}`");
         };
 
+        if let Some(author) = &self.author {
+            entry = format!("{entry} by @{author}");
+        }
+
         // Prepend the emoji to the entry
         if let Some(emoji) = &self.commit_emoji {
             entry = format!("{emoji}{entry}");
@@ -175,14 +324,14 @@ impl PrTitle {
         self.entry = entry;
     }
 
-    fn section(&self) -> ChangeKind {
+    pub(crate) fn section(&self) -> ChangeKind {
         match &self.section {
             Some(kind) => kind.clone(),
             None => ChangeKind::Changed,
         }
     }
 
-    fn entry(&self) -> String {
+    pub(crate) fn entry(&self) -> String {
         if self.entry.as_str() == "" {
             self.title.clone()
         } else {
@@ -199,15 +348,7 @@ impl PrTitle {
             return Err(Error::InvalidPath(log_file.to_owned()));
         };
 
-        let repo_url = match &self.pr_url {
-            Some(pr_url) => {
-                let url_string = pr_url.to_string();
-                let components = url_string.split('/').collect::<Vec<&str>>();
-                let url = format!("https://github.com/{}/{}", components[3], components[4]);
-                Some(url)
-            }
-            None => None,
-        };
+        let repo_url = self.repo_url();
 
         self.calculate_section_and_entry();
 
@@ -223,7 +364,9 @@ impl PrTitle {
                     .collect::<Vec<&str>>()
                     .join("\n")
             );
-            if file_contents.contains(&self.entry) {
+            // With a PR number the unreleased entry is matched on its `[#N]`
+            // reference below, so that a retitled PR updates its entry.
+            if self.pr_id.is_none() && file_contents.contains(&list_item_text(&self.entry)) {
                 log::trace!("The prlog exists and already contains the entry!");
                 return Ok(None);
             } else {
@@ -235,7 +378,7 @@ impl PrTitle {
         } else {
             log::trace!("The prlog does not exist! Create a default prlog.");
             let mut prlog = ChangelogBuilder::default()
-                .url(repo_url)
+                .url(repo_url.clone())
                 .build()
                 .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
             log::debug!("Changelog: {prlog:#?}");
@@ -251,6 +394,27 @@ impl PrTitle {
             prlog
         };
 
+        if self.reverts.is_some() {
+            return self.revert_in_prlog(change_log, log_file, repo_url.as_deref());
+        }
+
+        if let Some(id) = self.pr_id {
+            let released = change_log
+                .releases()
+                .iter()
+                .filter(|release| release.version().is_some())
+                .any(|release| {
+                    release
+                        .entries()
+                        .iter()
+                        .any(|(_, entry)| references_pr(entry, id))
+                });
+            if released {
+                log::trace!("The pull request is already recorded in a released version!");
+                return Ok(None);
+            }
+        }
+
         // Get the unreleased section from the Changelog.
         // If there is no unreleased section create it and add it to the prlog
         let unreleased = if let Some(unreleased) = change_log.get_unreleased_mut() {
@@ -264,33 +428,151 @@ impl PrTitle {
             unreleased
         };
 
-        match self.section() {
-            ChangeKind::Added => {
-                unreleased.added(self.entry());
-            }
-            ChangeKind::Fixed => {
-                unreleased.fixed(self.entry());
-            }
-            ChangeKind::Security => {
-                unreleased.security(self.entry());
-            }
-            ChangeKind::Removed => {
-                unreleased.removed(self.entry());
-            }
-            ChangeKind::Deprecated => {
-                unreleased.deprecated(self.entry());
+        // An entry for this PR from an earlier title is replaced where it
+        // stands, moving section if the type or scope changed.
+        let mut entries = unreleased.entries();
+        let existing = self.pr_id.and_then(|id| {
+            entries
+                .iter()
+                .position(|(_, entry)| references_pr(entry, id))
+        });
+
+        if let Some(position) = existing {
+            if entries[position] == (self.section(), self.entry()) {
+                log::trace!("The unreleased section already contains the entry!");
+                return Ok(None);
             }
-            ChangeKind::Changed => {
-                unreleased.changed(self.entry());
+            log::info!(
+                "Replacing `{}` with `{}` for the retitled pull request",
+                entries[position].1,
+                self.entry()
+            );
+            entries[position] = (self.section(), self.entry());
+            unreleased.set_entries(entries);
+        } else {
+            match self.section() {
+                ChangeKind::Added => {
+                    unreleased.added(self.entry());
+                }
+                ChangeKind::Fixed => {
+                    unreleased.fixed(self.entry());
+                }
+                ChangeKind::Security => {
+                    unreleased.security(self.entry());
+                }
+                ChangeKind::Removed => {
+                    unreleased.removed(self.entry());
+                }
+                ChangeKind::Deprecated => {
+                    unreleased.deprecated(self.entry());
+                }
+                ChangeKind::Changed => {
+                    unreleased.changed(self.entry());
+                }
             }
         }
 
         // add link to the url if it exists and the PR has a non-empty body
         if self.pr_url.is_some() && self.has_pr_body() {
-            change_log.add_link(
-                &format!("[#{}]:", self.pr_id.unwrap()),
-                &self.pr_url.clone().unwrap().to_string(),
-            );
+            let anchor = format!("#{}", self.pr_id.unwrap());
+            if !change_log.has_link(&anchor) {
+                change_log.add_link(
+                    &format!("[{anchor}]:"),
+                    &self.pr_url.clone().unwrap().to_string(),
+                );
+            }
+        }
+        self.add_issue_links(&mut change_log);
+
+        change_log
+            .save_to_file(log_file)
+            .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+
+        Ok(Some((self.section(), self.entry())))
+    }
+
+    /// Apply a revert PR to the prlog.
+    ///
+    /// While the reverted entry is still unreleased it is simply removed, along
+    /// with its link. Once released, a `Reverted` entry linking both PRs is
+    /// added instead. If the reverted entry cannot be found there is nothing
+    /// to do.
+    fn revert_in_prlog(
+        &mut self,
+        mut change_log: Changelog,
+        log_file: &str,
+        repo_url: Option<&str>,
+    ) -> Result<Option<(ChangeKind, String)>, Error> {
+        if let Some(unreleased) = change_log.get_unreleased_mut() {
+            let mut entries = unreleased.entries();
+            if let Some(position) = entries
+                .iter()
+                .position(|(_, entry)| self.is_reverted_entry(entry))
+            {
+                let (section, entry) = entries.remove(position);
+                unreleased.set_entries(entries);
+                log::info!("Removing reverted entry `{entry}` from the unreleased section");
+
+                for anchor in link_references(&entry) {
+                    let still_used = change_log.releases().iter().any(|release| {
+                        release
+                            .entries()
+                            .iter()
+                            .any(|(_, other)| link_references(other).contains(&anchor))
+                    });
+                    if !still_used {
+                        change_log.remove_link(&anchor)?;
+                    }
+                }
+                change_log
+                    .save_to_file(log_file)
+                    .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+
+                return Ok(Some((section, entry)));
+            }
+        }
+
+        let released = change_log
+            .releases()
+            .iter()
+            .filter(|release| release.version().is_some())
+            .any(|release| {
+                release
+                    .entries()
+                    .iter()
+                    .any(|(_, entry)| self.is_reverted_entry(entry))
+            });
+        if !released {
+            log::info!("Reverted entry not found in the prlog, nothing to record");
+            return Ok(None);
+        }
+
+        if change_log.get_unreleased().is_none() {
+            let release = Release::builder()
+                .build()
+                .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+            change_log.add_release(release);
+        }
+        if let Some(unreleased) = change_log.get_unreleased_mut() {
+            let mut entries = unreleased.entries();
+            entries.push((self.section(), self.entry()));
+            unreleased.set_entries(entries);
+        }
+
+        if let (Some(pr_url), Some(id)) = (&self.pr_url, self.pr_id) {
+            if self.has_pr_body() {
+                change_log.add_link(&format!("[#{id}]:"), &pr_url.to_string());
+            }
+        }
+        self.add_issue_links(&mut change_log);
+        if let (Some(repo_url), Some(reverted)) = (repo_url, self.reverted_pr()) {
+            let anchor = format!("#{reverted}");
+            if !change_log.has_link(&anchor) {
+                change_log.add_link(
+                    format!("[{anchor}]:"),
+                    format!("{repo_url}/pull/{reverted}"),
+                );
+            }
         }
 
         change_log
@@ -301,6 +583,92 @@ impl PrTitle {
     }
 }
 
+/// An issue closed by a PR. `repo` is `owner/repo` for an issue in another
+/// repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ClosedIssue {
+    pub(crate) repo: Option<String>,
+    pub(crate) number: i64,
+}
+
+impl ClosedIssue {
+    /// The link anchor: `#N`, or `owner/repo#N` for another repository.
+    pub(crate) fn anchor(&self) -> String {
+        match &self.repo {
+            Some(repo) => format!("{repo}#{}", self.number),
+            None => format!("#{}", self.number),
+        }
+    }
+
+    fn url(&self, repo_url: &str) -> String {
+        match &self.repo {
+            Some(repo) => format!("https://github.com/{repo}/issues/{}", self.number),
+            None => format!("{repo_url}/issues/{}", self.number),
+        }
+    }
+}
+
+/// The anchors of the `[#N]` and `[owner/repo#N]` link references in `entry`.
+fn link_references(entry: &str) -> Vec<String> {
+    let Ok(re) = regex::Regex::new(r"\[(?P<anchor>(?:[\w.-]+/[\w.-]+)?#\d+)\]") else {
+        return Vec::new();
+    };
+    re.captures_iter(entry)
+        .map(|captures| captures["anchor"].to_string())
+        .collect()
+}
+
+/// True if `entry` carries the `(pr #N)` or `(pr [#N])` reference for PR `id`.
+pub(crate) fn references_pr(entry: &str, id: i64) -> bool {
+    entry.contains(&format!("(pr [#{id}]")) || entry.contains(&format!("(pr #{id}"))
+}
+
+/// Lines of the first fenced ```` ```changelog ```` block in `body`.
+fn fenced_changelog_block(body: &str) -> Option<Vec<&str>> {
+    let mut lines = body.lines();
+    lines.find(|line| {
+        line.trim()
+            .strip_prefix("```")
+            .is_some_and(|info| info.trim().eq_ignore_ascii_case("changelog"))
+    })?;
+
+    Some(
+        lines
+            .take_while(|line| !line.trim().starts_with("```"))
+            .collect(),
+    )
+}
+
+/// Lines under a `Changelog` markdown heading in `body`, up to the next
+/// heading. HTML comments left by PR templates are skipped.
+fn changelog_section(body: &str) -> Option<Vec<&str>> {
+    let mut lines = body.lines();
+    lines.find(|line| {
+        let line = line.trim();
+        line.starts_with('#')
+            && line
+                .trim_start_matches('#')
+                .trim()
+                .eq_ignore_ascii_case("changelog")
+    })?;
+
+    Some(
+        lines
+            .take_while(|line| !line.trim_start().starts_with('#'))
+            .filter(|line| {
+                let line = line.trim();
+                !(line.starts_with("<!--") && line.ends_with("-->"))
+            })
+            .collect(),
+    )
+}
+
+/// `entry` as it is written in the PRLOG list: continuation lines are indented
+/// under the bullet.
+fn list_item_text(entry: &str) -> String {
+    entry.lines().collect::<Vec<&str>>().join("\n  ")
+}
+
 //test module
 #[cfg(test)]
 mod tests {
@@ -315,6 +683,7 @@ mod tests {
     use uuid::Uuid;
 
     use super::*;
+    use crate::SectionRule;
 
     fn get_test_logger() {
         let mut builder = env_logger::Builder::new();
@@ -610,6 +979,9 @@ mod tests {
             commit_breaking: false,
             section: Some(ChangeKind::Added),
             entry: "add new feature".to_string(),
+            sections: SectionMap::default(),
+            reverts: None,
+            author: None,
         };
 
         let file_name = &file_name.into_os_string();
@@ -656,6 +1028,9 @@ mod tests {
             commit_breaking: false,
             section: Some(ChangeKind::Added),
             entry: "add new feature".to_string(),
+            sections: SectionMap::default(),
+            reverts: None,
+            author: None,
         };
 
         let file_name = &file_name.into_os_string();
@@ -734,4 +1109,433 @@ mod tests {
         assert_eq!(pr_title.entry, "add new feature(pr [#5])");
         Ok(())
     }
+
+    #[rstest]
+    #[case::fenced_block(
+        "Some context.\n\n```changelog\nCurated note for the log\n```\n\nMore text.",
+        "Curated note for the log(pr [#5])"
+    )]
+    #[case::heading(
+        "## Summary\n\nWhy.\n\n## Changelog\n\n<!-- one line for the PRLOG -->\nCurated note for the log\n\n## Testing\n\nRan it.",
+        "Curated note for the log(pr [#5])"
+    )]
+    #[case::bullets(
+        "```changelog\n- first change\n- second change\n```",
+        "first change\n- second change(pr [#5])"
+    )]
+    #[case::fenced_block_wins_over_heading(
+        "## Changelog\n\nfrom heading\n\n```changelog\nfrom block\n```",
+        "from block(pr [#5])"
+    )]
+    #[case::empty_block_falls_back_to_title("```changelog\n```", "add new feature(pr [#5])")]
+    #[case::no_note_falls_back_to_title("Some description", "add new feature(pr [#5])")]
+    fn test_release_note_from_pr_body(
+        #[case] body: &str,
+        #[case] expected_entry: &str,
+    ) -> Result<(), Error> {
+        let mut pr_title = PrTitle::parse("feat: add new feature").unwrap();
+        pr_title.set_pr_id(5);
+        pr_title.set_pr_url(Url::parse("https://github.com/jerus-org/pcu/pull/5")?);
+        pr_title.set_pr_body(Some(body.to_string()));
+        pr_title.calculate_section_and_entry();
+        assert_eq!(pr_title.section(), ChangeKind::Added);
+        assert_eq!(pr_title.entry, expected_entry);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_line_release_note_is_written_once() -> Result<(), Error> {
+        get_test_logger();
+
+        let initial_content = fs::read_to_string("tests/data/initial_prlog.md")?;
+
+        let temp_dir = tempfile::tempdir()?;
+        let file_name = temp_dir.path().join("PRLOG.md");
+        let mut file = File::create(&file_name)?;
+        file.write_all(initial_content.as_bytes())?;
+        let file_name = &file_name.into_os_string();
+
+        let mut pr_title = PrTitle::parse("feat: add new feature")?;
+        pr_title.set_pr_id(5);
+        pr_title.set_pr_url(Url::parse("https://github.com/jerus-org/pcu/pull/5")?);
+        pr_title.set_pr_body(Some(
+            "```changelog\n- first change\n- second change\n```".to_string(),
+        ));
+
+        let update = pr_title.update_prlog(file_name, ChangelogParseOptions::default())?;
+        assert!(update.is_some());
+
+        let actual_content = fs::read_to_string(file_name)?;
+        assert!(actual_content.contains("- first change\n  - second change(pr [#5])"));
+
+        let update = pr_title.update_prlog(file_name, ChangelogParseOptions::default())?;
+        assert!(update.is_none(), "entry must not be added twice");
+
+        Ok(())
+    }
+
+    const RETITLE_PRLOG: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/)
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+## [Unreleased]
+
+### Added
+
+- first feature(pr [#4])
+- add new feature(pr [#5])
+- last feature(pr [#6])
+
+[#4]: https://github.com/jerus-org/pcu/pull/4
+[#5]: https://github.com/jerus-org/pcu/pull/5
+[#6]: https://github.com/jerus-org/pcu/pull/6
+";
+
+    #[rstest]
+    #[case::retitle(
+        "feat: add a better feature",
+        "### Added\n\n- first feature(pr [#4])\n- add a better feature(pr [#5])\n- last feature(pr [#6])\n"
+    )]
+    #[case::retype(
+        "fix: add new feature",
+        "### Added\n\n- first feature(pr [#4])\n- last feature(pr [#6])\n\n### Fixed\n\n- add new feature(pr [#5])\n"
+    )]
+    #[case::breaking_on(
+        "feat!: add new feature",
+        "### Added\n\n- first feature(pr [#4])\n- BREAKING: add new feature(pr [#5])\n- last feature(pr [#6])\n"
+    )]
+    fn test_retitled_pr_replaces_its_entry(
+        #[case] title: &str,
+        #[case] expected_changes: &str,
+    ) -> Result<(), Error> {
+        get_test_logger();
+
+        let temp_dir = tempfile::tempdir()?;
+        let file_name = temp_dir.path().join("PRLOG.md");
+        fs::write(&file_name, RETITLE_PRLOG)?;
+        let file_name = &file_name.into_os_string();
+
+        let mut pr_title = PrTitle::parse(title)?;
+        pr_title.set_pr_id(5);
+        pr_title.set_pr_url(Url::parse("https://github.com/jerus-org/pcu/pull/5")?);
+
+        let update = pr_title.update_prlog(file_name, ChangelogParseOptions::default())?;
+        assert!(update.is_some());
+
+        let content = fs::read_to_string(file_name)?;
+        assert!(
+            content.contains(expected_changes),
+            "unexpected prlog:\n{content}"
+        );
+        assert_eq!(content.matches("(pr [#5])").count(), 1);
+        assert_eq!(content.matches("[#5]: ").count(), 1);
+
+        // Running again with the same title changes nothing
+        let update = pr_title.update_prlog(file_name, ChangelogParseOptions::default())?;
+        assert!(update.is_none());
+
+        // Restoring the original title restores the original entry
+        let mut pr_title = PrTitle::parse("feat: add new feature")?;
+        pr_title.set_pr_id(5);
+        pr_title.set_pr_url(Url::parse("https://github.com/jerus-org/pcu/pull/5")?);
+        let update = pr_title.update_prlog(file_name, ChangelogParseOptions::default())?;
+        assert!(update.is_some());
+        let content = fs::read_to_string(file_name)?;
+        assert!(!content.contains("### Fixed"));
+        assert!(content.contains("- add new feature(pr [#5])"));
+        assert_eq!(content.matches("(pr [#5])").count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescoped_pr_moves_its_entry() -> Result<(), Error> {
+        get_test_logger();
+
+        let temp_dir = tempfile::tempdir()?;
+        let file_name = temp_dir.path().join("PRLOG.md");
+        fs::write(&file_name, RETITLE_PRLOG)?;
+        let file_name = &file_name.into_os_string();
+
+        let mut pr_title = PrTitle::parse("chore(deps): bump serde")?;
+        pr_title.set_pr_id(5);
+        pr_title.set_pr_url(Url::parse("https://github.com/jerus-org/pcu/pull/5")?);
+        let update = pr_title.update_prlog(file_name, ChangelogParseOptions::default())?;
+        assert_eq!(
+            update,
+            Some((
+                ChangeKind::Security,
+                "Dependencies: bump serde(pr [#5])".to_string()
+            ))
+        );
+        let content = fs::read_to_string(file_name)?;
+        assert!(
+            content.contains("### Security\n\n- Dependencies: bump serde(pr [#5])\n"),
+            "unexpected prlog:\n{content}"
+        );
+
+        // Changing only the scope moves the entry from Security to Changed
+        let mut pr_title = PrTitle::parse("chore(ci): bump serde")?;
+        pr_title.set_pr_id(5);
+        pr_title.set_pr_url(Url::parse("https://github.com/jerus-org/pcu/pull/5")?);
+        let update = pr_title.update_prlog(file_name, ChangelogParseOptions::default())?;
+        assert_eq!(
+            update,
+            Some((
+                ChangeKind::Changed,
+                "chore(ci)-bump serde(pr [#5])".to_string()
+            ))
+        );
+        let content = fs::read_to_string(file_name)?;
+        assert!(
+            content.contains("### Changed\n\n- chore(ci)-bump serde(pr [#5])\n"),
+            "unexpected prlog:\n{content}"
+        );
+        assert!(!content.contains("### Security"));
+        assert!(!content.contains("Dependencies: bump serde"));
+        assert_eq!(content.matches("(pr [#5])").count(), 1);
+        assert_eq!(content.matches("[#5]: ").count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pr_title_parse_revert() {
+        let pr_title = PrTitle::parse(r#"Revert "✨ feat(core)!: add new feature""#).unwrap();
+        assert_eq!(pr_title.title, "add new feature");
+        assert_eq!(pr_title.commit_type, Some("revert".to_string()));
+        assert_eq!(pr_title.commit_scope, None);
+        assert!(!pr_title.commit_breaking);
+        assert_eq!(
+            pr_title.reverts,
+            Some("✨ feat(core)!: add new feature".to_string())
+        );
+    }
+
+    #[test]
+    fn test_revert_type_keeps_the_default_rule() {
+        let mut pr_title = PrTitle::parse("revert: drop the parse cache").unwrap();
+        pr_title.calculate_section_and_entry();
+
+        assert_eq!(pr_title.reverts, None);
+        assert_eq!(pr_title.section, Some(ChangeKind::Changed));
+        assert_eq!(pr_title.entry, "revert-drop the parse cache");
+    }
+
+    const REVERT_PRLOG: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/)
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+## [Unreleased]
+
+### Added
+
+- add new feature(pr [#5])
+- keep this one(pr [#6])
+
+## [0.1.0] - 2024-01-01
+
+### Added
+
+- add old feature(pr [#2])
+
+[#2]: https://github.com/jerus-org/pcu/pull/2
+[#5]: https://github.com/jerus-org/pcu/pull/5
+[#6]: https://github.com/jerus-org/pcu/pull/6
+";
+
+    fn revert_pr(title: &str, body: &str) -> Result<PrTitle, Error> {
+        let mut pr_title = PrTitle::parse(title)?;
+        pr_title.set_pr_id(7);
+        pr_title.set_pr_url(Url::parse("https://github.com/jerus-org/pcu/pull/7")?);
+        pr_title.set_pr_body(Some(body.to_string()));
+        Ok(pr_title)
+    }
+
+    fn apply_to_revert_prlog(
+        pr_title: &mut PrTitle,
+    ) -> Result<(Option<(ChangeKind, String)>, String), Error> {
+        let temp_dir = tempfile::tempdir()?;
+        let file_name = temp_dir.path().join("PRLOG.md");
+        fs::write(&file_name, REVERT_PRLOG)?;
+
+        let opts = ChangelogParseOptions {
+            url: Some("https://github.com/jerus-org/pcu".to_string()),
+            head: Some("HEAD".to_string()),
+            tag_prefix: Some("v".to_string()),
+        };
+        let update = pr_title.update_prlog(file_name.as_os_str(), opts)?;
+        let content = fs::read_to_string(&file_name)?;
+
+        Ok((update, content))
+    }
+
+    #[rstest]
+    #[case::by_pr_number(r#"Revert "feat: add new feature""#, "Reverts jerus-org/pcu#5")]
+    #[case::by_title(r#"Revert "feat: add new feature""#, "")]
+    fn test_revert_removes_unreleased_entry(
+        #[case] title: &str,
+        #[case] body: &str,
+    ) -> Result<(), Error> {
+        get_test_logger();
+
+        let mut pr_title = revert_pr(title, body)?;
+        let (update, content) = apply_to_revert_prlog(&mut pr_title)?;
+
+        assert_eq!(
+            update,
+            Some((ChangeKind::Added, "add new feature(pr [#5])".to_string()))
+        );
+        assert!(!content.contains("add new feature"));
+        assert!(!content.contains("[#7]"));
+        assert!(content.contains("- keep this one(pr [#6])"));
+        assert!(!content.contains("[#5]:"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_revert_of_released_entry_records_reverted_entry() -> Result<(), Error> {
+        get_test_logger();
+
+        let mut pr_title = revert_pr(
+            r#"Revert "feat: add old feature""#,
+            "Reverts jerus-org/pcu#2",
+        )?;
+        let (update, content) = apply_to_revert_prlog(&mut pr_title)?;
+
+        let entry = "Reverted: add old feature(pr [#7], reverts [#2])";
+        assert_eq!(update, Some((ChangeKind::Removed, entry.to_string())));
+        assert!(content.contains(&format!("### Removed\n\n- {entry}")));
+        assert!(content.contains("- add old feature(pr [#2])"));
+        assert!(content.contains("[#7]: https://github.com/jerus-org/pcu/pull/7"));
+        assert_eq!(content.matches("[#2]: ").count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_revert_by_title_leaves_entry_it_is_a_prefix_of() -> Result<(), Error> {
+        get_test_logger();
+
+        let mut pr_title = revert_pr(r#"Revert "feat: add new""#, "")?;
+        let (update, content) = apply_to_revert_prlog(&mut pr_title)?;
+
+        assert_eq!(update, None);
+        assert_eq!(content, REVERT_PRLOG);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_revert_of_unknown_entry_changes_nothing() -> Result<(), Error> {
+        get_test_logger();
+
+        let mut pr_title = revert_pr(
+            r#"Revert "feat: add other feature""#,
+            "Reverts jerus-org/pcu#4",
+        )?;
+        let (update, content) = apply_to_revert_prlog(&mut pr_title)?;
+
+        assert_eq!(update, None);
+        assert_eq!(content, REVERT_PRLOG);
+
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::none("Just a description", &[])]
+    #[case::closes("Closes #12", &["#12"])]
+    #[case::keywords(
+        "fixes #1, Resolved #2 and CLOSE: #3\nresolves #1",
+        &["#1", "#2", "#3"]
+    )]
+    #[case::other_repo("Fixes other-org/tool#45", &["other-org/tool#45"])]
+    #[case::own_repo("Fixes jerus-org/PCU#45", &["#45"])]
+    #[case::issue_url(
+        "Closes https://github.com/other-org/tool/issues/8",
+        &["other-org/tool#8"]
+    )]
+    #[case::not_a_keyword("Prefixes #5 and see #6", &[])]
+    fn test_closed_issues(#[case] body: &str, #[case] expected: &[&str]) {
+        let mut pr_title = PrTitle::parse("fix: crash on empty body").unwrap();
+        pr_title.set_pr_url(Url::parse("https://github.com/jerus-org/pcu/pull/7").unwrap());
+        pr_title.set_pr_body(Some(body.to_string()));
+
+        let anchors = pr_title
+            .closed_issues()
+            .iter()
+            .map(ClosedIssue::anchor)
+            .collect::<Vec<String>>();
+        assert_eq!(anchors, expected);
+    }
+
+    #[test]
+    fn test_closed_issues_are_linked() -> Result<(), Error> {
+        get_test_logger();
+
+        let mut pr_title = revert_pr(
+            "fix: crash on empty body",
+            "Fixes #12 and closes other-org/tool#3",
+        )?;
+        let (update, content) = apply_to_revert_prlog(&mut pr_title)?;
+
+        let entry = "crash on empty body(pr [#7], closes [#12], [other-org/tool#3])";
+        assert_eq!(update, Some((ChangeKind::Fixed, entry.to_string())));
+        assert!(content.contains(&format!("### Fixed\n\n- {entry}")));
+        assert!(content.contains("[#12]: https://github.com/jerus-org/pcu/issues/12"));
+        assert!(content.contains("[other-org/tool#3]: https://github.com/other-org/tool/issues/3"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_author_is_credited() {
+        let mut pr_title = PrTitle::parse("✨ feat: add new feature").unwrap();
+        pr_title.set_pr_id(5);
+        pr_title.set_pr_url(Url::parse("https://github.com/jerus-org/pcu/pull/5").unwrap());
+        pr_title.set_pr_body(Some("Closes #3".to_string()));
+        pr_title.set_author(Some("octocat".to_string()));
+        pr_title.calculate_section_and_entry();
+
+        assert_eq!(
+            pr_title.entry,
+            "✨ add new feature(pr [#5], closes [#3]) by @octocat"
+        );
+    }
+
+    #[test]
+    fn test_configured_sections_override_built_in_rules() {
+        let mut sections = SectionMap::default();
+        sections.scopes.insert(
+            "deps".to_string(),
+            SectionRule::new(ChangeKind::Changed, "Dependencies: {title}"),
+        );
+        sections.types.insert(
+            "perf".to_string(),
+            SectionRule::new(ChangeKind::Changed, "Performance: {title}"),
+        );
+
+        let mut pr_title = PrTitle::parse("chore(deps)!: update tokio").unwrap();
+        pr_title.set_sections(sections.clone());
+        pr_title.set_pr_id(7);
+        pr_title.calculate_section_and_entry();
+        assert_eq!(pr_title.section(), ChangeKind::Changed);
+        assert_eq!(
+            pr_title.entry,
+            "BREAKING: Dependencies: update tokio(pr #7)"
+        );
+
+        let mut pr_title = PrTitle::parse("⚡ perf: cache parsed titles").unwrap();
+        pr_title.set_sections(sections);
+        pr_title.calculate_section_and_entry();
+        assert_eq!(pr_title.section(), ChangeKind::Changed);
+        assert_eq!(pr_title.entry, "⚡ Performance: cache parsed titles");
+    }
 }
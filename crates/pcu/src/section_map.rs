@@ -0,0 +1,311 @@
+//! Mapping of conventional-commit type and scope to PRLOG section and entry
+//! text.
+//!
+//! The built-in rules ([`SectionMap::default`]) are the ones pcu has always
+//! applied. A `[prlog.sections]` table in pcu.toml adds to or overrides them:
+//!
+//! ```toml
+//! [prlog.sections.types.perf]
+//! section = "Changed"
+//! template = "Performance: {title}"
+//!
+//! [prlog.sections.scopes.deps]
+//! section = "Changed"
+//! template = "Dependencies: {title}"
+//! ```
+//!
+//! A rule for the commit type wins over a rule for the scope, and a scope rule
+//! wins over the `default` rule. Templates may use `{type}`, `{scope}` and
+//! `{title}`; `scoped_template` is used in place of `template` when the title
+//! has a scope.
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use config::Config;
+use keep_a_changelog::ChangeKind;
+use serde::{Deserialize, Deserializer};
+
+use crate::Error;
+
+const TITLE_TEMPLATE: &str = "{title}";
+/// Entry text for a `Revert "..."` PR, as GitHub titles a revert.
+const REVERTED_TEMPLATE: &str = "Reverted: {title}";
+
+/// The section a commit lands in and the text its entry is given.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SectionRule {
+    #[serde(deserialize_with = "deserialize_change_kind")]
+    pub section: ChangeKind,
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub scoped_template: Option<String>,
+}
+
+impl SectionRule {
+    pub fn new(section: ChangeKind, template: &str) -> Self {
+        Self {
+            section,
+            template: Some(template.to_string()),
+            scoped_template: None,
+        }
+    }
+
+    pub fn with_scoped_template(mut self, template: &str) -> Self {
+        self.scoped_template = Some(template.to_string());
+        self
+    }
+
+    fn render(&self, commit_type: &str, scope: Option<&str>, title: &str) -> String {
+        let template = match (scope, &self.scoped_template) {
+            (Some(_), Some(scoped)) => scoped.as_str(),
+            _ => self.template.as_deref().unwrap_or(TITLE_TEMPLATE),
+        };
+
+        template
+            .replace("{type}", commit_type)
+            .replace("{scope}", scope.unwrap_or_default())
+            .replace("{title}", title)
+    }
+}
+
+/// The heading a section is written under in the PRLOG.
+pub(crate) fn section_heading(section: &ChangeKind) -> &'static str {
+    match section {
+        ChangeKind::Added => "Added",
+        ChangeKind::Changed => "Changed",
+        ChangeKind::Deprecated => "Deprecated",
+        ChangeKind::Fixed => "Fixed",
+        ChangeKind::Removed => "Removed",
+        ChangeKind::Security => "Security",
+    }
+}
+
+fn deserialize_change_kind<'de, D>(deserializer: D) -> Result<ChangeKind, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    ChangeKind::from_str(&value).map_err(serde::de::Error::custom)
+}
+
+/// Rules resolving a commit type and scope to a [`SectionRule`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SectionMap {
+    #[serde(default)]
+    pub types: BTreeMap<String, SectionRule>,
+    #[serde(default)]
+    pub scopes: BTreeMap<String, SectionRule>,
+    pub default: SectionRule,
+}
+
+/// The `[prlog.sections]` table as written in pcu.toml: every part optional,
+/// layered over the built-in rules.
+#[derive(Debug, Default, Deserialize)]
+struct SectionMapOverrides {
+    #[serde(default)]
+    types: BTreeMap<String, SectionRule>,
+    #[serde(default)]
+    scopes: BTreeMap<String, SectionRule>,
+    #[serde(default)]
+    default: Option<SectionRule>,
+}
+
+impl Default for SectionMap {
+    fn default() -> Self {
+        let types = BTreeMap::from([
+            (
+                "feat".to_string(),
+                SectionRule::new(ChangeKind::Added, TITLE_TEMPLATE),
+            ),
+            (
+                "fix".to_string(),
+                SectionRule::new(ChangeKind::Fixed, TITLE_TEMPLATE)
+                    .with_scoped_template("{scope}: {title}"),
+            ),
+        ]);
+
+        let scopes = BTreeMap::from([
+            (
+                "security".to_string(),
+                SectionRule::new(ChangeKind::Security, "Security: {title}"),
+            ),
+            (
+                "deps".to_string(),
+                SectionRule::new(ChangeKind::Security, "Dependencies: {title}"),
+            ),
+            (
+                "remove".to_string(),
+                SectionRule::new(ChangeKind::Removed, "Removed: {title}"),
+            ),
+            (
+                "deprecate".to_string(),
+                SectionRule::new(ChangeKind::Deprecated, "Deprecated: {title}"),
+            ),
+        ]);
+
+        let default = SectionRule::new(ChangeKind::Changed, "{type}-{title}")
+            .with_scoped_template("{type}({scope})-{title}");
+
+        Self {
+            types,
+            scopes,
+            default,
+        }
+    }
+}
+
+impl SectionMap {
+    /// Built-in rules overlaid with the `prlog.sections` table from `settings`,
+    /// if there is one.
+    pub fn from_settings(settings: &Config) -> Result<Self, Error> {
+        let mut map = Self::default();
+
+        let overrides = match settings.get::<SectionMapOverrides>("prlog.sections") {
+            Ok(overrides) => overrides,
+            Err(config::ConfigError::NotFound(_)) => return Ok(map),
+            Err(e) => return Err(Error::InvalidSectionMap(e.to_string())),
+        };
+
+        map.types.extend(overrides.types);
+        map.scopes.extend(overrides.scopes);
+        if let Some(default) = overrides.default {
+            map.default = default;
+        }
+
+        Ok(map)
+    }
+
+    /// Section and entry text for a `Revert "..."` PR whose reverted entry has
+    /// already been released. A `revert:` commit type is not a revert PR and
+    /// goes through [`SectionMap::resolve`] like any other type.
+    pub fn resolve_reverted(title: &str) -> (ChangeKind, String) {
+        (
+            ChangeKind::Removed,
+            REVERTED_TEMPLATE.replace("{title}", title),
+        )
+    }
+
+    /// Section and entry text for a PR title. Without a commit type the title
+    /// is used as-is in the Changed section.
+    pub fn resolve(
+        &self,
+        commit_type: Option<&str>,
+        scope: Option<&str>,
+        title: &str,
+    ) -> (ChangeKind, String) {
+        let Some(commit_type) = commit_type else {
+            return (ChangeKind::Changed, title.to_string());
+        };
+
+        let rule = self
+            .types
+            .get(commit_type)
+            .or_else(|| scope.and_then(|s| self.scopes.get(s)))
+            .unwrap_or(&self.default);
+        log::trace!("Rule for type `{commit_type}` and scope `{scope:?}`: {rule:?}");
+
+        (rule.section.clone(), rule.render(commit_type, scope, title))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn settings_from(toml: &str) -> Config {
+        Config::builder()
+            .set_default("prlog", "PRLOG.md")
+            .unwrap()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_no_table_gives_built_in_rules() {
+        let settings = settings_from("");
+        let map = SectionMap::from_settings(&settings).unwrap();
+        assert_eq!(map, SectionMap::default());
+    }
+
+    #[rstest]
+    #[case("perf", None, ChangeKind::Changed, "Performance: faster parse")]
+    #[case("revert", None, ChangeKind::Removed, "Reverted: faster parse")]
+    #[case("docs", Some("api"), ChangeKind::Changed, "Docs (api): faster parse")]
+    #[case(
+        "chore",
+        Some("deps"),
+        ChangeKind::Changed,
+        "Dependencies: faster parse"
+    )]
+    #[case("feat", None, ChangeKind::Added, "faster parse")]
+    #[case(
+        "chore",
+        Some("security"),
+        ChangeKind::Security,
+        "Security: faster parse"
+    )]
+    #[case("ci", None, ChangeKind::Changed, "ci-faster parse")]
+    fn test_configured_rules(
+        #[case] commit_type: &str,
+        #[case] scope: Option<&str>,
+        #[case] expected_kind: ChangeKind,
+        #[case] expected_entry: &str,
+    ) {
+        let settings = settings_from(
+            r#"
+[prlog.sections.types.perf]
+section = "Changed"
+template = "Performance: {title}"
+
+[prlog.sections.types.revert]
+section = "removed"
+template = "Reverted: {title}"
+
+[prlog.sections.types.docs]
+section = "Changed"
+template = "Docs: {title}"
+scoped_template = "Docs ({scope}): {title}"
+
+[prlog.sections.scopes.deps]
+section = "Changed"
+template = "Dependencies: {title}"
+"#,
+        );
+        let map = SectionMap::from_settings(&settings).unwrap();
+
+        let (kind, entry) = map.resolve(Some(commit_type), scope, "faster parse");
+        assert_eq!(kind, expected_kind);
+        assert_eq!(entry, expected_entry);
+    }
+
+    #[test]
+    fn test_default_rule_can_be_replaced() {
+        let settings = settings_from(
+            r#"
+[prlog.sections.default]
+section = "Changed"
+"#,
+        );
+        let map = SectionMap::from_settings(&settings).unwrap();
+
+        let (kind, entry) = map.resolve(Some("ci"), Some("config"), "tidy up");
+        assert_eq!(kind, ChangeKind::Changed);
+        assert_eq!(entry, "tidy up");
+    }
+
+    #[test]
+    fn test_unknown_section_is_an_error() {
+        let settings = settings_from(
+            r#"
+[prlog.sections.types.perf]
+section = "Faster"
+"#,
+        );
+        let result = SectionMap::from_settings(&settings);
+        assert!(matches!(result, Err(Error::InvalidSectionMap(_))));
+    }
+}
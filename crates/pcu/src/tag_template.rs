@@ -0,0 +1,202 @@
+//! Naming of release tags.
+//!
+//! Without configuration pcu names tags as it always has: `<prefix><version>`
+//! for a repository release, `<crate>-<prefix><version>` for a crate in a
+//! workspace. pcu.toml can set a template for the workspace and override it
+//! per crate:
+//!
+//! ```toml
+//! tag_template = "{crate}-v{version}"
+//!
+//! [tag_templates]
+//! pcu = "v{version}"
+//! ```
+//!
+//! A template may use `{crate}` and must end with `{version}`: tags are
+//! threaded through pcu as a prefix followed by the version, which is also how
+//! the prlog builds its compare links.
+
+use std::collections::BTreeMap;
+
+use config::Config;
+
+use crate::Error;
+
+const VERSION: &str = "{version}";
+const CRATE: &str = "{crate}";
+
+/// The tag templates configured for the workspace and its crates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagTemplates {
+    pub default: Option<String>,
+    pub crates: BTreeMap<String, String>,
+}
+
+impl TagTemplates {
+    /// The `tag_template` and `tag_templates` settings, checked for a trailing
+    /// `{version}`.
+    pub fn from_settings(settings: &Config) -> Result<Self, Error> {
+        let default = match settings.get::<String>("tag_template") {
+            Ok(template) => Some(template),
+            Err(config::ConfigError::NotFound(_)) => None,
+            Err(e) => return Err(Error::InvalidTagTemplate(e.to_string())),
+        };
+
+        let crates = match settings.get::<BTreeMap<String, String>>("tag_templates") {
+            Ok(crates) => crates,
+            Err(config::ConfigError::NotFound(_)) => BTreeMap::new(),
+            Err(e) => return Err(Error::InvalidTagTemplate(e.to_string())),
+        };
+
+        for template in default.iter().chain(crates.values()) {
+            if !template.ends_with(VERSION) {
+                return Err(Error::InvalidTagTemplate(format!(
+                    "`{template}` must end with {VERSION}"
+                )));
+            }
+        }
+
+        Ok(Self { default, crates })
+    }
+
+    /// The template for `crate_name`, falling back to the workspace template.
+    pub fn template(&self, crate_name: Option<&str>) -> Option<&str> {
+        crate_name
+            .and_then(|name| self.crates.get(name))
+            .or(self.default.as_ref())
+            .map(String::as_str)
+    }
+
+    /// The part of the tag before the version. `legacy` is the prefix used
+    /// when no template is configured.
+    pub fn prefix(&self, crate_name: Option<&str>, legacy: &str) -> Result<String, Error> {
+        let Some(template) = self.template(crate_name) else {
+            return Ok(legacy.to_string());
+        };
+
+        let prefix = template.strip_suffix(VERSION).ok_or_else(|| {
+            Error::InvalidTagTemplate(format!("`{template}` must end with {VERSION}"))
+        })?;
+
+        match crate_name {
+            Some(name) => Ok(prefix.replace(CRATE, name)),
+            None if prefix.contains(CRATE) => Err(Error::InvalidTagTemplate(format!(
+                "`{template}` names the crate, but no crate is being released"
+            ))),
+            None => Ok(prefix.to_string()),
+        }
+    }
+
+    /// The tag for `version` of `crate_name`.
+    pub fn tag(
+        &self,
+        crate_name: Option<&str>,
+        legacy: &str,
+        version: &str,
+    ) -> Result<String, Error> {
+        Ok(format!("{}{version}", self.prefix(crate_name, legacy)?))
+    }
+
+    /// The tag for the `--semver` of `pcu commit` and `pcu push`, which tag
+    /// without naming a crate; `None` when no version is given.
+    pub fn version_tag(
+        &self,
+        legacy: &str,
+        version: Option<&str>,
+    ) -> Result<Option<String>, Error> {
+        version
+            .map(|version| self.tag(None, legacy, version))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn settings_from(toml: &str) -> Config {
+        Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_no_template_keeps_the_legacy_prefix() {
+        let templates = TagTemplates::from_settings(&settings_from("")).unwrap();
+        assert_eq!(templates, TagTemplates::default());
+        assert_eq!(
+            templates.tag(Some("pcu"), "pcu-v", "1.2.0").unwrap(),
+            "pcu-v1.2.0"
+        );
+    }
+
+    #[rstest]
+    #[case::workspace_template(Some("gen-changelog"), "gen-changelog-v1.2.0")]
+    #[case::crate_override(Some("pcu"), "v1.2.0")]
+    fn test_configured_templates(#[case] crate_name: Option<&str>, #[case] expected: &str) {
+        let templates = TagTemplates::from_settings(&settings_from(
+            r#"
+tag_template = "{crate}-v{version}"
+
+[tag_templates]
+pcu = "v{version}"
+"#,
+        ))
+        .unwrap();
+
+        assert_eq!(
+            templates.tag(crate_name, "unused-", "1.2.0").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_crate_template_needs_a_crate() {
+        let templates =
+            TagTemplates::from_settings(&settings_from(r#"tag_template = "{crate}@{version}""#))
+                .unwrap();
+
+        assert!(matches!(
+            templates.prefix(None, "v"),
+            Err(Error::InvalidTagTemplate(_))
+        ));
+        assert_eq!(templates.prefix(Some("pcu"), "v").unwrap(), "pcu@");
+    }
+
+    #[test]
+    fn test_version_tag_without_a_crate() {
+        let templates =
+            TagTemplates::from_settings(&settings_from(r#"tag_template = "release-{version}""#))
+                .unwrap();
+        assert_eq!(
+            templates.version_tag("v", Some("1.2.0")).unwrap(),
+            Some("release-1.2.0".to_string())
+        );
+        assert_eq!(templates.version_tag("v", None).unwrap(), None);
+        assert_eq!(
+            TagTemplates::default()
+                .version_tag("v", Some("1.2.0"))
+                .unwrap(),
+            Some("v1.2.0".to_string())
+        );
+
+        // A template naming the crate cannot tag a commit made without one.
+        let templates =
+            TagTemplates::from_settings(&settings_from(r#"tag_template = "{crate}-v{version}""#))
+                .unwrap();
+        assert!(matches!(
+            templates.version_tag("v", Some("1.2.0")),
+            Err(Error::InvalidTagTemplate(_))
+        ));
+    }
+
+    #[test]
+    fn test_template_must_end_with_the_version() {
+        let result =
+            TagTemplates::from_settings(&settings_from(r#"tag_template = "v{version}-final""#));
+        assert!(matches!(result, Err(Error::InvalidTagTemplate(_))));
+    }
+}
@@ -0,0 +1,71 @@
+use keep_a_changelog::{changelog::ChangelogBuilder, Changelog};
+
+use crate::Error;
+
+pub trait ChangelogLinks {
+    fn has_link(&self, anchor: &str) -> bool;
+    fn remove_link(&mut self, anchor: &str) -> Result<(), Error>;
+}
+
+impl ChangelogLinks for Changelog {
+    /// True if a `[anchor]: url` link definition exists
+    fn has_link(&self, anchor: &str) -> bool {
+        self.links().iter().any(|link| link.anchor() == anchor)
+    }
+
+    /// Drop the `[anchor]: url` link definition. `keep_a_changelog` offers no
+    /// way to remove a link, so the changelog is rebuilt without it.
+    fn remove_link(&mut self, anchor: &str) -> Result<(), Error> {
+        if !self.has_link(anchor) {
+            return Ok(());
+        }
+
+        let links = self
+            .links()
+            .iter()
+            .filter(|link| link.anchor() != anchor)
+            .map(|link| link.to_string())
+            .collect::<Vec<String>>();
+
+        let mut builder = ChangelogBuilder::default();
+        builder
+            .compact(*self.compact())
+            .lint(self.lint().clone())
+            .flag(self.flag().clone())
+            .title(self.title().clone())
+            .description(self.description().clone())
+            .head(self.head().clone())
+            .footer(self.footer().clone())
+            .url(self.url().clone())
+            .tag_prefix(self.tag_prefix().clone())
+            .releases(self.releases().clone());
+        builder
+            .links(links)
+            .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+
+        *self = builder
+            .build()
+            .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_link() {
+        let markdown = "# Changelog\n\n## [Unreleased]\n\n### Added\n\n- first(pr [#1])\n\n[#1]: https://github.com/jerus-org/pcu/pull/1\n[#2]: https://github.com/jerus-org/pcu/pull/2\n";
+        let mut prlog = Changelog::parse(markdown.to_string(), None).unwrap();
+        assert!(prlog.has_link("#2"));
+
+        prlog.remove_link("#2").unwrap();
+
+        assert!(prlog.has_link("#1"));
+        assert!(!prlog.has_link("#2"));
+        assert!(!prlog.to_string().contains("[#2]:"));
+        assert!(prlog.to_string().contains("- first(pr [#1])"));
+    }
+}
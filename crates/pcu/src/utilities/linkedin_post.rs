@@ -3,16 +3,15 @@ use keep_a_changelog::Changelog;
 use url::Url;
 
 use super::ReleaseNotesProvider;
-use crate::Error;
+use crate::{client::prlog_file, Error};
 
 pub fn build_release_text(settings: &Config, prefix: &str, version: &str) -> Result<String, Error> {
-    let prlog = settings
-        .get::<String>("prlog")
-        .unwrap_or_else(|_| "PRLOG.md".to_string());
+    let prlog = prlog_file(settings)?;
 
     let pr = Changelog::parse_from_file(&prlog, None)
         .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
-    let rn = pr.release_notes(prefix, version)?;
+    let template = settings.get::<String>("release_notes_template").ok();
+    let rn = pr.release_notes_with(prefix, version, template.as_deref())?;
 
     let mut body = format!("{} released\n\n", rn.name);
     let mut desc = rn.body.trim().to_string();
@@ -1,7 +1,11 @@
+mod changelog_links;
 #[cfg(feature = "linkedin")]
 pub mod linkedin_post;
+mod release_entries;
 mod release_notes_provider;
 mod release_unreleased;
 
+pub use changelog_links::ChangelogLinks;
+pub use release_entries::ReleaseEntries;
 pub use release_notes_provider::ReleaseNotesProvider;
 pub use release_unreleased::ReleaseUnreleased;
@@ -0,0 +1,82 @@
+use std::str::FromStr;
+
+use keep_a_changelog::{ChangeKind, Changes, Release};
+
+/// Read and rewrite the individual entries of a release.
+///
+/// `keep_a_changelog` keeps the entries of a release private, so they are read
+/// back from its own rendering of the release's changes.
+pub trait ReleaseEntries {
+    /// The entries of the release, by section, in file order. Continuation
+    /// lines of multi-line entries are joined with `\n`.
+    fn entries(&self) -> Vec<(ChangeKind, String)>;
+    /// Replace every entry of the release with `entries`.
+    fn set_entries(&mut self, entries: Vec<(ChangeKind, String)>);
+}
+
+impl ReleaseEntries for Release {
+    fn entries(&self) -> Vec<(ChangeKind, String)> {
+        let mut entries: Vec<(ChangeKind, String)> = Vec::new();
+        let mut kind = None;
+
+        for line in self.changes().to_string().lines() {
+            if let Some(heading) = line.strip_prefix("### ") {
+                kind = ChangeKind::from_str(heading.trim()).ok();
+                continue;
+            }
+            let Some(kind) = &kind else {
+                continue;
+            };
+
+            if let Some(item) = line.strip_prefix("- ") {
+                entries.push((kind.clone(), item.to_string()));
+            } else if let Some(continuation) = line.strip_prefix("  ") {
+                if let Some((_, entry)) = entries.last_mut() {
+                    entry.push('\n');
+                    entry.push_str(continuation);
+                }
+            }
+        }
+
+        entries
+    }
+
+    fn set_entries(&mut self, entries: Vec<(ChangeKind, String)>) {
+        let mut changes = Changes::default();
+        for (kind, entry) in entries {
+            changes.add(kind, entry);
+        }
+        self.set_changes(changes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use keep_a_changelog::Changelog;
+
+    use super::*;
+
+    #[test]
+    fn test_entries_round_trip() {
+        let markdown = "# Changelog\n\n## [Unreleased]\n\n### Added\n\n- first(pr [#1])\n- curated note\n  - second bullet(pr [#2])\n\n### Fixed\n\n- a fix(pr [#3])\n";
+        let mut prlog = Changelog::parse(markdown.to_string(), None).unwrap();
+        let unreleased = prlog.get_unreleased_mut().unwrap();
+
+        let entries = unreleased.entries();
+        assert_eq!(
+            entries,
+            vec![
+                (ChangeKind::Added, "first(pr [#1])".to_string()),
+                (
+                    ChangeKind::Added,
+                    "curated note\n- second bullet(pr [#2])".to_string()
+                ),
+                (ChangeKind::Fixed, "a fix(pr [#3])".to_string()),
+            ]
+        );
+
+        let before = unreleased.changes().to_string();
+        unreleased.set_entries(entries);
+        assert_eq!(unreleased.changes().to_string(), before);
+    }
+}
@@ -1,5 +1,11 @@
-use keep_a_changelog::{Changelog, Version};
+use std::fs;
 
+use keep_a_changelog::{ChangeKind, Changelog, Release, Version};
+use minijinja::Environment;
+use regex::Regex;
+use serde::Serialize;
+
+use super::ReleaseEntries;
 use crate::Error;
 
 #[derive(Debug, Clone)]
@@ -10,19 +16,40 @@ pub struct ReleaseNotes {
 
 pub trait ReleaseNotesProvider {
     fn release_notes(&self, prefix: &str, release: &str) -> Result<ReleaseNotes, Error>;
+    /// Release notes with the body rendered from a
+    /// [minijinja](https://docs.rs/minijinja) `template`, such as the one named
+    /// by the `release_notes_template` setting.
+    fn render_release_notes(
+        &self,
+        prefix: &str,
+        release: &str,
+        template: &str,
+    ) -> Result<ReleaseNotes, Error>;
+
+    /// Release notes rendered from the template file at `template`, or the
+    /// default release notes without one.
+    fn release_notes_with(
+        &self,
+        prefix: &str,
+        release: &str,
+        template: Option<&str>,
+    ) -> Result<ReleaseNotes, Error> {
+        match template {
+            Some(path) => {
+                log::debug!("Rendering release notes from template {path}");
+                let template = fs::read_to_string(path)
+                    .map_err(|e| Error::ReleaseNotesTemplate(format!("cannot read {path}: {e}")))?;
+                self.render_release_notes(prefix, release, &template)
+            }
+            None => self.release_notes(prefix, release),
+        }
+    }
 }
 
 impl ReleaseNotesProvider for Changelog {
     fn release_notes(&self, prefix: &str, release: &str) -> Result<ReleaseNotes, Error> {
         let name = format!("{prefix}{release}");
-
-        let version = match Version::parse(release) {
-            Ok(version) => version,
-            Err(e) => {
-                log::error!("Error parsing version: {e}");
-                return Err(Error::InvalidVersion(release.to_string()));
-            }
-        };
+        let version = parse_version(release)?;
 
         let mut body = String::from("## What's Changed\n\n");
 
@@ -62,6 +89,371 @@ impl ReleaseNotesProvider for Changelog {
                 .unwrap_or("".to_string()),
         );
 
+        body.push_str(&contributors_section(&credits(self, &version)?));
+
         Ok(ReleaseNotes { name, body })
     }
+
+    fn render_release_notes(
+        &self,
+        prefix: &str,
+        release: &str,
+        template: &str,
+    ) -> Result<ReleaseNotes, Error> {
+        let name = format!("{prefix}{release}");
+        let version = parse_version(release)?;
+        let context = ReleaseNotesContext::new(self, prefix, &version)?;
+        log::trace!("Release notes context: {context:#?}");
+
+        let mut env = Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.set_keep_trailing_newline(true);
+        let body = env
+            .render_str(template, &context)
+            .map_err(|e| Error::ReleaseNotesTemplate(e.to_string()))?;
+
+        Ok(ReleaseNotes { name, body })
+    }
+}
+
+fn parse_version(release: &str) -> Result<Version, Error> {
+    Version::parse(release).map_err(|e| {
+        log::error!("Error parsing version: {e}");
+        Error::InvalidVersion(release.to_string())
+    })
+}
+
+/// The variables available to a release notes template.
+///
+/// Each change section is a list of entries, empty when the release has none,
+/// so a template can skip it with `{% if fixed %}`.
+#[derive(Debug, Default, Serialize)]
+struct ReleaseNotesContext {
+    version: String,
+    tag: String,
+    previous_tag: Option<String>,
+    compare_url: Option<String>,
+    date: Option<String>,
+    description: String,
+    /// All changes rendered as in the PRLOG, `### Section` headings included
+    changes: String,
+    added: Vec<String>,
+    changed: Vec<String>,
+    deprecated: Vec<String>,
+    removed: Vec<String>,
+    fixed: Vec<String>,
+    security: Vec<String>,
+    contributors: Vec<String>,
+    new_contributors: Vec<NewContributor>,
+}
+
+#[derive(Debug, Serialize)]
+struct NewContributor {
+    login: String,
+    pr: String,
+}
+
+impl ReleaseNotesContext {
+    fn new(prlog: &Changelog, prefix: &str, version: &Version) -> Result<Self, Error> {
+        let tag = format!("{prefix}{version}");
+        let mut context = Self {
+            version: version.to_string(),
+            tag: tag.clone(),
+            ..Default::default()
+        };
+
+        let previous = prlog
+            .releases()
+            .iter()
+            .filter_map(|r| r.version().as_ref())
+            .filter(|v| *v < version)
+            .max();
+        context.previous_tag = previous.map(|v| format!("{prefix}{v}"));
+        context.compare_url = prlog.url().as_ref().map(|url| match &context.previous_tag {
+            Some(previous_tag) => format!("{url}/compare/{previous_tag}...{tag}"),
+            None => format!("{url}/releases/tag/{tag}"),
+        });
+
+        if let Some(release) = find_release(prlog, version) {
+            context.date = release.date().map(|date| date.to_string());
+            context.description = release.description().clone().unwrap_or_default();
+            context.changes = release.changes().to_string();
+
+            for (kind, entry) in release.entries() {
+                let entry = entry.replace('\n', "\n  ");
+                match kind {
+                    ChangeKind::Added => context.added.push(entry),
+                    ChangeKind::Changed => context.changed.push(entry),
+                    ChangeKind::Deprecated => context.deprecated.push(entry),
+                    ChangeKind::Removed => context.removed.push(entry),
+                    ChangeKind::Fixed => context.fixed.push(entry),
+                    ChangeKind::Security => context.security.push(entry),
+                }
+            }
+        }
+
+        let credits = credits(prlog, version)?;
+        context.contributors = credits.logins;
+        context.new_contributors = credits
+            .new_contributors
+            .into_iter()
+            .map(|(login, pr)| NewContributor { login, pr })
+            .collect();
+
+        Ok(context)
+    }
+}
+
+fn find_release<'a>(prlog: &'a Changelog, version: &Version) -> Option<&'a Release> {
+    prlog
+        .releases()
+        .iter()
+        .find(|r| r.version().as_ref() == Some(version))
+}
+
+/// The authors credited with `by @login` on the entries of a release.
+#[derive(Debug, Default)]
+struct Credits {
+    /// Each credited login once, in entry order
+    logins: Vec<String>,
+    /// Login and PR number for those credited on no entry of an earlier
+    /// release. Left empty when earlier releases exist but none carries a
+    /// credit, as their authors are unknown.
+    new_contributors: Vec<(String, String)>,
+}
+
+fn credits(prlog: &Changelog, version: &Version) -> Result<Credits, Error> {
+    let credit_re = Regex::new(r"\(pr \[?#(?P<id>\d+)\]?.*\) by @(?P<login>[\w-]+(?:\[bot\])?)")?;
+    let entry_credits = |entries: Vec<(ChangeKind, String)>| {
+        entries
+            .iter()
+            .filter_map(|(_, entry)| {
+                let captures = credit_re.captures(entry)?;
+                Some((captures["login"].to_string(), captures["id"].to_string()))
+            })
+            .collect::<Vec<(String, String)>>()
+    };
+
+    let mut release_credits = Vec::new();
+    let mut earlier_releases = false;
+    let mut earlier_logins = Vec::new();
+    for release in prlog.releases() {
+        match release.version() {
+            Some(v) if v == version => release_credits = entry_credits(release.entries()),
+            Some(v) if v < version => {
+                earlier_releases = true;
+                earlier_logins.extend(
+                    entry_credits(release.entries())
+                        .into_iter()
+                        .map(|(login, _)| login),
+                );
+            }
+            _ => {}
+        }
+    }
+    // Credits switched on part way through the history say nothing about who
+    // contributed before, so nobody can be called new.
+    let history_credited = !earlier_releases || !earlier_logins.is_empty();
+
+    let mut credits = Credits::default();
+    for (login, id) in release_credits {
+        if credits.logins.contains(&login) {
+            continue;
+        }
+        credits.logins.push(login.clone());
+        if history_credited && !earlier_logins.contains(&login) {
+            credits.new_contributors.push((login, id));
+        }
+    }
+
+    Ok(credits)
+}
+
+/// The `## Contributors` and `## New contributors` sections of the default
+/// release notes. Empty when no entry carries a credit.
+fn contributors_section(credits: &Credits) -> String {
+    if credits.logins.is_empty() {
+        return String::new();
+    }
+
+    let mut section = String::from("\n## Contributors\n\n");
+    for login in &credits.logins {
+        section.push_str(&format!("- @{login}\n"));
+    }
+
+    if !credits.new_contributors.is_empty() {
+        section.push_str("\n## New contributors\n\n");
+        for (login, id) in &credits.new_contributors {
+            section.push_str(&format!(
+                "- @{login} made their first contribution in #{id}\n"
+            ));
+        }
+    }
+
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CREDITED_PRLOG: &str = "# Changelog
+
+## [Unreleased]
+
+## [0.2.0] - 2024-02-01
+
+### Added
+
+- new flag(pr [#5]) by @alice
+- other flag(pr [#6]) by @carol
+
+### Fixed
+
+- crash on start(pr [#7], closes [#3]) by @carol
+- old fix(pr [#8])
+
+## [0.1.0] - 2024-01-01
+
+### Added
+
+- first(pr [#1]) by @alice
+
+[#1]: https://github.com/jerus-org/pcu/pull/1
+";
+
+    #[test]
+    fn test_release_notes_credit_contributors() {
+        let opts = keep_a_changelog::ChangelogParseOptions {
+            url: Some("https://github.com/jerus-org/pcu".to_string()),
+            head: Some("HEAD".to_string()),
+            tag_prefix: Some("v".to_string()),
+        };
+        let prlog = Changelog::parse(CREDITED_PRLOG.to_string(), Some(opts)).unwrap();
+
+        let notes = prlog.release_notes("v", "0.2.0").unwrap();
+        assert!(notes.body.ends_with(
+            "\n## Contributors\n\n- @alice\n- @carol\n\n## New contributors\n\n- @carol made their first contribution in #6\n"
+        ));
+
+        let notes = prlog.release_notes("v", "0.1.0").unwrap();
+        assert!(notes
+            .body
+            .ends_with("\n## Contributors\n\n- @alice\n\n## New contributors\n\n- @alice made their first contribution in #1\n"));
+    }
+
+    #[test]
+    fn test_render_release_notes_from_template() {
+        let opts = keep_a_changelog::ChangelogParseOptions {
+            url: Some("https://github.com/jerus-org/pcu".to_string()),
+            head: Some("HEAD".to_string()),
+            tag_prefix: Some("v".to_string()),
+        };
+        let prlog = Changelog::parse(CREDITED_PRLOG.to_string(), Some(opts)).unwrap();
+        let template = "\
+# {{ tag }} ({{ date }})
+
+{% if added %}
+## New
+{% for entry in added %}
+- {{ entry }}
+{% endfor %}
+{% endif %}
+{% if security %}
+## Security
+{% endif %}
+Thanks to {% for login in contributors %}@{{ login }}{% if not loop.last %}, {% endif %}{% endfor %}.
+Full changelog: {{ compare_url }}
+";
+
+        let notes = prlog.render_release_notes("v", "0.2.0", template).unwrap();
+        assert_eq!(notes.name, "v0.2.0");
+        assert_eq!(
+            notes.body,
+            "\
+# v0.2.0 (2024-02-01)
+
+## New
+- new flag(pr [#5]) by @alice
+- other flag(pr [#6]) by @carol
+Thanks to @alice, @carol.
+Full changelog: https://github.com/jerus-org/pcu/compare/v0.1.0...v0.2.0
+"
+        );
+
+        let notes = prlog
+            .render_release_notes(
+                "v",
+                "0.1.0",
+                "{{ previous_tag or 'first' }} {{ compare_url }}",
+            )
+            .unwrap();
+        assert_eq!(
+            notes.body,
+            "first https://github.com/jerus-org/pcu/releases/tag/v0.1.0"
+        );
+    }
+
+    #[test]
+    fn test_render_release_notes_reports_template_errors() {
+        let prlog = Changelog::parse(CREDITED_PRLOG.to_string(), None).unwrap();
+        let result = prlog.render_release_notes("v", "0.2.0", "{% if added %}");
+        assert!(matches!(result, Err(Error::ReleaseNotesTemplate(_))));
+    }
+
+    #[test]
+    fn test_release_notes_after_uncredited_releases_skip_new_contributors() {
+        let markdown = "# Changelog
+
+## [0.3.0] - 2024-03-01
+
+### Added
+
+- later flag(pr [#9]) by @carol
+- extra flag(pr [#10]) by @dave
+
+## [0.2.0] - 2024-02-01
+
+### Added
+
+- new flag(pr [#5]) by @alice
+- other flag(pr [#6]) by @carol
+
+## [0.1.0] - 2024-01-01
+
+### Added
+
+- first(pr [#1])
+";
+        let opts = keep_a_changelog::ChangelogParseOptions {
+            url: Some("https://github.com/jerus-org/pcu".to_string()),
+            ..Default::default()
+        };
+        let prlog = Changelog::parse(markdown.to_string(), Some(opts)).unwrap();
+
+        let notes = prlog.release_notes("v", "0.2.0").unwrap();
+        assert!(notes
+            .body
+            .ends_with("\n## Contributors\n\n- @alice\n- @carol\n"));
+
+        // Once a release carries credits, newcomers after it are recognised
+        let notes = prlog.release_notes("v", "0.3.0").unwrap();
+        assert!(notes
+            .body
+            .ends_with("\n## New contributors\n\n- @dave made their first contribution in #10\n"));
+    }
+
+    #[test]
+    fn test_release_notes_without_credits_have_no_contributors() {
+        let markdown = "# Changelog\n\n## [0.1.0] - 2024-01-01\n\n### Added\n\n- first(pr #1)\n";
+        let opts = keep_a_changelog::ChangelogParseOptions {
+            url: Some("https://github.com/jerus-org/pcu".to_string()),
+            ..Default::default()
+        };
+        let prlog = Changelog::parse(markdown.to_string(), Some(opts)).unwrap();
+
+        let notes = prlog.release_notes("v", "0.1.0").unwrap();
+        assert!(!notes.body.contains("Contributors"));
+    }
 }
@@ -1,13 +1,64 @@
 use chrono::{Datelike, NaiveDate, Utc};
-use keep_a_changelog::{Changelog, Version};
+use keep_a_changelog::{Changelog, Release, Version};
 
-use crate::Error;
+use crate::{
+    utilities::{ChangelogLinks, ReleaseEntries},
+    Error,
+};
 
 pub trait ReleaseUnreleased {
     fn release_unreleased(&mut self, version: &str) -> Result<(), Error>;
+    fn collect_prereleases(&mut self, version: &str) -> Result<(), Error>;
 }
 
 impl ReleaseUnreleased for Changelog {
+    /// Fold the sections of the prereleases of `version` (`1.2.0-rc.1`,
+    /// `1.2.0-rc.2`, ...) into the Unreleased section, ready for the full
+    /// release, and drop their compare links. Nothing changes when `version` is
+    /// itself a prerelease.
+    fn collect_prereleases(&mut self, version: &str) -> Result<(), Error> {
+        let version = Version::parse(version).map_err(|e| Error::InvalidVersion(e.to_string()))?;
+        if !version.pre.is_empty() {
+            return Ok(());
+        }
+
+        let is_prerelease_of = |release: &Release| {
+            release.version().as_ref().is_some_and(|v| {
+                !v.pre.is_empty()
+                    && (v.major, v.minor, v.patch) == (version.major, version.minor, version.patch)
+            })
+        };
+
+        let (prereleases, releases): (Vec<Release>, Vec<Release>) = self
+            .releases_mut()
+            .drain(..)
+            .partition(|release| is_prerelease_of(release));
+        *self.releases_mut() = releases;
+
+        if prereleases.is_empty() {
+            return Ok(());
+        }
+
+        let Some(unreleased) = self.get_unreleased_mut() else {
+            return Err(Error::NoUnreleasedSection);
+        };
+
+        let mut entries = unreleased.entries();
+        for prerelease in &prereleases {
+            entries.extend(prerelease.entries());
+        }
+        unreleased.set_entries(entries);
+
+        for prerelease in &prereleases {
+            if let Some(prerelease_version) = prerelease.version() {
+                log::info!("Collecting {prerelease_version} into {version}");
+                self.remove_link(&prerelease_version.to_string())?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Release unreleased section with the version number specified and add
     /// a compare link for the new release to the PRLog
     fn release_unreleased(&mut self, version: &str) -> Result<(), Error> {
@@ -18,8 +69,11 @@ impl ReleaseUnreleased for Changelog {
         log::debug!("anchor: {anchor:?}");
         let repo_url = self.url().clone().unwrap();
 
+        // Compare links run between tags, named by the tag prefix the prlog was
+        // parsed with.
+        let prefix = self.tag_prefix().clone().unwrap_or_default();
         let url = format!(
-            "{}/compare/{}..{}",
+            "{}/compare/{prefix}{}...{prefix}{}",
             repo_url,
             self.releases()
                 .get(1)
@@ -150,4 +204,60 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_collect_prereleases_into_the_full_release() -> Result<(), Error> {
+        let markdown = "# Changelog\n\n## [Unreleased]\n\n### Fixed\n\n- late fix(pr [#4])\n\n## [1.2.0-rc.2] - 2024-02-10\n\n### Fixed\n\n- rc fix(pr [#3])\n\n## [1.2.0-rc.1] - 2024-02-01\n\n### Added\n\n- new flag(pr [#2])\n\n## [1.1.0] - 2024-01-10\n\n### Added\n\n- first(pr [#1])\n\n[Unreleased]: https://github.com/jerus-org/pcu/compare/v1.2.0-rc.2...HEAD\n[1.2.0-rc.2]: https://github.com/jerus-org/pcu/compare/v1.2.0-rc.1...v1.2.0-rc.2\n[1.2.0-rc.1]: https://github.com/jerus-org/pcu/compare/v1.1.0...v1.2.0-rc.1\n[1.1.0]: https://github.com/jerus-org/pcu/releases/tag/v1.1.0\n";
+        let opts = ChangelogParseOptions {
+            url: Some("https://github.com/jerus-org/pcu".to_string()),
+            head: Some("main".to_string()),
+            tag_prefix: Some("v".to_string()),
+        };
+        let mut prlog = Changelog::parse(markdown.to_string(), Some(opts))
+            .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+
+        prlog.collect_prereleases("1.2.0")?;
+        prlog.release_unreleased("1.2.0")?;
+
+        let versions = prlog
+            .releases()
+            .iter()
+            .filter_map(|release| release.version().as_ref().map(|v| v.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec!["1.2.0", "1.1.0"]);
+
+        let release = prlog.find_release("1.2.0".to_string()).unwrap().unwrap();
+        let entries = release
+            .entries()
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec!["new flag(pr [#2])", "late fix(pr [#4])", "rc fix(pr [#3])"]
+        );
+
+        assert!(!prlog.has_link("1.2.0-rc.1"));
+        assert!(!prlog.has_link("1.2.0-rc.2"));
+        assert!(prlog
+            .to_string()
+            .contains("[1.2.0]: https://github.com/jerus-org/pcu/compare/v1.1.0...v1.2.0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prerelease_does_not_collect_prereleases() -> Result<(), Error> {
+        let markdown = "# Changelog\n\n## [Unreleased]\n\n### Fixed\n\n- rc fix(pr [#3])\n\n## [1.2.0-rc.1] - 2024-02-01\n\n### Added\n\n- new flag(pr [#2])\n";
+        let mut prlog = Changelog::parse(markdown.to_string(), None)
+            .map_err(|e| Error::KeepAChangelog(e.to_string()))?;
+
+        prlog.collect_prereleases("1.2.0-rc.2")?;
+
+        assert!(prlog
+            .find_release("1.2.0-rc.1".to_string())
+            .unwrap()
+            .is_some());
+        Ok(())
+    }
 }